@@ -0,0 +1,618 @@
+use anyhow::{Context, Result, bail};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use lr_formatter_rs::formats::{self, Format, ReadOptions, sol, sol::raw::Amf0Value};
+use lr_formatter_rs::track::{Track, Vec2};
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+pub struct BatchOptions {
+    pub input_dir: PathBuf,
+    pub output_dir: Option<PathBuf>,
+    pub output_format: Format,
+    pub recursive: bool,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub jobs: Option<usize>,
+}
+
+struct Conversion {
+    /// Input path relative to the input directory, with the SOL track index if there are several
+    source: String,
+    result: Result<Converted>,
+}
+
+/// A file to convert, along with where each of its tracks goes
+struct Job {
+    relative_path: PathBuf,
+    format: Format,
+    /// Track list of a SOL file, parsed while planning since it holds the number of tracks
+    track_list: Option<Result<Amf0Value>>,
+    tracks: Vec<PlannedTrack>,
+}
+
+struct PlannedTrack {
+    source: String,
+    /// Index of the track in a SOL file
+    track_index: Option<u32>,
+    output: PathBuf,
+    /// Sources of the other tracks that would be written to the same output
+    collides_with: Vec<String>,
+}
+
+struct Converted {
+    output: PathBuf,
+    /// Properties of the track that the output format could not represent
+    lost: Vec<&'static str>,
+}
+
+pub fn run(options: BatchOptions) -> Result<()> {
+    // Resolved so that paths like `.` have a name to derive the output directory from
+    let input_dir = options.input_dir.canonicalize().with_context(|| {
+        format!(
+            "Failed to resolve input directory '{}'",
+            options.input_dir.display()
+        )
+    })?;
+
+    let output_dir = match &options.output_dir {
+        Some(dir) => dir.clone(),
+        None => default_output_dir(&input_dir)?,
+    };
+
+    let include = build_glob_set(&options.include).context("Invalid include pattern")?;
+    let exclude = build_glob_set(&options.exclude).context("Invalid exclude pattern")?;
+    let files = collect_files(
+        &input_dir,
+        options.recursive,
+        &output_dir,
+        &include,
+        &exclude,
+    )?;
+
+    if files.is_empty() {
+        println!("No track files found in {}", options.input_dir.display());
+        return Ok(());
+    }
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(options.jobs.unwrap_or(0))
+        .build()
+        .context("Failed to start worker threads")?;
+
+    let mut jobs: Vec<Job> = pool.install(|| {
+        files
+            .par_iter()
+            .map(|relative_path| {
+                let track_list = (Format::from_path(relative_path) == Some(Format::Sol))
+                    .then(|| read_track_list(&input_dir.join(relative_path)));
+                plan_file(
+                    &output_dir,
+                    relative_path,
+                    options.output_format,
+                    track_list,
+                )
+            })
+            .collect()
+    });
+    find_collisions(&mut jobs);
+    let collisions = jobs
+        .iter()
+        .flat_map(|job| &job.tracks)
+        .filter(|track| !track.collides_with.is_empty())
+        .count();
+
+    let conversions: Vec<Conversion> = pool.install(|| {
+        jobs.into_par_iter()
+            .flat_map_iter(|job| convert_file(&input_dir, job, options.output_format))
+            .collect()
+    });
+
+    print_summary(&conversions);
+    if collisions > 0 {
+        println!(
+            "{} tracks were not converted because other tracks have the same output name",
+            collisions
+        );
+    }
+
+    let failed = conversions.iter().filter(|c| c.result.is_err()).count();
+    if failed > 0 {
+        bail!("{} of {} conversions failed", failed, conversions.len());
+    }
+
+    Ok(())
+}
+
+/// Sibling of the input directory, named after it
+fn default_output_dir(input_dir: &Path) -> Result<PathBuf> {
+    let dir_name = input_dir
+        .file_name()
+        .and_then(|name| name.to_str())
+        .context("Failed to parse input directory name")?;
+    Ok(input_dir.with_file_name(format!("{} (Converted)", dir_name)))
+}
+
+fn build_glob_set(patterns: &[String]) -> Result<Option<GlobSet>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern).with_context(|| format!("'{}'", pattern))?);
+    }
+
+    Ok(Some(builder.build()?))
+}
+
+/// Lists convertible files under the input directory, relative to it
+fn collect_files(
+    input_dir: &Path,
+    recursive: bool,
+    output_dir: &Path,
+    include: &Option<GlobSet>,
+    exclude: &Option<GlobSet>,
+) -> Result<Vec<PathBuf>> {
+    let max_depth = if recursive { usize::MAX } else { 1 };
+    let mut files = vec![];
+
+    // Directories are walked from the resolved input directory, so they only need comparing
+    // to the resolved output directory, which does not exist yet on the first run
+    let output_dir = output_dir.canonicalize().ok();
+    let walker = WalkDir::new(input_dir)
+        .max_depth(max_depth)
+        .sort_by_file_name()
+        .into_iter()
+        // Avoid converting our own output when it is nested in the input directory
+        .filter_entry(|entry| Some(entry.path()) != output_dir.as_deref());
+
+    for entry in walker {
+        let entry = entry.context("Failed to read input directory")?;
+
+        if !entry.file_type().is_file() || Format::from_path(entry.path()).is_none() {
+            continue;
+        }
+
+        let relative_path = entry
+            .path()
+            .strip_prefix(input_dir)
+            .context("Failed to resolve relative path")?
+            .to_path_buf();
+
+        if include
+            .as_ref()
+            .is_some_and(|globs| !globs.is_match(&relative_path))
+        {
+            continue;
+        }
+
+        if exclude
+            .as_ref()
+            .is_some_and(|globs| globs.is_match(&relative_path))
+        {
+            continue;
+        }
+
+        files.push(relative_path);
+    }
+
+    Ok(files)
+}
+
+/// File name without its track format extension (including the `.track` of `.track.json`)
+fn track_name(path: &Path) -> String {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let lowercase = file_name.to_lowercase();
+
+    for suffix in [".track.json", ".json", ".trk", ".sol"] {
+        if lowercase.ends_with(suffix) {
+            return file_name[..file_name.len() - suffix.len()].to_string();
+        }
+    }
+
+    file_name
+}
+
+/// Work out where each track of a file is written to
+fn plan_file(
+    output_dir: &Path,
+    relative_path: &Path,
+    output_format: Format,
+    track_list: Option<Result<Amf0Value>>,
+) -> Job {
+    let Some(format) = Format::from_path(relative_path) else {
+        unreachable!("BUG: Collected files should have a known format");
+    };
+
+    let source = relative_path.display().to_string();
+    let name = track_name(relative_path);
+    let output_parent = output_dir.join(relative_path.parent().unwrap_or(Path::new("")));
+    let output = |name: &str| output_parent.join(format!("{}.{}", name, output_format.extension()));
+
+    let tracks = match format {
+        Format::Sol => {
+            // Files that fail to read are planned as a single track to report the error once
+            let track_count = match &track_list {
+                Some(Ok(track_list)) => track_list
+                    .as_object_properties()
+                    .map_or(0, |tracks| u32::try_from(tracks.len()).unwrap_or(u32::MAX)),
+                _ => 0,
+            };
+            let track_count = track_count.max(1);
+
+            (0..track_count)
+                .map(|index| {
+                    let (source, output_name) = if track_count > 1 {
+                        (
+                            format!("{} [track {}]", source, index),
+                            format!("{} ({})", name, index),
+                        )
+                    } else {
+                        (source.clone(), name.clone())
+                    };
+
+                    PlannedTrack {
+                        source,
+                        track_index: Some(index),
+                        output: output(&output_name),
+                        collides_with: vec![],
+                    }
+                })
+                .collect()
+        }
+        _ => vec![PlannedTrack {
+            source,
+            track_index: None,
+            output: output(&name),
+            collides_with: vec![],
+        }],
+    };
+
+    Job {
+        relative_path: relative_path.to_path_buf(),
+        format,
+        track_list,
+        tracks,
+    }
+}
+
+fn read_track_list(path: &Path) -> Result<Amf0Value> {
+    let input_data = fs::read(path).context("Failed to read input file")?;
+    sol::raw::read_raw(input_data).context("Failed to read track")
+}
+
+/// Find tracks that would be written to the same output, such as `a.trk` and `a.sol`
+fn find_collisions(jobs: &mut [Job]) {
+    let mut outputs: HashMap<String, Vec<(usize, usize)>> = HashMap::new();
+    for (job_index, job) in jobs.iter().enumerate() {
+        for (track_index, track) in job.tracks.iter().enumerate() {
+            // Compared without case, as some file systems ignore it
+            let output = track.output.to_string_lossy().to_lowercase();
+            outputs
+                .entry(output)
+                .or_default()
+                .push((job_index, track_index));
+        }
+    }
+
+    for tracks in outputs.into_values().filter(|tracks| tracks.len() > 1) {
+        for &(job_index, track_index) in &tracks {
+            let others = tracks
+                .iter()
+                .filter(|other| **other != (job_index, track_index))
+                .map(|&(other_job, other_track)| jobs[other_job].tracks[other_track].source.clone())
+                .collect();
+            jobs[job_index].tracks[track_index].collides_with = others;
+        }
+    }
+}
+
+fn convert_file(input_dir: &Path, job: Job, output_format: Format) -> Vec<Conversion> {
+    let file_error = |err: anyhow::Error| {
+        vec![Conversion {
+            source: job.relative_path.display().to_string(),
+            result: Err(err),
+        }]
+    };
+
+    match job.track_list {
+        // Parsed once and shared by every track in the file
+        Some(Ok(ref track_list)) => job
+            .tracks
+            .iter()
+            .map(|planned| Conversion {
+                source: planned.source.clone(),
+                result: check_collisions(planned).and_then(|()| {
+                    let track = sol::read_from_raw(track_list, planned.track_index)
+                        .context("Failed to read track")?;
+                    write_track(&track, output_format, &planned.output)
+                }),
+            })
+            .collect(),
+        Some(Err(err)) => file_error(err),
+        None => {
+            let input_data = match fs::read(input_dir.join(&job.relative_path)) {
+                Ok(data) => data,
+                Err(err) => {
+                    return file_error(
+                        anyhow::Error::new(err).context("Failed to read input file"),
+                    );
+                }
+            };
+
+            job.tracks
+                .iter()
+                .map(|planned| Conversion {
+                    source: planned.source.clone(),
+                    result: check_collisions(planned).and_then(|()| {
+                        let track = formats::read_with_options(
+                            job.format,
+                            input_data.clone(),
+                            &ReadOptions::default(),
+                        )
+                        .context("Failed to read track")?;
+                        write_track(&track, output_format, &planned.output)
+                    }),
+                })
+                .collect()
+        }
+    }
+}
+
+fn check_collisions(planned: &PlannedTrack) -> Result<()> {
+    if !planned.collides_with.is_empty() {
+        bail!(
+            "Output '{}' would also be written by {}",
+            planned.output.display(),
+            planned.collides_with.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
+fn write_track(track: &Track, to: Format, output: &Path) -> Result<Converted> {
+    let output_data = formats::write(to, track).context("Failed to write track")?;
+
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory '{}'", parent.display()))?;
+    }
+
+    fs::write(output, output_data)
+        .with_context(|| format!("Failed to write output file '{}'", output.display()))?;
+
+    Ok(Converted {
+        lost: lost_properties(track, to),
+        output: output.to_path_buf(),
+    })
+}
+
+fn vec2_differs(value: Option<Vec2>, default: Vec2) -> bool {
+    value.is_some_and(|v| v.x() != default.x() || v.y() != default.y())
+}
+
+/// Names of the track properties that the output format drops
+fn lost_properties(track: &Track, to: Format) -> Vec<&'static str> {
+    let metadata = track.metadata();
+    let line_group = track.line_group();
+    let mut lost = vec![];
+
+    let has_triggers = track.background_color_group().is_some()
+        || track.line_color_group().is_some()
+        || track.camera_zoom_group().is_some()
+        || track.legacy_camera_zoom_group().is_some();
+
+    // LRA properties are always filled in by the TRK reader, so only report them when they
+    // differ from the LRA defaults
+    let has_lra_properties = metadata.start_zoom().is_some_and(|zoom| zoom != 2.0)
+        || vec2_differs(metadata.start_gravity(), Vec2::new(0.0, 1.0))
+        || metadata
+            .gravity_well_size()
+            .is_some_and(|size| size != 10.0)
+        || metadata
+            .start_line_color()
+            .is_some_and(|color| (color.red(), color.green(), color.blue()) != (0, 0, 0))
+        || metadata
+            .start_background_color()
+            .is_some_and(|color| (color.red(), color.green(), color.blue()) != (244, 245, 249))
+        || metadata.zero_friction_riders()
+        || metadata.remount_riders();
+
+    let has_web_metadata = metadata.artist().is_some()
+        || metadata.description().is_some()
+        || metadata.script().is_some()
+        || metadata.duration().is_some();
+
+    let has_line_properties = line_group
+        .acceleration_lines()
+        .iter()
+        .any(|line| line.multiplier().is_some_and(|m| m != 1.0))
+        || line_group
+            .scenery_lines()
+            .iter()
+            .any(|line| line.width().is_some_and(|w| w != 1.0));
+
+    let capabilities = to.capabilities();
+    let dropped = [
+        (capabilities.layers, track.layer_group().is_some(), "layers"),
+        (capabilities.riders, track.rider_group().is_some(), "riders"),
+        (capabilities.triggers, has_triggers, "triggers"),
+        (
+            capabilities.lra_properties,
+            has_lra_properties,
+            "LRA track properties",
+        ),
+        (
+            capabilities.zero_velocity_start,
+            metadata.zero_velocity_start_riders(),
+            "zero start",
+        ),
+        (
+            capabilities.song_info,
+            metadata.audio_filename().is_some(),
+            "song info",
+        ),
+        (capabilities.title, metadata.title().is_some(), "title"),
+        (
+            capabilities.web_metadata,
+            has_web_metadata,
+            "linerider.com track properties",
+        ),
+        (
+            capabilities.line_properties,
+            has_line_properties,
+            "line properties",
+        ),
+    ];
+
+    for (supported, present, name) in dropped {
+        if present && !supported {
+            lost.push(name);
+        }
+    }
+
+    // Unknown properties are only written back to the format they were read from
+    let extras = track.extras();
+    let has_foreign_extras = match to {
+        Format::Json => {
+            !extras.trk_features().is_empty()
+                || !extras.trk_metadata().is_empty()
+                || !extras.sol_properties().is_empty()
+        }
+        Format::Sol => {
+            !extras.trk_features().is_empty()
+                || !extras.trk_metadata().is_empty()
+                || !extras.json_properties().is_empty()
+        }
+        Format::Trk => !extras.json_properties().is_empty() || !extras.sol_properties().is_empty(),
+    };
+    if has_foreign_extras {
+        lost.push("unknown properties");
+    }
+
+    lost
+}
+
+fn print_summary(conversions: &[Conversion]) {
+    let mut succeeded = 0;
+    let mut lossy = vec![];
+    let mut failed = vec![];
+
+    for conversion in conversions {
+        match &conversion.result {
+            Ok(converted) => {
+                succeeded += 1;
+                if !converted.lost.is_empty() {
+                    lossy.push((conversion, converted));
+                }
+            }
+            Err(err) => failed.push((conversion, err)),
+        }
+    }
+
+    if !lossy.is_empty() {
+        println!("Lossy conversions:");
+        for (conversion, converted) in &lossy {
+            println!(
+                "  {} -> {} (dropped {})",
+                conversion.source,
+                converted.output.display(),
+                converted.lost.join(", ")
+            );
+        }
+    }
+
+    if !failed.is_empty() {
+        println!("Failed conversions:");
+        for (conversion, err) in &failed {
+            println!("  {}: {:#}", conversion.source, err);
+        }
+    }
+
+    println!(
+        "{} succeeded ({} lossy), {} failed, {} total",
+        succeeded,
+        lossy.len(),
+        failed.len(),
+        conversions.len()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_collisions, plan_file, track_name};
+    use anyhow::anyhow;
+    use lr_formatter_rs::formats::{Format, sol};
+    use std::path::{Path, PathBuf};
+
+    fn outputs(job: &super::Job) -> Vec<PathBuf> {
+        job.tracks
+            .iter()
+            .map(|track| track.output.clone())
+            .collect()
+    }
+
+    #[test]
+    fn track_names_drop_format_extensions() {
+        assert_eq!(track_name(Path::new("a/b.track.json")), "b");
+        assert_eq!(track_name(Path::new("b.JSON")), "b");
+        assert_eq!(track_name(Path::new("b.trk")), "b");
+        assert_eq!(track_name(Path::new("b.c.sol")), "b.c");
+    }
+
+    #[test]
+    fn tracks_are_planned_next_to_their_input() {
+        let job = plan_file(Path::new("out"), Path::new("a/b.trk"), Format::Sol, None);
+        assert_eq!(outputs(&job), [Path::new("out/a/b.sol")]);
+        assert_eq!(job.tracks[0].track_index, None);
+    }
+
+    #[test]
+    fn sol_tracks_are_counted_from_the_track_list() {
+        let data = include_bytes!("../../samples/Silk_Road.sol").to_vec();
+        let track_list = sol::raw::read_raw(data).unwrap();
+
+        let job = plan_file(
+            Path::new("out"),
+            Path::new("b.sol"),
+            Format::Trk,
+            Some(Ok(track_list)),
+        );
+        assert_eq!(
+            outputs(&job),
+            [
+                Path::new("out/b (0).trk"),
+                Path::new("out/b (1).trk"),
+                Path::new("out/b (2).trk"),
+            ]
+        );
+        assert_eq!(job.tracks[2].source, "b.sol [track 2]");
+
+        // Unreadable files are planned as one track that reports the error
+        let job = plan_file(
+            Path::new("out"),
+            Path::new("b.sol"),
+            Format::Trk,
+            Some(Err(anyhow!("Failed to read track"))),
+        );
+        assert_eq!(outputs(&job), [Path::new("out/b.trk")]);
+    }
+
+    #[test]
+    fn tracks_with_the_same_output_collide() {
+        let mut jobs = ["a.trk", "A.sol", "b.trk"]
+            .map(|path| plan_file(Path::new("out"), Path::new(path), Format::Json, None));
+        find_collisions(&mut jobs);
+
+        assert_eq!(jobs[0].tracks[0].collides_with, ["A.sol"]);
+        assert_eq!(jobs[1].tracks[0].collides_with, ["a.trk"]);
+        assert!(jobs[2].tracks[0].collides_with.is_empty());
+    }
+}
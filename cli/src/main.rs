@@ -1,11 +1,16 @@
-use anyhow::{Context, Result, bail};
+mod batch;
+
+use anyhow::{Context, Result};
 use clap::Parser;
 use dialoguer::Input;
-use lr_formatter_rs::formats::{json, sol, trk};
+use lr_formatter_rs::formats::{self, Format, ReadOptions, sol};
 use std::fs::File;
-use std::io::{Read, Write};
+use std::io::{self, Read, Write};
 use std::path::Path;
 
+/// Path that stands for standard input or output
+const STDIO_PATH: &str = "-";
+
 #[derive(Parser)]
 #[command(
     version = "1.1.0",
@@ -13,112 +18,124 @@ use std::path::Path;
     about = "CLI for converting Line Rider file formats"
 )]
 struct Cli {
-    /// Path of the file to convert
+    /// Path of the file to convert, a directory to batch convert, or - for standard input
     input_file: String,
-    /// Format to convert to (json, sol)
+    /// Format to convert to (json, trk, sol)
     output_format: String,
-    /// Optional output file path
+    /// Optional output file path, or - for standard output (output directory in batch mode)
     output_file: Option<String>,
+    /// Track index to convert from a SOL file, instead of prompting for it
+    #[arg(short, long)]
+    track_index: Option<u32>,
+    /// Batch mode: also convert files in subdirectories
+    #[arg(short, long)]
+    recursive: bool,
+    /// Batch mode: only convert files whose relative path matches this glob (repeatable)
+    #[arg(long, value_name = "GLOB")]
+    include: Vec<String>,
+    /// Batch mode: skip files whose relative path matches this glob (repeatable)
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+    /// Batch mode: number of files to convert in parallel (defaults to the number of CPUs)
+    #[arg(short, long)]
+    jobs: Option<usize>,
 }
 
-enum Format {
-    Json,
-    TRK,
-    SOL(Option<u32>),
+fn convert(
+    input: Vec<u8>,
+    from: Format,
+    read_options: &ReadOptions,
+    to: Format,
+) -> Result<Vec<u8>> {
+    let internal_format = formats::read_with_options(from, input, read_options)?;
+    Ok(formats::write(to, &internal_format)?)
 }
 
-fn convert(input: Vec<u8>, from: Format, to: Format) -> Result<Vec<u8>> {
-    let internal_format = match from {
-        Format::Json => json::read(input)?,
-        Format::TRK => trk::read(input)?,
-        Format::SOL(track_index) => sol::read(input, track_index)?,
-    };
-
-    let output_bytes = match to {
-        Format::Json => json::write(&internal_format)?,
-        Format::SOL(_) => sol::write(&internal_format)?,
-        _ => bail!("Unsupported to format. Must be one of: json, sol"),
-    };
+fn run() -> Result<()> {
+    let args = Cli::parse();
 
-    Ok(output_bytes)
-}
+    let output_format = args
+        .output_format
+        .parse::<Format>()
+        .context("Failed to parse output format")?;
+
+    if Path::new(&args.input_file).is_dir() {
+        let options = batch::BatchOptions {
+            input_dir: args.input_file.into(),
+            output_dir: args.output_file.map(Into::into),
+            output_format,
+            recursive: args.recursive,
+            include: args.include,
+            exclude: args.exclude,
+            jobs: args.jobs,
+        };
+        return batch::run(options);
+    }
 
-fn parse_format(format: &str, sol_index: Option<u32>) -> Result<Format> {
-    match format.to_lowercase().as_str() {
-        "json" => Ok(Format::Json),
-        "trk" => Ok(Format::TRK),
-        "sol" => Ok(Format::SOL(sol_index)),
-        _ => Err(anyhow::anyhow!(
-            "Invalid format '{}'. Must be one of: json, trk, sol",
-            format
-        )),
+    let mut input_data = Vec::new();
+    let from_stdin = args.input_file == STDIO_PATH;
+
+    if from_stdin {
+        io::stdin()
+            .read_to_end(&mut input_data)
+            .context("Failed to read standard input")?;
+    } else {
+        File::open(&args.input_file)
+            .with_context(|| format!("Failed to open input file '{}'", &args.input_file))?
+            .read_to_end(&mut input_data)
+            .context("Failed to read input file")?;
     }
-}
 
-fn run() -> Result<()> {
-    let args = Cli::parse();
+    let input_path = Path::new(&args.input_file);
 
-    let mut input_data = Vec::new();
+    let input_format = if from_stdin {
+        Format::detect(&input_data).context("Failed to detect input format from standard input")?
+    } else {
+        let input_extension = input_path
+            .extension()
+            .and_then(|e| e.to_str())
+            .context("Failed to parse file extension")?;
+        input_extension
+            .parse::<Format>()
+            .context("Failed to parse input format")?
+    };
 
-    File::open(&args.input_file)
-        .with_context(|| format!("Failed to open input file '{}'", &args.input_file))?
-        .read_to_end(&mut input_data)
-        .context("Failed to read input file")?;
+    let mut read_options = ReadOptions::default();
 
-    let input_path = Path::new(&args.input_file);
-    let input_extension = input_path
-        .extension()
-        .and_then(|e| e.to_str())
-        .context("Failed to parse file extension")?;
-    let input_name = input_path
-        .file_stem()
-        .and_then(|e| e.to_str())
-        .context("Failed to parse file name")?;
-
-    let mut sol_index = None;
-
-    if input_extension == "sol" {
-        let max_index = sol::get_track_count(&input_data) - 1;
-        if max_index > 0 {
-            sol_index = Some(
-                Input::new()
-                    .with_prompt(format!(
-                        "SOL detected, please enter track file index (0 - {})",
-                        max_index
-                    ))
-                    .validate_with(|input: &u32| {
-                        if (0..=max_index).contains(input) {
-                            Ok(())
-                        } else {
-                            Err(format!(
-                                "Track file index must be in range (0 - {})",
-                                max_index
-                            ))
-                        }
-                    })
-                    .interact_text()
-                    .unwrap_or(0),
-            );
-        }
+    if input_format == Format::Sol {
+        read_options.sol.track_index = Some(match args.track_index {
+            Some(index) => index,
+            // Standard input is taken by the track data, so we cannot prompt there
+            None if from_stdin => 0,
+            None => prompt_sol_index(&input_data),
+        });
     }
 
-    let input_format =
-        parse_format(input_extension, sol_index).context("Failed to parse input format")?;
-    let output_format =
-        parse_format(&args.output_format, None).context("Failed to parse output format")?;
-    let output_extension = match output_format {
-        Format::SOL(_) => ".sol",
-        Format::TRK => ".trk",
-        Format::Json => ".track.json",
+    let output_file_name = match args.output_file {
+        Some(output_file) => output_file,
+        None if from_stdin => STDIO_PATH.to_string(),
+        None => {
+            let input_name = input_path
+                .file_stem()
+                .and_then(|e| e.to_str())
+                .context("Failed to parse file name")?;
+            let file_name = format!("{} (Converted).{}", input_name, output_format.extension());
+            let parent_dir = input_path.parent().unwrap_or_else(|| Path::new("."));
+            parent_dir.join(file_name).to_string_lossy().into_owned()
+        }
     };
-    let output_file_name = args.output_file.unwrap_or_else(|| {
-        let file_name = format!("{} (Converted){}", input_name, output_extension);
-        let parent_dir = input_path.parent().unwrap_or_else(|| Path::new("."));
-        parent_dir.join(file_name).to_string_lossy().into_owned()
-    });
 
-    let output_data =
-        &convert(input_data, input_format, output_format).context("Conversion failed")?;
+    let output_data = &convert(input_data, input_format, &read_options, output_format)
+        .context("Conversion failed")?;
+
+    if output_file_name == STDIO_PATH {
+        let mut stdout = io::stdout().lock();
+        return match stdout.write_all(output_data).and_then(|_| stdout.flush()) {
+            // The reading end of a pipe closing early is not a conversion failure
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+            result => result.context("Failed to write standard output"),
+        };
+    }
 
     File::create(&output_file_name)
         .with_context(|| format!("Failed to create output file '{}'", output_file_name))?
@@ -130,6 +147,33 @@ fn run() -> Result<()> {
     Ok(())
 }
 
+/// Ask which track of a multi-track SOL file to convert
+fn prompt_sol_index(input_data: &[u8]) -> u32 {
+    let max_index = sol::get_track_count(input_data).saturating_sub(1);
+
+    if max_index == 0 {
+        return 0;
+    }
+
+    Input::new()
+        .with_prompt(format!(
+            "SOL detected, please enter track file index (0 - {})",
+            max_index
+        ))
+        .validate_with(|input: &u32| {
+            if (0..=max_index).contains(input) {
+                Ok(())
+            } else {
+                Err(format!(
+                    "Track file index must be in range (0 - {})",
+                    max_index
+                ))
+            }
+        })
+        .interact_text()
+        .unwrap_or(0)
+}
+
 fn main() {
     if let Err(err) = run() {
         eprintln!("{:?}", err);
@@ -1,6 +1,16 @@
 mod error;
+mod format;
 pub mod json;
+mod limits;
 pub mod sol;
+pub mod svg;
 pub mod trk;
 
-pub use error::{TrackReadError, TrackWriteError};
+pub(crate) use error::Recovery;
+pub use error::{ErrorContext, ParseFormatError, ReadWarning, TrackReadError, TrackWriteError};
+pub use format::{
+    Format, FormatCapabilities, ReadOptions, WriteOptions, read, read_lenient, read_with_options,
+    write, write_with_options,
+};
+pub(crate) use limits::Budget;
+pub use limits::{LimitExceeded, ReadLimit, ReadLimits};
@@ -1,8 +1,147 @@
 use crate::formats::json::{JsonReadError, JsonWriteError};
+use crate::formats::limits::{Budget, ReadLimits};
 use crate::formats::sol::{SolReadError, SolWriteError};
-use crate::formats::trk::TrkReadError;
+use crate::formats::trk::{TrkReadError, TrkWriteError};
+use std::fmt;
 use thiserror::Error;
 
+/// Where in the input a read error happened
+///
+/// Binary formats report the byte offset of the reader and the record it was reading, like
+/// `lines[42]`. Structured formats report the path to the broken value, like `lines[42].type`
+/// for JSON or `trackList/3/data/127/8` for SOL.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorContext {
+    offset: Option<u64>,
+    path: Option<String>,
+}
+
+impl ErrorContext {
+    pub fn new(offset: Option<u64>, path: Option<String>) -> Self {
+        Self { offset, path }
+    }
+
+    pub fn at_offset(offset: u64) -> Self {
+        Self::new(Some(offset), None)
+    }
+
+    pub fn at_path(path: impl Into<String>) -> Self {
+        Self::new(None, Some(path.into()))
+    }
+
+    /// Byte offset into the input
+    pub fn offset(&self) -> Option<u64> {
+        self.offset
+    }
+
+    /// Path to the broken record or value
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.path, self.offset) {
+            (Some(path), Some(offset)) => write!(f, "at `{}`, byte {}", path, offset),
+            (Some(path), None) => write!(f, "at `{}`", path),
+            (None, Some(offset)) => write!(f, "at byte {}", offset),
+            (None, None) => write!(f, "at unknown location"),
+        }
+    }
+}
+
+/// A problem that a lenient read skipped over or repaired
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadWarning {
+    message: String,
+    context: ErrorContext,
+}
+
+impl ReadWarning {
+    pub fn new(message: impl Into<String>, context: ErrorContext) -> Self {
+        Self {
+            message: message.into(),
+            context,
+        }
+    }
+
+    /// What was skipped or repaired, and why
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Where in the input the problem is
+    pub fn context(&self) -> &ErrorContext {
+        &self.context
+    }
+}
+
+impl fmt::Display for ReadWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.message, self.context)
+    }
+}
+
+/// Decides whether readers fail on a broken record, or skip it with a warning
+///
+/// Also holds the budget of the read, since a file that goes over its limits is never
+/// recovered from.
+#[derive(Debug)]
+pub(crate) struct Recovery {
+    lenient: bool,
+    warnings: Vec<ReadWarning>,
+    pub budget: Budget,
+}
+
+impl Recovery {
+    pub fn strict(limits: &ReadLimits) -> Self {
+        Self {
+            lenient: false,
+            warnings: vec![],
+            budget: Budget::new(limits),
+        }
+    }
+
+    pub fn lenient(limits: &ReadLimits) -> Self {
+        Self {
+            lenient: true,
+            warnings: vec![],
+            budget: Budget::new(limits),
+        }
+    }
+
+    pub fn is_lenient(&self) -> bool {
+        self.lenient
+    }
+
+    /// Pass the error on in strict mode, or record it as a warning and return `None` in lenient
+    /// mode
+    pub fn recover<T, E: fmt::Display>(
+        &mut self,
+        result: Result<T, E>,
+        action: &str,
+        context: impl FnOnce() -> ErrorContext,
+    ) -> Result<Option<T>, E> {
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(err) if self.lenient && !self.budget.is_exceeded() => {
+                self.warn(format!("{}: {}", action, err), context());
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn warn(&mut self, message: impl Into<String>, context: ErrorContext) {
+        self.warnings.push(ReadWarning::new(message, context));
+    }
+
+    pub fn into_warnings(self) -> Vec<ReadWarning> {
+        self.warnings
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum TrackReadError {
     #[error("{0}")]
@@ -13,8 +152,25 @@ pub enum TrackReadError {
     Sol(#[from] SolReadError),
 }
 
+impl TrackReadError {
+    /// Where in the input the error happened, if known
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            TrackReadError::Trk(err) => err.context(),
+            TrackReadError::Json(err) => err.context(),
+            TrackReadError::Sol(err) => err.context(),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("Invalid format '{0}'. Must be one of: json, trk, sol")]
+pub struct ParseFormatError(pub(crate) String);
+
 #[derive(Error, Debug)]
 pub enum TrackWriteError {
+    #[error("{0}")]
+    Trk(#[from] TrkWriteError),
     #[error("{0}")]
     Json(#[from] JsonWriteError),
     #[error("{0}")]
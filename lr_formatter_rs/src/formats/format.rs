@@ -0,0 +1,300 @@
+use std::{fmt, path::Path, str::FromStr};
+
+use crate::{
+    formats::{
+        ParseFormatError, ReadLimits, ReadWarning, Recovery, TrackReadError, TrackWriteError,
+        json::{self, JsonWriteOptions},
+        sol::{self, SolReadOptions, SolWriteOptions},
+        trk,
+    },
+    track::Track,
+};
+
+/// A supported track file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+    /// Line Rider Advanced binary format
+    Trk,
+    /// linerider.com and Line Rider Advanced JSON format
+    Json,
+    /// Flash Line Rider shared object format
+    Sol,
+}
+
+/// What a format can store, so callers can tell which properties a conversion drops
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatCapabilities {
+    /// Tracks can be read from the format
+    pub read: bool,
+    /// Tracks can be written to the format
+    pub write: bool,
+    /// One file can hold several tracks
+    pub multiple_tracks: bool,
+    /// Track title
+    pub title: bool,
+    /// Artist, description, script and duration from linerider.com
+    pub web_metadata: bool,
+    /// Song file name and offset
+    pub song_info: bool,
+    /// Start zoom, gravity, colors and other LRA track properties
+    pub lra_properties: bool,
+    /// Riders starting without velocity
+    pub zero_velocity_start: bool,
+    /// Acceleration multipliers and scenery widths
+    pub line_properties: bool,
+    pub layers: bool,
+    pub riders: bool,
+    /// Zoom and color triggers
+    pub triggers: bool,
+}
+
+impl Format {
+    pub const ALL: [Format; 3] = [Format::Trk, Format::Json, Format::Sol];
+
+    /// Short lowercase name of the format
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Trk => "trk",
+            Format::Json => "json",
+            Format::Sol => "sol",
+        }
+    }
+
+    /// File extension used when writing the format, without the leading dot
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Trk => "trk",
+            Format::Json => "track.json",
+            Format::Sol => "sol",
+        }
+    }
+
+    /// Look up the format of a file extension, ignoring case
+    pub fn from_extension(extension: &str) -> Option<Format> {
+        match extension.to_lowercase().as_str() {
+            "trk" => Some(Format::Trk),
+            "json" | "track.json" => Some(Format::Json),
+            "sol" => Some(Format::Sol),
+            _ => None,
+        }
+    }
+
+    /// Look up the format of a file from the extension of its path
+    pub fn from_path(path: &Path) -> Option<Format> {
+        Format::from_extension(path.extension()?.to_str()?)
+    }
+
+    /// Guess the format of track data from its leading bytes
+    pub fn detect(data: &[u8]) -> Option<Format> {
+        if data.starts_with(b"TRK\xF2") {
+            return Some(Format::Trk);
+        }
+
+        if data.starts_with(b"\x00\xBF") {
+            return Some(Format::Sol);
+        }
+
+        let first_char = data.iter().find(|byte| !byte.is_ascii_whitespace());
+
+        if first_char == Some(&b'{') {
+            return Some(Format::Json);
+        }
+
+        None
+    }
+
+    pub fn capabilities(&self) -> FormatCapabilities {
+        let all = FormatCapabilities {
+            read: true,
+            write: true,
+            multiple_tracks: false,
+            title: true,
+            web_metadata: true,
+            song_info: true,
+            lra_properties: true,
+            zero_velocity_start: true,
+            line_properties: true,
+            layers: true,
+            riders: true,
+            triggers: true,
+        };
+
+        match self {
+            Format::Trk => FormatCapabilities {
+                title: false,
+                web_metadata: false,
+                layers: false,
+                riders: false,
+                ..all
+            },
+            Format::Json => FormatCapabilities {
+                song_info: false,
+                lra_properties: false,
+                zero_velocity_start: false,
+                triggers: false,
+                ..all
+            },
+            Format::Sol => FormatCapabilities {
+                multiple_tracks: true,
+                web_metadata: false,
+                song_info: false,
+                lra_properties: false,
+                line_properties: false,
+                layers: false,
+                riders: false,
+                triggers: false,
+                ..all
+            },
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Format {
+    type Err = ParseFormatError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Format::ALL
+            .into_iter()
+            .find(|format| format.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| ParseFormatError(name.to_string()))
+    }
+}
+
+/// Options for reading tracks
+#[derive(Debug, Clone, Default)]
+pub struct ReadOptions {
+    /// Bounds on what every reader accepts
+    pub limits: ReadLimits,
+    /// Only used for SOL files
+    pub sol: SolReadOptions,
+}
+
+/// Options for writing tracks, only used by the format they belong to
+#[derive(Debug, Clone, Default)]
+pub struct WriteOptions {
+    pub json: JsonWriteOptions,
+    pub sol: SolWriteOptions,
+}
+
+pub fn read(format: Format, data: Vec<u8>) -> Result<Track, TrackReadError> {
+    read_with_options(format, data, &ReadOptions::default())
+}
+
+pub fn read_with_options(
+    format: Format,
+    data: Vec<u8>,
+    options: &ReadOptions,
+) -> Result<Track, TrackReadError> {
+    let track = match format {
+        Format::Trk => trk::read_with_limits(data, &options.limits)?,
+        Format::Json => json::read_with_limits(data, &options.limits)?,
+        Format::Sol => sol::read_with_limits(data, options.sol.track_index, &options.limits)?,
+    };
+
+    Ok(track)
+}
+
+/// Read a track, skipping the records that fail to read and reporting them as warnings
+pub fn read_lenient(
+    format: Format,
+    data: Vec<u8>,
+    options: &ReadOptions,
+) -> Result<(Track, Vec<ReadWarning>), TrackReadError> {
+    let mut recovery = Recovery::lenient(&options.limits);
+    let track = match format {
+        Format::Trk => trk::read_with_recovery(data, &mut recovery)?,
+        Format::Json => json::read_with_recovery(data, &mut recovery)?,
+        Format::Sol => sol::read_with_recovery(data, options.sol.track_index, &mut recovery)?,
+    };
+
+    Ok((track, recovery.into_warnings()))
+}
+
+pub fn write(format: Format, track: &Track) -> Result<Vec<u8>, TrackWriteError> {
+    write_with_options(format, track, &WriteOptions::default())
+}
+
+pub fn write_with_options(
+    format: Format,
+    track: &Track,
+    options: &WriteOptions,
+) -> Result<Vec<u8>, TrackWriteError> {
+    let data = match format {
+        Format::Trk => trk::write(track)?,
+        Format::Json => json::write_with_options(track, &options.json)?,
+        Format::Sol => sol::write_with_options(track, &options.sol)?,
+    };
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Format, read, write};
+    use crate::track::{GridVersion, LineType, TrackBuilder, Vec2};
+    use std::path::Path;
+
+    #[test]
+    fn formats_are_found_by_name_extension_and_contents() {
+        assert_eq!("TRK".parse::<Format>().unwrap(), Format::Trk);
+        assert!("png".parse::<Format>().is_err());
+        assert_eq!(
+            Format::from_path(Path::new("tracks/Loop.track.json")),
+            Some(Format::Json)
+        );
+
+        let mut track_builder = TrackBuilder::default();
+        track_builder.metadata().grid_version(GridVersion::V6_2);
+        track_builder
+            .line_group()
+            .add_scenery_line(1, (Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0)));
+        let track = track_builder.build().unwrap();
+
+        for format in Format::ALL {
+            let data = write(format, &track).unwrap();
+            assert_eq!(Format::detect(&data), Some(format));
+
+            let track = read(format, data).unwrap();
+            assert_eq!(track.line_group().scenery_lines().len(), 1);
+        }
+    }
+
+    #[test]
+    fn lines_are_written_in_the_order_they_were_added() {
+        let endpoints = (Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0));
+        let mut track_builder = TrackBuilder::default();
+        track_builder.metadata().grid_version(GridVersion::V6_2);
+        let line_group = track_builder.line_group();
+        line_group.add_scenery_line(4, endpoints);
+        line_group.add_standard_line(1, endpoints, false, false, false);
+        line_group.add_acceleration_line(3, endpoints, false, false, false);
+        line_group.add_standard_line(2, endpoints, false, false, false);
+        let track = track_builder.build().unwrap();
+
+        for format in Format::ALL {
+            let track = read(format, write(format, &track).unwrap()).unwrap();
+            let line_types = track
+                .line_group()
+                .iter()
+                .map(|line| line.line_type())
+                .collect::<Vec<_>>();
+            assert_eq!(
+                line_types,
+                [
+                    LineType::Scenery,
+                    LineType::Standard,
+                    LineType::Acceleration,
+                    LineType::Standard
+                ],
+                "{} line order",
+                format
+            );
+        }
+    }
+}
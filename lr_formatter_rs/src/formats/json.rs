@@ -6,11 +6,28 @@ mod serde_line_array;
 mod writer;
 
 pub use error::{JsonReadError, JsonWriteError};
-pub use reader::read;
-pub use writer::write;
+pub(crate) use reader::read_with_recovery;
+pub use reader::{read, read_lenient, read_with_limits};
+pub use writer::{write, write_with_options};
 
 use serde::{Deserialize, Serialize};
 
+/// Variant of the JSON format to write
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum JsonDialect {
+    /// Line objects, as written by [linerider.com](https://www.linerider.com/)
+    #[default]
+    LineRiderCom,
+    /// Compact line arrays and the extra track properties written by Line Rider Advanced
+    LineRiderAdvanced,
+}
+
+/// Options for writing JSON tracks
+#[derive(Debug, Clone, Default)]
+pub struct JsonWriteOptions {
+    pub dialect: JsonDialect,
+}
+
 // A u32 value that can take the range of a normal u32, or negative for invalid (for parsing some json fields)
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -109,6 +126,8 @@ struct JsonLine {
     multiplier: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     width: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    layer: Option<u32>,
 }
 
 const LAYER_TYPE_LAYER: u8 = 0;
@@ -147,6 +166,7 @@ struct JsonTrack {
     description: Option<String>,
     duration: Option<u32>,
     version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     lines: Option<Vec<JsonLine>>,
     layers: Option<Vec<JsonLayer>>,
     riders: Option<Vec<JsonRider>>,
@@ -181,4 +201,188 @@ struct JsonTrack {
     line_color_green: Option<u32>,
     #[serde(rename = "lineB", skip_serializing_if = "Option::is_none")]
     line_color_blue: Option<u32>,
+    // Keys this library does not model, kept so they can be written back
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{
+        JsonDialect, JsonReadError, JsonWriteOptions, read, read_lenient, read_with_limits, write,
+        write_with_options,
+    };
+    use crate::{
+        formats::{ReadLimit, ReadLimits},
+        track::{GridVersion, TrackBuilder, Vec2},
+    };
+
+    fn error_path(json: &str) -> Option<String> {
+        let Err(err) = read(json.as_bytes().to_vec()) else {
+            panic!("Expected {} to fail to read", json);
+        };
+        err.context()
+            .and_then(|context| context.path())
+            .map(str::to_string)
+    }
+
+    #[test]
+    fn read_errors_point_at_broken_value() {
+        let line = r#"{"id":1,"type":0,"x1":0,"y1":0,"x2":1,"y2":1}"#;
+        let bad_type = r#"{"id":2,"type":7,"x1":0,"y1":0,"x2":1,"y2":1}"#;
+        let bad_field = r#"{"id":2,"type":0,"x1":"a","y1":0,"x2":1,"y2":1}"#;
+
+        assert_eq!(
+            error_path(&format!(
+                r#"{{"version":"6.2","lines":[{},{}]}}"#,
+                line, bad_type
+            )),
+            Some("lines[1].type".to_string())
+        );
+        assert_eq!(
+            error_path(&format!(
+                r#"{{"version":"6.2","lines":[{},{}]}}"#,
+                line, bad_field
+            )),
+            Some("lines[1]".to_string())
+        );
+        assert_eq!(
+            error_path(r#"{"version":"5.0"}"#),
+            Some("version".to_string())
+        );
+    }
+
+    #[test]
+    fn lenient_read_skips_broken_values() {
+        let json = r#"{"version":"6.2","lines":[
+            {"id":1,"type":0,"x1":0,"y1":0,"x2":1,"y2":1},
+            {"id":2,"type":7,"x1":0,"y1":0,"x2":1,"y2":1},
+            {"id":3,"type":2,"x1":"a","y1":0,"x2":1,"y2":1}
+        ],"gameTriggers":[{"triggerType":1,"start":0,"end":10,"zoomTarget":1,"backgroundRed":300}]}"#;
+
+        let (track, warnings) = read_lenient(json.as_bytes().to_vec()).unwrap();
+        assert_eq!(track.line_group().standard_lines().len(), 1);
+        assert!(track.line_group().scenery_lines().is_empty());
+
+        let paths: Vec<_> = warnings
+            .iter()
+            .filter_map(|warning| warning.context().path())
+            .collect();
+        assert_eq!(paths, ["lines[2]", "lines[1].type", "gameTriggers[0]"]);
+    }
+
+    #[test]
+    fn lra_dialect_round_trips_line_arrays() {
+        let mut track_builder = TrackBuilder::default();
+        track_builder.metadata().grid_version(GridVersion::V6_2);
+        track_builder.metadata().start_zoom(1.0);
+        track_builder
+            .line_group()
+            .add_acceleration_line(
+                1,
+                (Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0)),
+                true,
+                false,
+                true,
+            )
+            .multiplier(3.0);
+        let track = track_builder.build().unwrap();
+
+        let options = JsonWriteOptions {
+            dialect: JsonDialect::LineRiderAdvanced,
+        };
+        let bytes = write_with_options(&track, &options).unwrap();
+        let json = String::from_utf8(bytes.clone()).unwrap();
+        assert!(json.contains(r#""linesArray":[[1,1,0.0,0.0,10.0,0.0,2,true,-1,-1,3]]"#));
+        assert!(!json.contains(r#""lines""#));
+
+        let track = read(bytes).unwrap();
+        let line = &track.line_group().acceleration_lines()[0];
+        assert!(line.flipped() && line.right_extension() && !line.left_extension());
+        assert_eq!(line.multiplier(), Some(3.0));
+        assert_eq!(track.metadata().start_zoom(), Some(1.0));
+    }
+
+    #[test]
+    fn line_layers_round_trip() {
+        let endpoints = (Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0));
+        let mut track_builder = TrackBuilder::default();
+        track_builder.metadata().grid_version(GridVersion::V6_2);
+        let layer_group = track_builder.layer_group();
+        layer_group.add_layer(0, 0).unwrap();
+        layer_group.add_layer(3, 1).unwrap();
+        let line_group = track_builder.line_group();
+        line_group.add_standard_line(1, endpoints, false, false, false);
+        line_group.add_acceleration_line(2, endpoints, false, false, false);
+        line_group.add_scenery_line(3, endpoints);
+        line_group.add_scenery_line(4, endpoints);
+        line_group.set_line_layers(&HashMap::from([(1, 3), (2, 0), (3, 3)]));
+        let track = track_builder.build().unwrap();
+
+        let bytes = write(&track).unwrap();
+        let json = String::from_utf8(bytes.clone()).unwrap();
+        assert!(json.contains(r#""id":1,"type":0"#) && json.contains(r#""layer":3"#));
+
+        let track = read(bytes).unwrap();
+        let layers = track
+            .line_group()
+            .iter()
+            .map(|line| (line.id(), line.layer()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            layers,
+            [(1, Some(3)), (2, Some(0)), (3, Some(3)), (4, None)]
+        );
+
+        // Line arrays have no place for layers
+        let options = JsonWriteOptions {
+            dialect: JsonDialect::LineRiderAdvanced,
+        };
+        let track = read(write_with_options(&track, &options).unwrap()).unwrap();
+        assert!(track.line_group().iter().all(|line| line.layer().is_none()));
+    }
+
+    #[test]
+    fn limits_are_checked_before_parsing() {
+        let limits = ReadLimits {
+            max_lines: 1,
+            max_string_length: 8,
+            max_nesting_depth: 3,
+            ..ReadLimits::default()
+        };
+        let exceeded = |json: String| {
+            let Err(err) = read_with_limits(json.into_bytes(), &limits) else {
+                panic!("Expected a read limit to be exceeded");
+            };
+            let JsonReadError::WithContext { source, .. } = err else {
+                panic!("Expected the error to have a location");
+            };
+            let JsonReadError::LimitExceeded(exceeded) = *source else {
+                panic!("Expected a read limit error, got {}", source);
+            };
+            exceeded.limit()
+        };
+
+        let line = r#"{"id":1,"type":2,"x1":0,"y1":0,"x2":1,"y2":1}"#;
+        let track = format!(r#"{{"version":"6.2","lines":[{line}]}}"#);
+        assert!(read_with_limits(track.into_bytes(), &limits).is_ok());
+        let lines = format!(r#"{{"version":"6.2","lines":[{line}, {line}]}}"#);
+        assert_eq!(exceeded(lines), ReadLimit::Lines);
+
+        // Escapes count as the characters they stand for
+        let label = r#"{"version":"6.2","label":"\u00e9\"ab"}"#;
+        assert!(read_with_limits(label.as_bytes().to_vec(), &limits).is_ok());
+        let label = r#"{"version":"6.2","label":"abcdefghi"}"#.to_string();
+        assert_eq!(exceeded(label), ReadLimit::StringLength);
+
+        // Nested far deeper than the JSON parser itself allows
+        let nested = format!(
+            r#"{{"version":"6.2","x":{}{}}}"#,
+            "[".repeat(500),
+            "]".repeat(500)
+        );
+        assert_eq!(exceeded(nested), ReadLimit::NestingDepth);
+    }
 }
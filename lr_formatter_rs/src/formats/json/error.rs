@@ -7,6 +7,7 @@ use std::{
 use thiserror::Error;
 
 use crate::{
+    formats::{ErrorContext, LimitExceeded},
     track::{
         TrackBuilderError, layer::layer_group::LayerGroupBuilderError,
         line::line_group::LineGroupBuilderError, rider::rider_group::RiderGroupBuilderError,
@@ -40,6 +41,34 @@ pub enum JsonReadError {
     FromUTF8(#[from] FromUtf8Error),
     #[error("{0}")]
     SerdeJson(#[from] serde_json::Error),
+    #[error("{0}")]
+    LimitExceeded(#[from] LimitExceeded),
+    #[error("{source} ({context})")]
+    WithContext {
+        context: ErrorContext,
+        source: Box<JsonReadError>,
+    },
+}
+
+impl JsonReadError {
+    /// Where in the input the error happened, if known
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            JsonReadError::WithContext { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    /// Attach a location to the error, keeping any more precise location it already has
+    pub(crate) fn with_context(self, context: ErrorContext) -> Self {
+        match self {
+            JsonReadError::WithContext { .. } => self,
+            _ => JsonReadError::WithContext {
+                context,
+                source: Box::new(self),
+            },
+        }
+    }
 }
 
 #[derive(Error, Debug)]
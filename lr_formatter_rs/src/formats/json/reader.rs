@@ -1,5 +1,14 @@
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
 use crate::{
-    formats::json::{FaultyBool, FaultyU32, JsonReadError, JsonTrack, LRAJsonArrayLine},
+    formats::{
+        Budget, ErrorContext, ReadLimits, ReadWarning, Recovery,
+        json::{
+            FaultyBool, FaultyU32, JsonLayer, JsonLine, JsonReadError, JsonRider, JsonTrack,
+            LRAJsonArrayLine, LRAJsonLegacyZoomTrigger, LRAJsonTrigger,
+        },
+    },
     track::{
         BackgroundColorEvent, CameraZoomEvent, FrameBoundsTrigger, GridVersion, LineColorEvent,
         LineHitTrigger, LineType, RGBColor, RemountVersion, Track, TrackBuilder, Vec2,
@@ -8,101 +17,93 @@ use crate::{
 };
 
 pub fn read(data: Vec<u8>) -> Result<Track, JsonReadError> {
+    read_with_limits(data, &ReadLimits::default())
+}
+
+/// Read a track within the given limits
+///
+/// The JSON text is parsed in one go, so its size is charged against the allocation limit up
+/// front and the other limits are checked by scanning the text before it is parsed.
+pub fn read_with_limits(data: Vec<u8>, limits: &ReadLimits) -> Result<Track, JsonReadError> {
+    read_with_recovery(data, &mut Recovery::strict(limits))
+}
+
+/// Read a track, skipping broken lines, layers, riders and triggers instead of failing
+pub fn read_lenient(data: Vec<u8>) -> Result<(Track, Vec<ReadWarning>), JsonReadError> {
+    let mut recovery = Recovery::lenient(&ReadLimits::default());
+    let track = read_with_recovery(data, &mut recovery)?;
+    Ok((track, recovery.into_warnings()))
+}
+
+pub(crate) fn read_with_recovery(
+    data: Vec<u8>,
+    recovery: &mut Recovery,
+) -> Result<Track, JsonReadError> {
     let track_builder = &mut TrackBuilder::default();
+    // Parsed values take up about as much as the text they were parsed from
+    recovery.budget.allocate(data.len())?;
     let json_string = String::from_utf8(data.to_vec())?;
-    let json_track: JsonTrack = serde_json::from_str(&json_string)?;
+    check_limits(&json_string, &mut recovery.budget)?;
+    let json_track: JsonTrack = match serde_json::from_str(&json_string) {
+        Ok(json_track) => json_track,
+        Err(err) if err.is_data() && recovery.is_lenient() => read_repaired(&json_string, recovery)
+            .map_err(|_repair_err| locate_serde_error(&json_string, err))?,
+        Err(err) => return Err(locate_serde_error(&json_string, err)),
+    };
+
+    let mut path = String::new();
+    read_track(json_track, track_builder, &mut path, recovery)
+        .map_err(|err| err.with_context(ErrorContext::at_path(path)))?;
+
+    Ok(track_builder.build()?)
+}
+
+// Reads a parsed track into the track builder, keeping `path` updated with the location of the
+// value being read
+fn read_track(
+    json_track: JsonTrack,
+    track_builder: &mut TrackBuilder,
+    path: &mut String,
+    recovery: &mut Recovery,
+) -> Result<(), JsonReadError> {
+    *path = String::from("version");
 
     let grid_version = match json_track.version.as_str() {
         "6.0" => GridVersion::V6_0,
         "6.1" => GridVersion::V6_1,
         "6.2" => GridVersion::V6_2,
         other => {
-            return Err(JsonReadError::InvalidData {
+            let invalid: Result<(), _> = Err(JsonReadError::InvalidData {
                 name: "grid version",
                 value: other.to_string(),
             });
+            recovery.recover(invalid, "Used grid version 6.2 instead", || {
+                ErrorContext::at_path(path.clone())
+            })?;
+            GridVersion::V6_2
         }
     };
 
     track_builder.metadata().grid_version(grid_version);
 
-    if let Some(line_list) = json_track.lines {
-        for line in line_list {
-            let line_type = match line.line_type {
-                0 => LineType::Standard,
-                1 => LineType::Acceleration,
-                2 => LineType::Scenery,
-                other => {
-                    return Err(JsonReadError::InvalidData {
-                        name: "line type",
-                        value: other.to_string(),
-                    });
-                }
-            };
-
-            let endpoints = (Vec2::new(line.x1, line.y1), Vec2::new(line.x2, line.y2));
-
-            let (left_extension, right_extension) = if line_type == LineType::Scenery {
-                (false, false)
-            } else if let Some(ext) = line.extended {
-                (ext & 1 != 0, ext & 2 != 0)
-            } else if let (Some(left_ext), Some(right_ext)) = (line.left_ext, line.right_ext) {
-                let left_ext_bool = match left_ext {
-                    FaultyBool::BoolRep(x) => x,
-                    FaultyBool::IntRep(x) => x == 1,
-                };
-                let right_ext_bool = match right_ext {
-                    FaultyBool::BoolRep(x) => x,
-                    FaultyBool::IntRep(x) => x == 1,
-                };
-                (left_ext_bool, right_ext_bool)
-            } else {
-                (false, false)
-            };
-
-            let flipped = match line.flipped {
-                None => false,
-                Some(FaultyBool::BoolRep(x)) => x,
-                Some(FaultyBool::IntRep(x)) => x == 1,
-            };
+    for (name, value) in json_track.extra {
+        track_builder.extras().json_property(name, value);
+    }
 
-            match line_type {
-                LineType::Standard => {
-                    track_builder.line_group().add_standard_line(
-                        line.id,
-                        endpoints,
-                        flipped,
-                        left_extension,
-                        right_extension,
-                    );
-                }
-                LineType::Acceleration => {
-                    let line_builder = track_builder.line_group().add_acceleration_line(
-                        line.id,
-                        endpoints,
-                        flipped,
-                        left_extension,
-                        right_extension,
-                    );
-                    if let Some(multiplier) = line.multiplier {
-                        line_builder.multiplier(multiplier);
-                    }
-                }
-                LineType::Scenery => {
-                    let line_builder = track_builder
-                        .line_group()
-                        .add_scenery_line(line.id, endpoints);
-                    if let Some(width) = line.width {
-                        line_builder.width(width);
-                    }
-                }
-            }
+    if let Some(line_list) = json_track.lines {
+        for (index, line) in line_list.into_iter().enumerate() {
+            *path = format!("lines[{}].type", index);
+            recovery.budget.add_line()?;
+            let line = read_line(line, track_builder);
+            recovery.recover(line, "Skipped line", || ErrorContext::at_path(path.clone()))?;
         }
     }
 
     // Legacy line array
     if let Some(line_list) = json_track.line_array {
-        for line in line_list {
+        for (index, line) in line_list.into_iter().enumerate() {
+            *path = format!("linesArray[{}]", index);
+            recovery.budget.add_line()?;
             match line {
                 LRAJsonArrayLine::Standard(id, x1, y1, x2, y2, extended, flipped) => {
                     let endpoints = (Vec2::new(x1, y1), Vec2::new(x2, y2));
@@ -152,46 +153,17 @@ pub fn read(data: Vec<u8>) -> Result<Track, JsonReadError> {
 
     if let Some(layers) = json_track.layers {
         for (index, layer) in layers.iter().enumerate() {
-            let layer_is_folder = layer.size.is_some();
-
-            if !layer_is_folder {
-                let layer_builder = track_builder
-                    .layer_group()
-                    .add_layer(layer.id, index)?
-                    .index(index)
-                    .name(layer.name.to_string())
-                    .visible(layer.visible);
-
-                if let Some(editable) = layer.editable {
-                    layer_builder.editable(editable);
-                }
-
-                if let Some(folder_id) = &layer.folder_id {
-                    if let FaultyU32::Valid(valid_folder_id) = folder_id {
-                        layer_builder.folder_id(Some(*valid_folder_id));
-                    }
-                }
-            } else {
-                let layer_folder_builder = track_builder
-                    .layer_group()
-                    .add_layer_folder(layer.id, index)?
-                    .index(index)
-                    .name(layer.name.to_string())
-                    .visible(layer.visible);
-
-                if let Some(editable) = layer.editable {
-                    layer_folder_builder.editable(editable);
-                }
-
-                if let Some(size) = layer.size {
-                    layer_folder_builder.size(size);
-                }
-            }
+            *path = format!("layers[{}]", index);
+            let layer = read_layer(layer, index, track_builder);
+            recovery.recover(layer, "Skipped layer", || {
+                ErrorContext::at_path(path.clone())
+            })?;
         }
     }
 
     if let Some(riders) = json_track.riders {
-        for rider in riders.iter() {
+        for (index, rider) in riders.iter().enumerate() {
+            *path = format!("riders[{}]", index);
             let start_position = Vec2::new(rider.start_pos.x, rider.start_pos.y);
             let start_velocity = Vec2::new(rider.start_vel.x, rider.start_vel.y);
 
@@ -244,10 +216,8 @@ pub fn read(data: Vec<u8>) -> Result<Track, JsonReadError> {
         track_builder.metadata().script(script);
     }
 
-    if let Some(zero_start) = json_track.zero_start {
-        if zero_start {
-            track_builder.metadata().zero_velocity_start_riders(true);
-        }
+    if json_track.zero_start == Some(true) {
+        track_builder.metadata().zero_velocity_start_riders(true);
     }
 
     if let Some(gravity_well_size) = json_track.gravity_well_size {
@@ -278,20 +248,41 @@ pub fn read(data: Vec<u8>) -> Result<Track, JsonReadError> {
             .start_zoom(from_lra_zoom(start_zoom));
     }
 
+    *path = String::from("lineR");
     let init_line_red = if let Some(init_red) = json_track.line_color_red {
-        u8::try_from(init_red)?
+        recovery
+            .recover(
+                u8::try_from(init_red),
+                "Used the default color instead",
+                || ErrorContext::at_path(path.clone()),
+            )?
+            .unwrap_or(0)
     } else {
         0
     };
 
+    *path = String::from("lineG");
     let init_line_green = if let Some(init_green) = json_track.line_color_green {
-        u8::try_from(init_green)?
+        recovery
+            .recover(
+                u8::try_from(init_green),
+                "Used the default color instead",
+                || ErrorContext::at_path(path.clone()),
+            )?
+            .unwrap_or(0)
     } else {
         0
     };
 
+    *path = String::from("lineB");
     let init_line_blue = if let Some(init_blue) = json_track.line_color_blue {
-        u8::try_from(init_blue)?
+        recovery
+            .recover(
+                u8::try_from(init_blue),
+                "Used the default color instead",
+                || ErrorContext::at_path(path.clone()),
+            )?
+            .unwrap_or(0)
     } else {
         0
     };
@@ -302,20 +293,41 @@ pub fn read(data: Vec<u8>) -> Result<Track, JsonReadError> {
         init_line_blue,
     ));
 
+    *path = String::from("bgR");
     let init_bg_red = if let Some(init_red) = json_track.background_color_red {
-        u8::try_from(init_red)?
+        recovery
+            .recover(
+                u8::try_from(init_red),
+                "Used the default color instead",
+                || ErrorContext::at_path(path.clone()),
+            )?
+            .unwrap_or(244)
     } else {
         244
     };
 
+    *path = String::from("bgG");
     let init_bg_green = if let Some(init_green) = json_track.background_color_green {
-        u8::try_from(init_green)?
+        recovery
+            .recover(
+                u8::try_from(init_green),
+                "Used the default color instead",
+                || ErrorContext::at_path(path.clone()),
+            )?
+            .unwrap_or(245)
     } else {
         245
     };
 
+    *path = String::from("bgB");
     let init_bg_blue = if let Some(init_blue) = json_track.background_color_blue {
-        u8::try_from(init_blue)?
+        recovery
+            .recover(
+                u8::try_from(init_blue),
+                "Used the default color instead",
+                || ErrorContext::at_path(path.clone()),
+            )?
+            .unwrap_or(249)
     } else {
         249
     };
@@ -325,7 +337,8 @@ pub fn read(data: Vec<u8>) -> Result<Track, JsonReadError> {
         .start_background_color(RGBColor::new(init_bg_red, init_bg_green, init_bg_blue));
 
     if let Some(line_triggers) = json_track.line_based_triggers {
-        for trigger in line_triggers {
+        for (index, trigger) in line_triggers.into_iter().enumerate() {
+            *path = format!("triggers[{}]", index);
             if trigger.zoom {
                 let line_hit = LineHitTrigger::new(trigger.id, trigger.frames);
                 let zoom_event = CameraZoomEvent::new(from_lra_zoom(trigger.target));
@@ -339,62 +352,455 @@ pub fn read(data: Vec<u8>) -> Result<Track, JsonReadError> {
     }
 
     if let Some(time_triggers) = json_track.time_based_triggers {
-        for trigger in time_triggers {
-            match trigger.trigger_type {
-                0 => {
-                    // Zoom
-                    let target_zoom = from_lra_zoom(trigger.zoom_target);
-                    let start_frame = trigger.start;
-                    let end_frame = trigger.end;
-                    let zoom_event = CameraZoomEvent::new(target_zoom);
-                    let frame_bounds = FrameBoundsTrigger::new(start_frame, end_frame);
-                    track_builder
-                        .camera_zoom_group()
-                        .add_trigger()
-                        .trigger(frame_bounds)
-                        .event(zoom_event);
+        for (index, trigger) in time_triggers.into_iter().enumerate() {
+            *path = format!("gameTriggers[{}]", index);
+            let trigger = read_time_trigger(trigger, track_builder);
+            recovery.recover(trigger, "Skipped trigger", || {
+                ErrorContext::at_path(path.clone())
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+fn read_line(line: JsonLine, track_builder: &mut TrackBuilder) -> Result<(), JsonReadError> {
+    let line_type = match line.line_type {
+        0 => LineType::Standard,
+        1 => LineType::Acceleration,
+        2 => LineType::Scenery,
+        other => {
+            return Err(JsonReadError::InvalidData {
+                name: "line type",
+                value: other.to_string(),
+            });
+        }
+    };
+
+    let endpoints = (Vec2::new(line.x1, line.y1), Vec2::new(line.x2, line.y2));
+
+    let (left_extension, right_extension) = if line_type == LineType::Scenery {
+        (false, false)
+    } else if let Some(ext) = line.extended {
+        (ext & 1 != 0, ext & 2 != 0)
+    } else if let (Some(left_ext), Some(right_ext)) = (line.left_ext, line.right_ext) {
+        let left_ext_bool = match left_ext {
+            FaultyBool::BoolRep(x) => x,
+            FaultyBool::IntRep(x) => x == 1,
+        };
+        let right_ext_bool = match right_ext {
+            FaultyBool::BoolRep(x) => x,
+            FaultyBool::IntRep(x) => x == 1,
+        };
+        (left_ext_bool, right_ext_bool)
+    } else {
+        (false, false)
+    };
+
+    let flipped = match line.flipped {
+        None => false,
+        Some(FaultyBool::BoolRep(x)) => x,
+        Some(FaultyBool::IntRep(x)) => x == 1,
+    };
+
+    match line_type {
+        LineType::Standard => {
+            let line_builder = track_builder.line_group().add_standard_line(
+                line.id,
+                endpoints,
+                flipped,
+                left_extension,
+                right_extension,
+            );
+            if let Some(layer) = line.layer {
+                line_builder.layer(layer);
+            }
+        }
+        LineType::Acceleration => {
+            let line_builder = track_builder.line_group().add_acceleration_line(
+                line.id,
+                endpoints,
+                flipped,
+                left_extension,
+                right_extension,
+            );
+            if let Some(multiplier) = line.multiplier {
+                line_builder.multiplier(multiplier);
+            }
+            if let Some(layer) = line.layer {
+                line_builder.layer(layer);
+            }
+        }
+        LineType::Scenery => {
+            let line_builder = track_builder
+                .line_group()
+                .add_scenery_line(line.id, endpoints);
+            if let Some(width) = line.width {
+                line_builder.width(width);
+            }
+            if let Some(layer) = line.layer {
+                line_builder.layer(layer);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn read_layer(
+    layer: &JsonLayer,
+    index: usize,
+    track_builder: &mut TrackBuilder,
+) -> Result<(), JsonReadError> {
+    let layer_is_folder = layer.size.is_some();
+
+    if !layer_is_folder {
+        let layer_builder = track_builder
+            .layer_group()
+            .add_layer(layer.id, index)?
+            .index(index)
+            .name(layer.name.to_string())
+            .visible(layer.visible);
+
+        if let Some(editable) = layer.editable {
+            layer_builder.editable(editable);
+        }
+
+        if let Some(FaultyU32::Valid(valid_folder_id)) = &layer.folder_id {
+            layer_builder.folder_id(Some(*valid_folder_id));
+        }
+    } else {
+        let layer_folder_builder = track_builder
+            .layer_group()
+            .add_layer_folder(layer.id, index)?
+            .index(index)
+            .name(layer.name.to_string())
+            .visible(layer.visible);
+
+        if let Some(editable) = layer.editable {
+            layer_folder_builder.editable(editable);
+        }
+
+        if let Some(size) = layer.size {
+            layer_folder_builder.size(size);
+        }
+    }
+
+    Ok(())
+}
+
+fn read_time_trigger(
+    trigger: LRAJsonTrigger,
+    track_builder: &mut TrackBuilder,
+) -> Result<(), JsonReadError> {
+    match trigger.trigger_type {
+        0 => {
+            // Zoom
+            let target_zoom = from_lra_zoom(trigger.zoom_target);
+            let start_frame = trigger.start;
+            let end_frame = trigger.end;
+            let zoom_event = CameraZoomEvent::new(target_zoom);
+            let frame_bounds = FrameBoundsTrigger::new(start_frame, end_frame);
+            track_builder
+                .camera_zoom_group()
+                .add_trigger()
+                .trigger(frame_bounds)
+                .event(zoom_event);
+        }
+        1 => {
+            // Background Color
+            let red = extract_u8(&trigger.background_red, "background red")?;
+            let green = extract_u8(&trigger.background_green, "background green")?;
+            let blue = extract_u8(&trigger.background_blue, "background blue")?;
+            let start_frame = trigger.start;
+            let end_frame = trigger.end;
+            let bg_color_event = BackgroundColorEvent::new(RGBColor::new(red, green, blue));
+            let frame_bounds = FrameBoundsTrigger::new(start_frame, end_frame);
+            track_builder
+                .background_color_group()
+                .add_trigger()
+                .trigger(frame_bounds)
+                .event(bg_color_event);
+        }
+        2 => {
+            // Line Color
+            let red = extract_u8(&trigger.line_red, "line red")?;
+            let green = extract_u8(&trigger.line_green, "line green")?;
+            let blue = extract_u8(&trigger.line_blue, "line blue")?;
+            let start_frame = trigger.start;
+            let end_frame = trigger.end;
+            let line_color_event = LineColorEvent::new(RGBColor::new(red, green, blue));
+            let frame_bounds = FrameBoundsTrigger::new(start_frame, end_frame);
+            track_builder
+                .line_color_group()
+                .add_trigger()
+                .trigger(frame_bounds)
+                .event(line_color_event);
+        }
+        other => {
+            return Err(JsonReadError::InvalidData {
+                name: "trigger type",
+                value: other.to_string(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+// Scan the JSON text for what the read limits bound, before anything is parsed from it
+//
+// Invalid JSON is left for the parser to report, so the scan only needs to be right for valid
+// text. Nesting depth counts the top level object as depth 0.
+fn check_limits(json_string: &str, budget: &mut Budget) -> Result<(), JsonReadError> {
+    let bytes = json_string.as_bytes();
+    let at = |offset: usize| ErrorContext::at_offset(u64::try_from(offset).unwrap_or(u64::MAX));
+    // Open arrays and objects, and whether each holds the lines of the track
+    let mut open: Vec<bool> = vec![];
+    let mut last_string = (0, 0);
+    let mut line_count = 0;
+    let mut in_line = false;
+    let mut offset = 0;
+
+    while let Some(&byte) = bytes.get(offset) {
+        // Each value directly in a line array is a line
+        if open.last() == Some(&true)
+            && !in_line
+            && !matches!(byte, b',' | b']')
+            && !byte.is_ascii_whitespace()
+        {
+            line_count += 1;
+            in_line = true;
+            budget
+                .expect_lines(line_count)
+                .map_err(|err| JsonReadError::from(err).with_context(at(offset)))?;
+        }
+
+        match byte {
+            b'"' => {
+                let (end, length) = scan_string(bytes, offset + 1);
+                budget
+                    .check_string_length(length)
+                    .map_err(|err| JsonReadError::from(err).with_context(at(offset)))?;
+                last_string = (offset + 1, end);
+                offset = end;
+            }
+            b'[' | b'{' => {
+                // The last string before an array in the top level object is its key
+                let key = json_string.get(last_string.0..last_string.1);
+                open.push(
+                    byte == b'[' && open.len() == 1 && matches!(key, Some("lines" | "linesArray")),
+                );
+                budget
+                    .check_depth(open.len() - 1)
+                    .map_err(|err| JsonReadError::from(err).with_context(at(offset)))?;
+            }
+            b']' | b'}' => {
+                open.pop();
+            }
+            b',' => in_line = false,
+            _ => {}
+        }
+
+        offset += 1;
+    }
+
+    Ok(())
+}
+
+// Find the closing quote of a string starting at `start`, along with the length of the string
+// once its escapes are decoded
+fn scan_string(bytes: &[u8], start: usize) -> (usize, usize) {
+    let hex = |offset: usize| {
+        bytes
+            .get(offset..offset + 4)
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+    };
+    let mut offset = start;
+    let mut length = 0;
+
+    while let Some(&byte) = bytes.get(offset) {
+        match byte {
+            b'"' => return (offset, length),
+            b'\\' if bytes.get(offset + 1) == Some(&b'u') => {
+                let code = hex(offset + 2).unwrap_or(0);
+                let low = (bytes.get(offset + 6..offset + 8) == Some(b"\\u"))
+                    .then(|| hex(offset + 8))
+                    .flatten();
+                match (code, low) {
+                    (0xD800..=0xDBFF, Some(0xDC00..=0xDFFF)) => {
+                        length += 4;
+                        offset += 12;
+                    }
+                    _ => {
+                        length += char::from_u32(code).map_or(3, char::len_utf8);
+                        offset += 6;
+                    }
                 }
-                1 => {
-                    // Background Color
-                    let red = extract_u8(&trigger.background_red, "background red")?;
-                    let green = extract_u8(&trigger.background_green, "background green")?;
-                    let blue = extract_u8(&trigger.background_blue, "background blue")?;
-                    let start_frame = trigger.start;
-                    let end_frame = trigger.end;
-                    let bg_color_event = BackgroundColorEvent::new(RGBColor::new(red, green, blue));
-                    let frame_bounds = FrameBoundsTrigger::new(start_frame, end_frame);
-                    track_builder
-                        .background_color_group()
-                        .add_trigger()
-                        .trigger(frame_bounds)
-                        .event(bg_color_event);
+                continue;
+            }
+            b'\\' => {
+                length += 1;
+                offset += 2;
+                continue;
+            }
+            _ => length += 1,
+        }
+        offset += 1;
+    }
+
+    (offset, length)
+}
+
+// Deserialize a track again with the broken values left out, warning about each one
+//
+// Broken elements are dropped from the arrays that hold lines, layers, riders and triggers, and
+// any other broken property is dropped entirely.
+fn read_repaired(
+    json_string: &str,
+    recovery: &mut Recovery,
+) -> Result<JsonTrack, serde_json::Error> {
+    let mut track: Value = serde_json::from_str(json_string)?;
+
+    if let Some(properties) = track.as_object_mut() {
+        let keys: Vec<String> = properties.keys().cloned().collect();
+
+        for key in keys {
+            let Some(value) = properties.get_mut(&key) else {
+                continue;
+            };
+
+            let mut probe = Map::new();
+            probe.insert("version".to_string(), Value::from("6.2"));
+            probe.insert(key.clone(), value.clone());
+            let probed = serde_json::from_value::<JsonTrack>(Value::Object(probe));
+            if probed.is_ok() || key == "version" {
+                continue;
+            }
+
+            match (key.as_str(), value.as_array_mut()) {
+                ("lines", Some(items)) => {
+                    retain_valid::<JsonLine>(&key, items, "Skipped line", recovery)
                 }
-                2 => {
-                    // Line Color
-                    let red = extract_u8(&trigger.line_red, "line red")?;
-                    let green = extract_u8(&trigger.line_green, "line green")?;
-                    let blue = extract_u8(&trigger.line_blue, "line blue")?;
-                    let start_frame = trigger.start;
-                    let end_frame = trigger.end;
-                    let line_color_event = LineColorEvent::new(RGBColor::new(red, green, blue));
-                    let frame_bounds = FrameBoundsTrigger::new(start_frame, end_frame);
-                    track_builder
-                        .line_color_group()
-                        .add_trigger()
-                        .trigger(frame_bounds)
-                        .event(line_color_event);
+                ("linesArray", Some(items)) => {
+                    retain_valid::<LRAJsonArrayLine>(&key, items, "Skipped line", recovery)
                 }
-                other => {
-                    return Err(JsonReadError::InvalidData {
-                        name: "trigger type",
-                        value: other.to_string(),
-                    });
+                ("layers", Some(items)) => {
+                    retain_valid::<JsonLayer>(&key, items, "Skipped layer", recovery)
+                }
+                ("riders", Some(items)) => {
+                    retain_valid::<JsonRider>(&key, items, "Skipped rider", recovery)
+                }
+                ("triggers", Some(items)) => retain_valid::<LRAJsonLegacyZoomTrigger>(
+                    &key,
+                    items,
+                    "Skipped trigger",
+                    recovery,
+                ),
+                ("gameTriggers", Some(items)) => {
+                    retain_valid::<LRAJsonTrigger>(&key, items, "Skipped trigger", recovery)
+                }
+                _ => {
+                    recovery.recover(probed, "Skipped property", || {
+                        ErrorContext::at_path(key.clone())
+                    })?;
+                    properties.remove(&key);
                 }
             }
         }
     }
 
-    Ok(track_builder.build()?)
+    serde_json::from_value(track)
+}
+
+// Drop the elements of an array that fail to deserialize
+fn retain_valid<T: DeserializeOwned>(
+    key: &str,
+    items: &mut Vec<Value>,
+    action: &str,
+    recovery: &mut Recovery,
+) {
+    let mut index = 0;
+
+    items.retain(|item| {
+        let element = serde_json::from_value::<T>(item.clone());
+        let path = format!("{}[{}]", key, index);
+        index += 1;
+        matches!(
+            recovery.recover(element, action, || ErrorContext::at_path(path)),
+            Ok(Some(_))
+        )
+    });
+}
+
+// Find where a JSON track failed to parse
+//
+// Serde only reports the line and column, so for data errors the value is deserialized again
+// one property and element at a time to find the path of the broken value.
+fn locate_serde_error(json_string: &str, err: serde_json::Error) -> JsonReadError {
+    let offset = byte_offset(json_string, err.line(), err.column());
+    let path = if err.is_data() {
+        serde_json::from_str::<Value>(json_string)
+            .ok()
+            .and_then(|value| locate_invalid_value(&value))
+    } else {
+        None
+    };
+
+    JsonReadError::from(err).with_context(ErrorContext::new(offset, path))
+}
+
+fn locate_invalid_value(track: &Value) -> Option<String> {
+    let properties = track.as_object()?;
+
+    for (key, value) in properties {
+        // Probe each property on its own, alongside the required version
+        let mut probe = Map::new();
+        probe.insert("version".to_string(), Value::from("6.2"));
+        probe.insert(key.clone(), value.clone());
+        if serde_json::from_value::<JsonTrack>(Value::Object(probe)).is_ok() {
+            continue;
+        }
+
+        let items = value.as_array().map(Vec::as_slice).unwrap_or_default();
+        let index = match key.as_str() {
+            "lines" => first_invalid::<JsonLine>(items),
+            "linesArray" => first_invalid::<LRAJsonArrayLine>(items),
+            "layers" => first_invalid::<JsonLayer>(items),
+            "riders" => first_invalid::<JsonRider>(items),
+            "triggers" => first_invalid::<LRAJsonLegacyZoomTrigger>(items),
+            "gameTriggers" => first_invalid::<LRAJsonTrigger>(items),
+            _ => None,
+        };
+
+        return Some(match index {
+            Some(index) => format!("{}[{}]", key, index),
+            None => key.clone(),
+        });
+    }
+
+    None
+}
+
+fn first_invalid<T: DeserializeOwned>(items: &[Value]) -> Option<usize> {
+    items
+        .iter()
+        .position(|item| serde_json::from_value::<T>(item.clone()).is_err())
+}
+
+// Serde counts lines and columns from 1, and reports 0 when the error has no position
+fn byte_offset(json_string: &str, line: usize, column: usize) -> Option<u64> {
+    if line == 0 {
+        return None;
+    }
+
+    let line_start: usize = json_string
+        .split_inclusive('\n')
+        .take(line - 1)
+        .map(str::len)
+        .sum();
+    u64::try_from(line_start + column.saturating_sub(1)).ok()
 }
 
 fn extract_u8(value: &Option<FaultyU32>, field: &'static str) -> Result<u8, JsonReadError> {
@@ -4,17 +4,65 @@ use super::LRAJsonArrayLine;
 use serde::{
     Deserialize, Deserializer, Serialize,
     de::{Error as DeError, SeqAccess, Visitor},
-    ser::Error,
+    ser::SerializeSeq,
 };
 
 impl Serialize for LRAJsonArrayLine {
-    fn serialize<S>(&self, _serializer: S) -> Result<S::Ok, S::Error>
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        Err(S::Error::custom(
-            "LRAJsonArrayLine not intended to be serialized",
-        ))
+        match self {
+            LRAJsonArrayLine::Standard(id, x1, y1, x2, y2, extended, flipped) => {
+                let mut seq = serializer.serialize_seq(Some(8))?;
+                seq.serialize_element(&0u8)?;
+                seq.serialize_element(id)?;
+                seq.serialize_element(x1)?;
+                seq.serialize_element(y1)?;
+                seq.serialize_element(x2)?;
+                seq.serialize_element(y2)?;
+                seq.serialize_element(extended)?;
+                seq.serialize_element(flipped)?;
+                seq.end()
+            }
+            LRAJsonArrayLine::Acceleration(
+                id,
+                x1,
+                y1,
+                x2,
+                y2,
+                extended,
+                flipped,
+                _,
+                _,
+                multiplier,
+            ) => {
+                let mut seq = serializer.serialize_seq(Some(11))?;
+                seq.serialize_element(&1u8)?;
+                seq.serialize_element(id)?;
+                seq.serialize_element(x1)?;
+                seq.serialize_element(y1)?;
+                seq.serialize_element(x2)?;
+                seq.serialize_element(y2)?;
+                seq.serialize_element(extended)?;
+                seq.serialize_element(flipped)?;
+                // Unknown slots, which LRA fills with -1
+                seq.serialize_element(&-1)?;
+                seq.serialize_element(&-1)?;
+                seq.serialize_element(multiplier)?;
+                seq.end()
+            }
+            LRAJsonArrayLine::Scenery(id, x1, y1, x2, y2) => {
+                let mut seq = serializer.serialize_seq(Some(6))?;
+                seq.serialize_element(&2u8)?;
+                seq.serialize_element(id)?;
+                seq.serialize_element(x1)?;
+                seq.serialize_element(y1)?;
+                seq.serialize_element(x2)?;
+                seq.serialize_element(y2)?;
+                seq.end()
+            }
+        }
     }
 }
 
@@ -1,12 +1,21 @@
 use crate::{
     formats::json::{
-        FaultyBool, FaultyU32, JsonLayer, JsonLine, JsonReadError, JsonRider, JsonTrack,
-        LAYER_TYPE_FOLDER, LAYER_TYPE_LAYER, V2,
+        FaultyBool, FaultyU32, JsonDialect, JsonLayer, JsonLine, JsonRider, JsonTrack,
+        JsonWriteError, JsonWriteOptions, LAYER_TYPE_FOLDER, LAYER_TYPE_LAYER, LRAJsonArrayLine,
+        V2,
     },
-    track::{GridVersion, RemountVersion, Track},
+    track::{GridVersion, LineType, RemountVersion, Track, line::line_view::Line},
+    util::scale_factor::to_lra_zoom,
 };
 
-pub fn write(track: &Track) -> Result<Vec<u8>, JsonReadError> {
+pub fn write(track: &Track) -> Result<Vec<u8>, JsonWriteError> {
+    write_with_options(track, &JsonWriteOptions::default())
+}
+
+pub fn write_with_options(
+    track: &Track,
+    options: &JsonWriteOptions,
+) -> Result<Vec<u8>, JsonWriteError> {
     let version = match track.metadata().grid_version() {
         GridVersion::V6_0 => String::from("6.0"),
         GridVersion::V6_1 => String::from("6.1"),
@@ -17,54 +26,31 @@ pub fn write(track: &Track) -> Result<Vec<u8>, JsonReadError> {
     let mut layers = Vec::<JsonLayer>::new();
     let mut riders = Vec::<JsonRider>::new();
 
-    for line in track.line_group().standard_lines() {
+    for line in track.line_group().iter() {
+        let (start, end) = line.endpoints();
+        let line_type = match line.line_type() {
+            LineType::Standard => 0,
+            LineType::Acceleration => 1,
+            LineType::Scenery => 2,
+        };
+        // Scenery lines have no physics flags to write
+        let flag = |value: bool| {
+            (line.line_type() != LineType::Scenery).then_some(FaultyBool::BoolRep(value))
+        };
         lines.push(JsonLine {
             id: line.id(),
-            line_type: 0,
-            x1: line.x1(),
-            y1: line.y1(),
-            x2: line.x2(),
-            y2: line.y2(),
-            flipped: Some(FaultyBool::BoolRep(line.flipped())),
-            left_ext: Some(FaultyBool::BoolRep(line.left_extension())),
-            right_ext: Some(FaultyBool::BoolRep(line.right_extension())),
-            extended: None,
-            multiplier: None,
-            width: None,
-        });
-    }
-
-    for line in track.line_group().acceleration_lines() {
-        lines.push(JsonLine {
-            id: line.id(),
-            line_type: 1,
-            x1: line.x1(),
-            y1: line.y1(),
-            x2: line.x2(),
-            y2: line.y2(),
-            flipped: Some(FaultyBool::BoolRep(line.flipped())),
-            left_ext: Some(FaultyBool::BoolRep(line.left_extension())),
-            right_ext: Some(FaultyBool::BoolRep(line.right_extension())),
+            line_type,
+            x1: start.x(),
+            y1: start.y(),
+            x2: end.x(),
+            y2: end.y(),
+            flipped: flag(line.flipped()),
+            left_ext: flag(line.left_extension()),
+            right_ext: flag(line.right_extension()),
             extended: None,
             multiplier: line.multiplier(),
-            width: None,
-        });
-    }
-
-    for line in track.line_group().scenery_lines() {
-        lines.push(JsonLine {
-            id: line.id(),
-            line_type: 2,
-            x1: line.x1(),
-            y1: line.y1(),
-            x2: line.x2(),
-            y2: line.y2(),
-            flipped: None,
-            left_ext: None,
-            right_ext: None,
-            extended: None,
-            multiplier: None,
             width: line.width(),
+            layer: line.layer(),
         });
     }
 
@@ -192,7 +178,9 @@ pub fn write(track: &Track) -> Result<Vec<u8>, JsonReadError> {
     let script = Some(track.metadata().script().clone().unwrap_or("".to_string()));
     let duration = Some(track.metadata().duration().unwrap_or(1200));
 
-    let track = JsonTrack {
+    let extra = track.extras().json_properties().clone();
+
+    let mut track_json = JsonTrack {
         label,
         version,
         start_pos,
@@ -218,9 +206,77 @@ pub fn write(track: &Track) -> Result<Vec<u8>, JsonReadError> {
         gravity_well_size: None,
         x_gravity: None,
         y_gravity: None,
+        extra,
     };
 
-    let track_string = serde_json::to_string(&track)?;
+    if options.dialect == JsonDialect::LineRiderAdvanced {
+        write_lra_properties(track, &mut track_json);
+    }
+
+    let track_string = serde_json::to_string(&track_json)?;
 
     Ok(track_string.into_bytes())
 }
+
+// Replace the line objects with LRA line arrays and fill in the LRA track properties
+fn write_lra_properties(track: &Track, track_json: &mut JsonTrack) {
+    let mut line_array = vec![];
+
+    for line in track.line_group().iter() {
+        let (start, end) = line.endpoints();
+        let (x1, y1, x2, y2) = (start.x(), start.y(), end.x(), end.y());
+        let ext = extension_flags(line.left_extension(), line.right_extension());
+
+        line_array.push(match line {
+            Line::Standard(line) => {
+                LRAJsonArrayLine::Standard(line.id(), x1, y1, x2, y2, ext, line.flipped())
+            }
+            Line::Acceleration(line) => {
+                // LRA only supports whole multipliers
+                let multiplier = line.multiplier().unwrap_or(1.0).round().clamp(0.0, 255.0) as u32;
+                LRAJsonArrayLine::Acceleration(
+                    line.id(),
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    ext,
+                    line.flipped(),
+                    (),
+                    (),
+                    multiplier,
+                )
+            }
+            Line::Scenery(line) => LRAJsonArrayLine::Scenery(line.id(), x1, y1, x2, y2),
+        });
+    }
+
+    let metadata = track.metadata();
+
+    track_json.lines = None;
+    track_json.line_array = Some(line_array);
+    track_json.start_zoom = metadata.start_zoom().map(to_lra_zoom);
+    track_json.zero_start = Some(metadata.zero_velocity_start_riders());
+    track_json.gravity_well_size = metadata.gravity_well_size();
+
+    if let Some(gravity) = metadata.start_gravity() {
+        track_json.x_gravity = Some(gravity.x() as f32);
+        track_json.y_gravity = Some(gravity.y() as f32);
+    }
+
+    if let Some(color) = metadata.start_line_color() {
+        track_json.line_color_red = Some(u32::from(color.red()));
+        track_json.line_color_green = Some(u32::from(color.green()));
+        track_json.line_color_blue = Some(u32::from(color.blue()));
+    }
+
+    if let Some(color) = metadata.start_background_color() {
+        track_json.background_color_red = Some(u32::from(color.red()));
+        track_json.background_color_green = Some(u32::from(color.green()));
+        track_json.background_color_blue = Some(u32::from(color.blue()));
+    }
+}
+
+fn extension_flags(left_extension: bool, right_extension: bool) -> u8 {
+    u8::from(left_extension) | (u8::from(right_extension) << 1)
+}
@@ -0,0 +1,183 @@
+use std::fmt;
+use thiserror::Error;
+
+/// Rough size of a line in the track model, including its id and adjacency
+const LINE_ALLOCATION: usize = 128;
+
+/// Bounds on what readers accept, so that hostile files cannot exhaust memory or the stack
+///
+/// The defaults fit every real track by a wide margin. Readers fail with a `LimitExceeded` error
+/// when a file goes past any of them, including in lenient mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadLimits {
+    /// Number of lines in the track
+    pub max_lines: usize,
+    /// Length of a single string, in bytes
+    pub max_string_length: usize,
+    /// Depth of nested values in structured formats
+    pub max_nesting_depth: usize,
+    /// Estimated number of bytes allocated for everything read
+    pub max_allocation: usize,
+}
+
+impl ReadLimits {
+    pub fn unlimited() -> Self {
+        Self {
+            max_lines: usize::MAX,
+            max_string_length: usize::MAX,
+            max_nesting_depth: usize::MAX,
+            max_allocation: usize::MAX,
+        }
+    }
+}
+
+impl Default for ReadLimits {
+    fn default() -> Self {
+        Self {
+            max_lines: 10_000_000,
+            max_string_length: 16 * 1024 * 1024,
+            max_nesting_depth: 64,
+            max_allocation: 1024 * 1024 * 1024,
+        }
+    }
+}
+
+/// One of the bounds in `ReadLimits`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadLimit {
+    Lines,
+    StringLength,
+    NestingDepth,
+    Allocation,
+}
+
+impl fmt::Display for ReadLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadLimit::Lines => write!(f, "line count"),
+            ReadLimit::StringLength => write!(f, "string length"),
+            ReadLimit::NestingDepth => write!(f, "nesting depth"),
+            ReadLimit::Allocation => write!(f, "allocation size"),
+        }
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("The {limit} of {value} is over the read limit of {max}")]
+pub struct LimitExceeded {
+    limit: ReadLimit,
+    value: usize,
+    max: usize,
+}
+
+impl LimitExceeded {
+    pub fn limit(&self) -> ReadLimit {
+        self.limit
+    }
+
+    /// The value the file asked for
+    pub fn value(&self) -> usize {
+        self.value
+    }
+
+    /// The configured maximum
+    pub fn max(&self) -> usize {
+        self.max
+    }
+}
+
+/// Keeps count of what a read has used so far against its limits
+#[derive(Debug)]
+pub(crate) struct Budget {
+    limits: ReadLimits,
+    lines: usize,
+    allocated: usize,
+    depth: usize,
+    exceeded: bool,
+}
+
+impl Budget {
+    pub fn new(limits: &ReadLimits) -> Self {
+        Self {
+            limits: *limits,
+            lines: 0,
+            allocated: 0,
+            depth: 0,
+            exceeded: false,
+        }
+    }
+
+    /// Whether a limit has been hit, after which reading must stop even in lenient mode
+    pub fn is_exceeded(&self) -> bool {
+        self.exceeded
+    }
+
+    /// Check a line count declared ahead of the lines, before looping over it
+    pub fn expect_lines(&mut self, count: usize) -> Result<(), LimitExceeded> {
+        let lines = self.lines.saturating_add(count);
+        self.check(ReadLimit::Lines, lines, self.limits.max_lines)
+    }
+
+    pub fn add_line(&mut self) -> Result<(), LimitExceeded> {
+        self.lines += 1;
+        self.check(ReadLimit::Lines, self.lines, self.limits.max_lines)?;
+        self.allocate(LINE_ALLOCATION)
+    }
+
+    pub fn string(&mut self, length: usize) -> Result<(), LimitExceeded> {
+        self.check_string_length(length)?;
+        self.allocate(length)
+    }
+
+    /// Check the length of a string that has already been charged for
+    pub fn check_string_length(&mut self, length: usize) -> Result<(), LimitExceeded> {
+        self.check(
+            ReadLimit::StringLength,
+            length,
+            self.limits.max_string_length,
+        )
+    }
+
+    pub fn allocate(&mut self, bytes: usize) -> Result<(), LimitExceeded> {
+        self.allocated = self.allocated.saturating_add(bytes);
+        self.check(
+            ReadLimit::Allocation,
+            self.allocated,
+            self.limits.max_allocation,
+        )
+    }
+
+    /// Bytes allocated so far, to find out how much reading a value took
+    pub fn allocated(&self) -> usize {
+        self.allocated
+    }
+
+    /// Go one level deeper into nested values, which must be paired with `leave`
+    pub fn enter(&mut self) -> Result<(), LimitExceeded> {
+        self.depth += 1;
+        self.check_depth(0)
+    }
+
+    pub fn leave(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+    }
+
+    /// Check that a value nested `depth` levels below the current one fits
+    pub fn check_depth(&mut self, depth: usize) -> Result<(), LimitExceeded> {
+        let depth = self.depth.saturating_add(depth);
+        self.check(
+            ReadLimit::NestingDepth,
+            depth,
+            self.limits.max_nesting_depth,
+        )
+    }
+
+    fn check(&mut self, limit: ReadLimit, value: usize, max: usize) -> Result<(), LimitExceeded> {
+        if value <= max {
+            return Ok(());
+        }
+
+        self.exceeded = true;
+        Err(LimitExceeded { limit, value, max })
+    }
+}
@@ -2,24 +2,222 @@
 
 mod amf0;
 mod error;
+pub mod raw;
 mod reader;
 mod writer;
 
 pub use amf0::{Amf0DeserializationError, Amf0SerializationError};
 pub use error::{SolReadError, SolWriteError};
-pub use reader::read;
-pub use writer::write;
+pub(crate) use reader::read_with_recovery;
+pub use reader::{read, read_from_raw, read_lenient, read_with_limits};
+pub use writer::{write, write_with_options};
 
 use byteorder::{BigEndian, ReadBytesExt};
 use std::io::{Cursor, Seek};
 
+/// Options for reading SOL tracks
+#[derive(Debug, Clone, Default)]
+pub struct SolReadOptions {
+    /// Index of the track to read from the file, the first track if not set
+    pub track_index: Option<u32>,
+}
+
+/// Layout of a SOL track, which differs between the flash builds that wrote it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SolFlavor {
+    /// Beta 1, which predates the `version` property and only has grid version 6.0
+    Beta1,
+    /// Beta 2 versions 6.0 to 6.2
+    #[default]
+    Beta2,
+    /// Version 6.7, which leaves line extensions undefined
+    V6_7,
+    /// Written by Line Rider Advanced, with an object start position and boolean flipped flags
+    Lra,
+}
+
+/// Options for writing SOL tracks
+#[derive(Debug, Clone, Default)]
+pub struct SolWriteOptions {
+    /// Layout to write, the flavor the track was read as (or Beta 2) if not set
+    pub flavor: Option<SolFlavor>,
+}
+
 /// Retrieve the number of tracks an sol file contains
 pub fn get_track_count(data: &[u8]) -> u32 {
     let mut cursor = Cursor::new(data);
 
     // HACK: We assume header size is constant, and track list length will always be at 0x2C - 0x2F
     let _ = cursor.seek(std::io::SeekFrom::Start(0x2C));
-    let num_tracks = cursor.read_u32::<BigEndian>().unwrap_or(0);
+    cursor.read_u32::<BigEndian>().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::{
+        SolFlavor, SolReadError, SolWriteError, SolWriteOptions,
+        raw::{Amf0Value, read_raw, write_raw},
+        read, read_from_raw, read_lenient, write, write_with_options,
+    };
+    use crate::track::{GridVersion, TrackBuilder, Vec2};
+    use indexmap::IndexMap;
+    use std::collections::HashMap;
+
+    #[test]
+    fn round_trip_keeps_unknown_track_properties() {
+        let mut track_builder = TrackBuilder::default();
+        track_builder.metadata().grid_version(GridVersion::V6_2);
+        track_builder.line_group().add_standard_line(
+            1,
+            (Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0)),
+            false,
+            false,
+            false,
+        );
+        let track = track_builder.build().unwrap();
+
+        // Patch in a property that a flash mod would save
+        let mut track_list = read_raw(write(&track).unwrap()).unwrap();
+        if let Amf0Value::ECMAArray(tracks) = &mut track_list
+            && let Some(Amf0Value::Object(sol_track)) = tracks.get_mut("0")
+        {
+            let mut settings = IndexMap::new();
+            settings.insert("speed".to_string(), Amf0Value::Number(2.0));
+            sol_track.insert("modSettings".to_string(), Amf0Value::Object(settings));
+        }
+        let patched = write_raw(&track_list).unwrap();
+
+        let track = read(patched.clone(), None).unwrap();
+        assert!(track.extras().sol_properties().contains_key("modSettings"));
+        let from_raw = read_from_raw(&track_list, Some(0)).unwrap();
+        assert_eq!(write(&from_raw).unwrap(), patched);
+        assert_eq!(write(&track).unwrap(), patched);
+    }
+
+    #[test]
+    fn flavors_are_detected_and_written_back() {
+        let mut track_builder = TrackBuilder::default();
+        track_builder.metadata().grid_version(GridVersion::V6_0);
+        track_builder.line_group().add_standard_line(
+            1,
+            (Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0)),
+            true,
+            true,
+            false,
+        );
+        let track = track_builder.build().unwrap();
+
+        for flavor in [
+            SolFlavor::Beta1,
+            SolFlavor::Beta2,
+            SolFlavor::V6_7,
+            SolFlavor::Lra,
+        ] {
+            let options = SolWriteOptions {
+                flavor: Some(flavor),
+            };
+            let bytes = write_with_options(&track, &options).unwrap();
+            let track = read(bytes.clone(), None).unwrap();
+            assert_eq!(track.extras().sol_flavor(), Some(flavor));
+            assert_eq!(write(&track).unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn beta_1_only_stores_grid_version_6_0() {
+        let mut track_builder = TrackBuilder::default();
+        track_builder.metadata().grid_version(GridVersion::V6_2);
+        let track = track_builder.build().unwrap();
+
+        let options = SolWriteOptions {
+            flavor: Some(SolFlavor::Beta1),
+        };
+        let Err(SolWriteError::UnsupportedGridVersion { .. }) =
+            write_with_options(&track, &options)
+        else {
+            panic!("Expected Beta 1 to reject grid version 6.2");
+        };
+    }
+
+    #[test]
+    fn truncated_files_are_read_errors() {
+        let mut track_builder = TrackBuilder::default();
+        track_builder.metadata().grid_version(GridVersion::V6_2);
+        let bytes = write(&track_builder.build().unwrap()).unwrap();
+
+        // Cut off right after the track list name, before any track data
+        let data = bytes[..43].to_vec();
+        let Err(SolReadError::Io(err)) = read(data.clone(), None) else {
+            panic!("Expected a file without track data to fail");
+        };
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(read_lenient(data, None).is_err());
+
+        for length in 0..bytes.len() {
+            assert!(read(bytes[..length].to_vec(), None).is_err());
+            assert!(read_lenient(bytes[..length].to_vec(), None).is_err());
+        }
+    }
+
+    #[test]
+    fn header_sizes_are_not_trusted() {
+        let mut track_builder = TrackBuilder::default();
+        track_builder.metadata().grid_version(GridVersion::V6_2);
+        let mut bytes = write(&track_builder.build().unwrap()).unwrap();
+
+        bytes[2..6].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(read(bytes, None).is_ok());
+    }
+
+    #[test]
+    fn rewritten_sample_keeps_line_links() {
+        let data = include_bytes!("../../../samples/Silk_Road.sol").to_vec();
+        let rewritten = write(&read(data.clone(), Some(0)).unwrap()).unwrap();
+
+        // Slots 6 and 7 of every line, keyed by the line id in slot 8
+        let line_links = |data: Vec<u8>| {
+            let track_list = read_raw(data).unwrap();
+            let track = track_list.as_object_properties().unwrap()["0"]
+                .as_object_properties()
+                .unwrap()
+                .clone();
+            track["data"]
+                .as_object_properties()
+                .unwrap()
+                .values()
+                .map(|line| {
+                    let line = line.as_object_properties().unwrap();
+                    let id = line["8"].as_number().unwrap() as u32;
+                    (id, (line["6"].clone(), line["7"].clone()))
+                })
+                .collect::<HashMap<_, _>>()
+        };
+
+        assert_eq!(line_links(rewritten), line_links(data));
+    }
+
+    #[test]
+    fn referenced_objects_are_written_back_as_copies() {
+        // The track list is complex value 0, the track 1, and the shared object 2
+        let mut shared = IndexMap::new();
+        shared.insert("x".to_string(), Amf0Value::Number(1.0));
+        let mut sol_track = IndexMap::new();
+        sol_track.insert("shared".to_string(), Amf0Value::Object(shared));
+        sol_track.insert("copy".to_string(), Amf0Value::Reference(2));
+        let mut tracks = IndexMap::new();
+        tracks.insert("0".to_string(), Amf0Value::Object(sol_track));
+        let bytes = write_raw(&Amf0Value::ECMAArray(tracks)).unwrap();
+
+        let track_list = read_raw(bytes.clone()).unwrap();
+        let sol_track = track_list.as_object_properties().unwrap()["0"]
+            .as_object_properties()
+            .unwrap();
+        assert_eq!(sol_track["copy"], sol_track["shared"]);
 
-    num_tracks
+        let rewritten = write_raw(&track_list).unwrap();
+        assert!(rewritten.len() > bytes.len());
+        assert_eq!(read_raw(rewritten).unwrap(), track_list);
+    }
 }
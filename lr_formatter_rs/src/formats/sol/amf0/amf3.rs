@@ -0,0 +1,248 @@
+//! Reads AMF3 values that follow an AVM+ marker, keeping their encoded bytes as is
+
+use super::errors::Amf0DeserializationError;
+use crate::formats::Budget;
+use byteorder::ReadBytesExt;
+use std::io::Read;
+
+mod markers {
+    pub const UNDEFINED_MARKER: u8 = 0;
+    pub const NULL_MARKER: u8 = 1;
+    pub const FALSE_MARKER: u8 = 2;
+    pub const TRUE_MARKER: u8 = 3;
+    pub const INTEGER_MARKER: u8 = 4;
+    pub const DOUBLE_MARKER: u8 = 5;
+    pub const STRING_MARKER: u8 = 6;
+    pub const XML_DOCUMENT_MARKER: u8 = 7;
+    pub const DATE_MARKER: u8 = 8;
+    pub const ARRAY_MARKER: u8 = 9;
+    pub const OBJECT_MARKER: u8 = 10;
+    pub const XML_MARKER: u8 = 11;
+    pub const BYTE_ARRAY_MARKER: u8 = 12;
+    pub const VECTOR_INT_MARKER: u8 = 13;
+    pub const VECTOR_UINT_MARKER: u8 = 14;
+    pub const VECTOR_DOUBLE_MARKER: u8 = 15;
+    pub const VECTOR_OBJECT_MARKER: u8 = 16;
+    pub const DICTIONARY_MARKER: u8 = 17;
+}
+
+struct Traits {
+    sealed_count: u32,
+    dynamic: bool,
+}
+
+struct Amf3Reader<'a, R: Read> {
+    bytes: &'a mut R,
+    out: &'a mut Vec<u8>,
+    traits: Vec<Traits>,
+    budget: &'a mut Budget,
+}
+
+// Reads a single AMF3 value, appending its encoded bytes to `out`
+//
+// The value is not decoded, only walked far enough to know where it ends. Its reference tables
+// start out empty, as each AVM+ switch begins a new AMF3 context.
+pub(super) fn read_value<R: Read>(
+    bytes: &mut R,
+    out: &mut Vec<u8>,
+    budget: &mut Budget,
+) -> Result<(), Amf0DeserializationError> {
+    let mut reader = Amf3Reader {
+        bytes,
+        out,
+        traits: vec![],
+        budget,
+    };
+    reader.value()
+}
+
+impl<R: Read> Amf3Reader<'_, R> {
+    fn value(&mut self) -> Result<(), Amf0DeserializationError> {
+        self.budget.enter()?;
+        self.value_contents()?;
+        self.budget.leave();
+        Ok(())
+    }
+
+    fn value_contents(&mut self) -> Result<(), Amf0DeserializationError> {
+        let marker = self.byte()?;
+
+        match marker {
+            markers::UNDEFINED_MARKER
+            | markers::NULL_MARKER
+            | markers::FALSE_MARKER
+            | markers::TRUE_MARKER => {}
+            markers::INTEGER_MARKER => {
+                self.u29()?;
+            }
+            markers::DOUBLE_MARKER => self.raw(8)?,
+            markers::STRING_MARKER => {
+                self.string()?;
+            }
+            markers::XML_DOCUMENT_MARKER | markers::XML_MARKER | markers::BYTE_ARRAY_MARKER => {
+                let header = self.u29()?;
+                if header & 1 == 1 {
+                    self.raw(u64::from(header >> 1))?;
+                }
+            }
+            markers::DATE_MARKER => {
+                let header = self.u29()?;
+                if header & 1 == 1 {
+                    self.raw(8)?;
+                }
+            }
+            markers::ARRAY_MARKER => {
+                let header = self.u29()?;
+                if header & 1 == 1 {
+                    self.dynamic_members()?;
+                    for _ in 0..header >> 1 {
+                        self.value()?;
+                    }
+                }
+            }
+            markers::OBJECT_MARKER => self.object()?,
+            markers::VECTOR_INT_MARKER | markers::VECTOR_UINT_MARKER => {
+                let header = self.u29()?;
+                if header & 1 == 1 {
+                    self.byte()?;
+                    self.raw(u64::from(header >> 1) * 4)?;
+                }
+            }
+            markers::VECTOR_DOUBLE_MARKER => {
+                let header = self.u29()?;
+                if header & 1 == 1 {
+                    self.byte()?;
+                    self.raw(u64::from(header >> 1) * 8)?;
+                }
+            }
+            markers::VECTOR_OBJECT_MARKER => {
+                let header = self.u29()?;
+                if header & 1 == 1 {
+                    self.byte()?;
+                    self.string()?;
+                    for _ in 0..header >> 1 {
+                        self.value()?;
+                    }
+                }
+            }
+            markers::DICTIONARY_MARKER => {
+                let header = self.u29()?;
+                if header & 1 == 1 {
+                    self.byte()?;
+                    for _ in 0..header >> 1 {
+                        self.value()?;
+                        self.value()?;
+                    }
+                }
+            }
+            _ => return Err(Amf0DeserializationError::UnknownAmf3Marker { marker }),
+        }
+
+        Ok(())
+    }
+
+    fn object(&mut self) -> Result<(), Amf0DeserializationError> {
+        let header = self.u29()?;
+
+        if header & 1 == 0 {
+            // Object reference
+            return Ok(());
+        }
+
+        let (sealed_count, dynamic) = if header & 2 == 0 {
+            let index = header >> 2;
+            let traits = self
+                .traits
+                .get(index as usize)
+                .ok_or(Amf0DeserializationError::InvalidAmf3TraitsReference { index })?;
+            (traits.sealed_count, traits.dynamic)
+        } else {
+            if header & 4 != 0 {
+                // Externalizable objects use a class specific encoding with no known length
+                return Err(Amf0DeserializationError::ExternalizableAmf3Object);
+            }
+
+            let traits = Traits {
+                sealed_count: header >> 4,
+                dynamic: header & 8 != 0,
+            };
+
+            // Class name, followed by the sealed member names
+            self.string()?;
+            for _ in 0..traits.sealed_count {
+                self.string()?;
+            }
+
+            let result = (traits.sealed_count, traits.dynamic);
+            self.traits.push(traits);
+            result
+        };
+
+        for _ in 0..sealed_count {
+            self.value()?;
+        }
+
+        if dynamic {
+            self.dynamic_members()?;
+        }
+
+        Ok(())
+    }
+
+    // Name and value pairs, ending with an empty name
+    fn dynamic_members(&mut self) -> Result<(), Amf0DeserializationError> {
+        while !self.string()? {
+            self.value()?;
+        }
+
+        Ok(())
+    }
+
+    // Returns whether the string was an inline empty string
+    fn string(&mut self) -> Result<bool, Amf0DeserializationError> {
+        let header = self.u29()?;
+
+        if header & 1 == 0 {
+            // String reference
+            return Ok(false);
+        }
+
+        let length = header >> 1;
+        self.raw(u64::from(length))?;
+        Ok(length == 0)
+    }
+
+    // Variable length unsigned 29 bit integer
+    fn u29(&mut self) -> Result<u32, Amf0DeserializationError> {
+        let mut value = 0;
+
+        for _ in 0..3 {
+            let byte = self.byte()?;
+            value = (value << 7) | u32::from(byte & 0x7F);
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Ok((value << 8) | u32::from(self.byte()?))
+    }
+
+    fn byte(&mut self) -> Result<u8, Amf0DeserializationError> {
+        let byte = self.bytes.read_u8()?;
+        self.out.push(byte);
+        Ok(byte)
+    }
+
+    fn raw(&mut self, length: u64) -> Result<(), Amf0DeserializationError> {
+        self.budget
+            .allocate(usize::try_from(length).unwrap_or(usize::MAX))?;
+        let read = self.bytes.by_ref().take(length).read_to_end(self.out)?;
+
+        if read as u64 != length {
+            return Err(Amf0DeserializationError::UnexpectedEof);
+        }
+
+        Ok(())
+    }
+}
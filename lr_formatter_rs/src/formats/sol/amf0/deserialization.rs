@@ -2,37 +2,58 @@
 //! License: See ./LICENSE-APACHE and ./LICENSE-MIT
 //! Modifications Copyright 2025 Tobias Bessler
 
-use super::{Amf0Value, errors::Amf0DeserializationError, markers};
+use super::{Amf0Value, amf3, errors::Amf0DeserializationError, markers};
+use crate::formats::{Budget, ReadLimits};
 use byteorder::{BigEndian, ReadBytesExt};
-use std::collections::HashMap;
-use std::io::Read;
+use indexmap::IndexMap;
+use std::{
+    io::{Cursor, Read},
+    mem,
+};
 
 struct ObjectProperty {
     label: String,
     value: Amf0Value,
 }
 
-// Turns any readable byte stream and converts it into an array of AMF0 values
-pub(in crate::formats::sol) fn deserialize<R: Read>(
-    bytes: &mut R,
+// A complex value that can be referenced, kept as the position it starts at so that only
+// referenced values are ever copied, with what it took to read so that a copy is charged the same
+struct Referenced {
+    position: u64,
+    allocated: usize,
+    depth: usize,
+}
+
+#[derive(Default)]
+struct References {
+    // Complex values get their reference index when they start, but can only be referenced
+    // once they have been read completely
+    values: Vec<Option<Referenced>>,
+    // Set while a referenced value is read again, since its complex values are numbered already
+    replaying: bool,
+}
+
+// Converts AMF0 bytes into an array of AMF0 values
+pub(in crate::formats::sol) fn deserialize(
+    bytes: &mut Cursor<&[u8]>,
+    budget: &mut Budget,
 ) -> Result<Vec<Amf0Value>, Amf0DeserializationError> {
-    let mut references = vec![];
+    let mut references = References::default();
     let mut results = vec![];
 
-    loop {
-        match read_next_value(bytes, &mut references)? {
-            Some(x) => results.push(x),
-            None => break,
-        };
+    while let Some(value) = read_next_value(bytes, &mut references, budget)? {
+        results.push(value);
     }
 
     Ok(results)
 }
 
-fn read_next_value<R: Read>(
-    bytes: &mut R,
-    references: &mut Vec<Amf0Value>,
+fn read_next_value(
+    bytes: &mut Cursor<&[u8]>,
+    references: &mut References,
+    budget: &mut Budget,
 ) -> Result<Option<Amf0Value>, Amf0DeserializationError> {
+    let position = bytes.position();
     let mut buffer: [u8; 1] = [0];
     let bytes_read = bytes.read(&mut buffer)?;
 
@@ -44,21 +65,49 @@ fn read_next_value<R: Read>(
         return Ok(None);
     }
 
+    budget.allocate(mem::size_of::<Amf0Value>())?;
+
     match buffer[0] {
         markers::BOOLEAN_MARKER => parse_bool(bytes).map(Some),
         markers::NULL_MARKER => parse_null().map(Some),
         markers::UNDEFINED_MARKER => parse_undefined().map(Some),
         markers::NUMBER_MARKER => parse_number(bytes).map(Some),
-        markers::OBJECT_MARKER => parse_object(bytes, references).map(Some),
-        markers::ECMA_ARRAY_MARKER => parse_ecma_array(bytes, references).map(Some),
-        markers::STRING_MARKER => parse_string(bytes).map(Some),
-        markers::STRICT_ARRAY_MARKER => parse_strict_array(bytes, references).map(Some),
-        markers::REFERENCE_MARKER => parse_reference(bytes, references).map(Some),
+        markers::OBJECT_MARKER => {
+            parse_complex(position, references, budget, |references, budget| {
+                parse_object(bytes, references, budget)
+            })
+            .map(Some)
+        }
+        markers::ECMA_ARRAY_MARKER => {
+            parse_complex(position, references, budget, |references, budget| {
+                parse_ecma_array(bytes, references, budget)
+            })
+            .map(Some)
+        }
+        markers::STRING_MARKER => parse_string(bytes, budget).map(Some),
+        markers::STRICT_ARRAY_MARKER => {
+            parse_complex(position, references, budget, |references, budget| {
+                parse_strict_array(bytes, references, budget)
+            })
+            .map(Some)
+        }
+        markers::REFERENCE_MARKER => parse_reference(bytes, references, budget).map(Some),
+        markers::DATE_MARKER => parse_date(bytes).map(Some),
+        markers::LONG_STRING_MARKER => parse_long_string(bytes, budget).map(Some),
+        markers::UNSUPPORTED_MARKER => Ok(Some(Amf0Value::Unsupported)),
+        markers::XML_DOCUMENT_MARKER => parse_xml_document(bytes, budget).map(Some),
+        markers::TYPED_OBJECT_MARKER => {
+            parse_complex(position, references, budget, |references, budget| {
+                parse_typed_object(bytes, references, budget)
+            })
+            .map(Some)
+        }
+        markers::AVM_PLUS_MARKER => parse_avm_plus(bytes, budget).map(Some),
         _ => Err(Amf0DeserializationError::UnknownMarker { marker: buffer[0] }),
     }
 }
 
-fn parse_number<R: Read>(bytes: &mut R) -> Result<Amf0Value, Amf0DeserializationError> {
+fn parse_number(bytes: &mut Cursor<&[u8]>) -> Result<Amf0Value, Amf0DeserializationError> {
     let number = bytes.read_f64::<BigEndian>()?;
     let value = Amf0Value::Number(number);
 
@@ -73,7 +122,7 @@ fn parse_undefined() -> Result<Amf0Value, Amf0DeserializationError> {
     Ok(Amf0Value::Undefined)
 }
 
-fn parse_bool<R: Read>(bytes: &mut R) -> Result<Amf0Value, Amf0DeserializationError> {
+fn parse_bool(bytes: &mut Cursor<&[u8]>) -> Result<Amf0Value, Amf0DeserializationError> {
     let value = bytes.read_u8()?;
 
     if value == 1 {
@@ -83,40 +132,151 @@ fn parse_bool<R: Read>(bytes: &mut R) -> Result<Amf0Value, Amf0DeserializationEr
     }
 }
 
-fn parse_string<R: Read>(bytes: &mut R) -> Result<Amf0Value, Amf0DeserializationError> {
+fn parse_string(
+    bytes: &mut Cursor<&[u8]>,
+    budget: &mut Budget,
+) -> Result<Amf0Value, Amf0DeserializationError> {
     let length = bytes.read_u16::<BigEndian>()?;
-    let mut buffer: Vec<u8> = vec![0_u8; length as usize];
-    bytes.read_exact(&mut buffer)?;
-
-    let value = String::from_utf8(buffer)?;
+    let value = read_utf8(bytes, length as usize, budget)?;
     Ok(Amf0Value::Utf8String(value))
 }
 
-fn parse_object<R: Read>(
-    bytes: &mut R,
-    references: &mut Vec<Amf0Value>,
+fn parse_long_string(
+    bytes: &mut Cursor<&[u8]>,
+    budget: &mut Budget,
 ) -> Result<Amf0Value, Amf0DeserializationError> {
-    let mut properties = HashMap::new();
+    let length = bytes.read_u32::<BigEndian>()?;
+    let value = read_utf8(bytes, length as usize, budget)?;
+    Ok(Amf0Value::LongUtf8String(value))
+}
 
-    loop {
-        match parse_object_property(bytes, references)? {
-            Some(property) => properties.insert(property.label, property.value),
-            None => break,
-        };
+fn parse_xml_document(
+    bytes: &mut Cursor<&[u8]>,
+    budget: &mut Budget,
+) -> Result<Amf0Value, Amf0DeserializationError> {
+    let length = bytes.read_u32::<BigEndian>()?;
+    let value = read_utf8(bytes, length as usize, budget)?;
+    Ok(Amf0Value::XmlDocument(value))
+}
+
+fn read_utf8(
+    bytes: &mut Cursor<&[u8]>,
+    length: usize,
+    budget: &mut Budget,
+) -> Result<String, Amf0DeserializationError> {
+    budget.string(length)?;
+
+    // Read through `take` so a corrupt length cannot allocate more than the data contains
+    let mut buffer: Vec<u8> = vec![];
+    bytes
+        .by_ref()
+        .take(length as u64)
+        .read_to_end(&mut buffer)?;
+
+    if buffer.len() != length {
+        return Err(Amf0DeserializationError::UnexpectedEof);
     }
 
-    let deserialized_value = Amf0Value::Object(properties);
-    references.push(deserialized_value.clone());
-    Ok(deserialized_value)
+    Ok(String::from_utf8(buffer)?)
 }
 
-fn parse_ecma_array<R: Read>(
-    bytes: &mut R,
-    references: &mut Vec<Amf0Value>,
+fn parse_date(bytes: &mut Cursor<&[u8]>) -> Result<Amf0Value, Amf0DeserializationError> {
+    let unix_time = bytes.read_f64::<BigEndian>()?;
+    let time_zone = bytes.read_i16::<BigEndian>()?;
+    Ok(Amf0Value::Date {
+        unix_time,
+        time_zone,
+    })
+}
+
+// Reads a value that can be referenced, one level deeper than its parent
+fn parse_complex(
+    position: u64,
+    references: &mut References,
+    budget: &mut Budget,
+    parse: impl FnOnce(&mut References, &mut Budget) -> Result<Amf0Value, Amf0DeserializationError>,
+) -> Result<Amf0Value, Amf0DeserializationError> {
+    if references.replaying {
+        budget.enter()?;
+        let value = parse(references, budget)?;
+        budget.leave();
+        return Ok(value);
+    }
+
+    let index = references.values.len();
+    references.values.push(None);
+
+    let allocated_before = budget.allocated();
+    budget.enter()?;
+    let value = parse(references, budget)?;
+    budget.leave();
+
+    references.values[index] = Some(Referenced {
+        position,
+        allocated: budget.allocated() - allocated_before,
+        depth: nesting_depth(&value),
+    });
+    Ok(value)
+}
+
+fn nesting_depth(value: &Amf0Value) -> usize {
+    let children: Box<dyn Iterator<Item = &Amf0Value>> = match value {
+        Amf0Value::Object(properties)
+        | Amf0Value::ECMAArray(properties)
+        | Amf0Value::TypedObject { properties, .. } => Box::new(properties.values()),
+        Amf0Value::StrictArray(values) => Box::new(values.iter()),
+        _ => return 0,
+    };
+
+    1 + children.map(nesting_depth).max().unwrap_or(0)
+}
+
+fn parse_object(
+    bytes: &mut Cursor<&[u8]>,
+    references: &mut References,
+    budget: &mut Budget,
+) -> Result<Amf0Value, Amf0DeserializationError> {
+    let properties = parse_object_properties(bytes, references, budget)?;
+    Ok(Amf0Value::Object(properties))
+}
+
+fn parse_object_properties(
+    bytes: &mut Cursor<&[u8]>,
+    references: &mut References,
+    budget: &mut Budget,
+) -> Result<IndexMap<String, Amf0Value>, Amf0DeserializationError> {
+    let mut properties = IndexMap::new();
+
+    while let Some(property) = parse_object_property(bytes, references, budget)? {
+        properties.insert(property.label, property.value);
+    }
+
+    Ok(properties)
+}
+
+fn parse_typed_object(
+    bytes: &mut Cursor<&[u8]>,
+    references: &mut References,
+    budget: &mut Budget,
+) -> Result<Amf0Value, Amf0DeserializationError> {
+    let class_name_length = bytes.read_u16::<BigEndian>()?;
+    let class_name = read_utf8(bytes, class_name_length as usize, budget)?;
+    let properties = parse_object_properties(bytes, references, budget)?;
+
+    Ok(Amf0Value::TypedObject {
+        class_name,
+        properties,
+    })
+}
+
+fn parse_ecma_array(
+    bytes: &mut Cursor<&[u8]>,
+    references: &mut References,
+    budget: &mut Budget,
 ) -> Result<Amf0Value, Amf0DeserializationError> {
     // An ECMA array is an array of values indexed via strings instead of numeric indexes (so
-    // essentially a hash map).  It seems functionally equivalent to an object so for simplicity
-    // treat it as such.
+    // essentially a hash map).  It reads like an object, but stays an ECMA array so that writing
+    // it back produces the same bytes.
 
     // While the spec says it gives you the count of items in the array, it is vague about if
     // the object end marker is used.  In real world usages I have found the associative array
@@ -125,18 +285,21 @@ fn parse_ecma_array<R: Read>(
     // like we can ignore the associative count and just read exactly as we would an object.
 
     let _associative_count = bytes.read_u32::<BigEndian>()?;
-    parse_object(bytes, references)
+
+    let properties = parse_object_properties(bytes, references, budget)?;
+    Ok(Amf0Value::ECMAArray(properties))
 }
 
-fn parse_strict_array<R: Read>(
-    bytes: &mut R,
-    references: &mut Vec<Amf0Value>,
+fn parse_strict_array(
+    bytes: &mut Cursor<&[u8]>,
+    references: &mut References,
+    budget: &mut Budget,
 ) -> Result<Amf0Value, Amf0DeserializationError> {
     let _array_count = bytes.read_u32::<BigEndian>()?;
     let mut values: Vec<Amf0Value> = Vec::new();
 
     for _ in 0.._array_count {
-        match read_next_value(bytes, references)? {
+        match read_next_value(bytes, references, budget)? {
             Some(value) => {
                 values.push(value);
             }
@@ -144,14 +307,13 @@ fn parse_strict_array<R: Read>(
         };
     }
 
-    references.push(Amf0Value::StrictArray(values.clone()));
-
     Ok(Amf0Value::StrictArray(values))
 }
 
-fn parse_object_property<R: Read>(
-    bytes: &mut R,
-    references: &mut Vec<Amf0Value>,
+fn parse_object_property(
+    bytes: &mut Cursor<&[u8]>,
+    references: &mut References,
+    budget: &mut Budget,
 ) -> Result<Option<ObjectProperty>, Amf0DeserializationError> {
     let label_length = bytes.read_u16::<BigEndian>()?;
     if label_length == 0 {
@@ -165,12 +327,9 @@ fn parse_object_property<R: Read>(
         return Ok(None);
     }
 
-    let mut label_buffer = vec![0; label_length as usize];
-    bytes.read_exact(&mut label_buffer)?;
-
-    let label = String::from_utf8(label_buffer)?;
+    let label = read_utf8(bytes, label_length as usize, budget)?;
 
-    match read_next_value(bytes, references)? {
+    match read_next_value(bytes, references, budget)? {
         None => Err(Amf0DeserializationError::UnexpectedEof),
         Some(property_value) => Ok(Some(ObjectProperty {
             label,
@@ -179,23 +338,62 @@ fn parse_object_property<R: Read>(
     }
 }
 
-fn parse_reference<R: Read>(
-    bytes: &mut R,
-    references: &Vec<Amf0Value>,
+fn parse_reference(
+    bytes: &mut Cursor<&[u8]>,
+    references: &mut References,
+    budget: &mut Budget,
 ) -> Result<Amf0Value, Amf0DeserializationError> {
     let index = bytes.read_u16::<BigEndian>()?;
-    Ok(references[index as usize].clone())
+
+    // References to values that are still being read would make the value tree cyclic
+    let Some(Some(referenced)) = references.values.get(index as usize) else {
+        return Err(Amf0DeserializationError::InvalidReference { index });
+    };
+
+    // The copy costs as much as the original, which stops a few bytes of references
+    // from multiplying into a huge value
+    budget.check_depth(referenced.depth)?;
+    budget.allocate(referenced.allocated)?;
+
+    // Read the value again from where it starts, which was already charged for above
+    let mut copy = Cursor::new(*bytes.get_ref());
+    copy.set_position(referenced.position);
+    let replaying = mem::replace(&mut references.replaying, true);
+    let value = read_next_value(
+        &mut copy,
+        references,
+        &mut Budget::new(&ReadLimits::unlimited()),
+    );
+    references.replaying = replaying;
+
+    value?.ok_or(Amf0DeserializationError::InvalidReference { index })
+}
+
+fn parse_avm_plus(
+    bytes: &mut Cursor<&[u8]>,
+    budget: &mut Budget,
+) -> Result<Amf0Value, Amf0DeserializationError> {
+    let mut value = vec![];
+    amf3::read_value(bytes, &mut value, budget)?;
+    Ok(Amf0Value::AvmPlus(value))
 }
 
 #[cfg(test)]
 mod tests {
     use super::super::Amf0Value;
-    use super::deserialize;
-    use super::markers;
+    use super::{Amf0DeserializationError, markers};
+    use crate::formats::{Budget, ReadLimit, ReadLimits};
     use byteorder::{BigEndian, WriteBytesExt};
-    use std::collections::HashMap;
+    use indexmap::IndexMap;
     use std::io::Cursor;
 
+    fn deserialize(
+        input: &mut Cursor<Vec<u8>>,
+    ) -> Result<Vec<Amf0Value>, Amf0DeserializationError> {
+        let mut bytes = Cursor::new(input.get_ref().as_slice());
+        super::deserialize(&mut bytes, &mut Budget::new(&ReadLimits::default()))
+    }
+
     #[test]
     fn can_deserialize_strict_array() {
         let mut vector = vec![];
@@ -301,13 +499,38 @@ mod tests {
         let mut input = Cursor::new(vector);
         let result = deserialize(&mut input).unwrap();
 
-        let mut properties = HashMap::new();
+        let mut properties = IndexMap::new();
         properties.insert("test".to_string(), Amf0Value::Number(NUMBER));
 
         let expected = vec![Amf0Value::Object(properties)];
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn object_properties_keep_read_order() {
+        let mut vector = vec![];
+        vector.push(markers::OBJECT_MARKER);
+        for name in ["version", "startLine", "level", "data", "label"] {
+            vector.write_u16::<BigEndian>(name.len() as u16).unwrap();
+            vector.extend(name.as_bytes());
+            vector.push(markers::NULL_MARKER);
+        }
+        vector
+            .write_u16::<BigEndian>(markers::UTF_8_EMPTY_MARKER)
+            .unwrap();
+        vector.push(markers::OBJECT_END_MARKER);
+
+        let mut input = Cursor::new(vector.clone());
+        let result = deserialize(&mut input).unwrap();
+        let properties = result[0].clone().get_object_properties().unwrap();
+
+        assert_eq!(
+            properties.keys().collect::<Vec<_>>(),
+            ["version", "startLine", "level", "data", "label"]
+        );
+        assert_eq!(super::super::serialize(&result).unwrap(), vector);
+    }
+
     #[test]
     fn can_deserialize_emca_array() {
         let mut vector = vec![];
@@ -330,14 +553,14 @@ mod tests {
         let mut input = Cursor::new(vector);
         let result = deserialize(&mut input).unwrap();
 
-        let mut properties = HashMap::new();
+        let mut properties = IndexMap::new();
         properties.insert("test1".to_string(), Amf0Value::Number(1.0));
         properties.insert(
             "test2".to_string(),
             Amf0Value::Utf8String("second".to_string()),
         );
 
-        let expected = vec![Amf0Value::Object(properties)];
+        let expected = vec![Amf0Value::ECMAArray(properties)];
         assert_eq!(result, expected);
     }
 
@@ -352,4 +575,180 @@ mod tests {
         let expected = vec![Amf0Value::Undefined];
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn can_deserialize_date_and_long_strings() {
+        let mut vector = vec![];
+        vector.push(markers::DATE_MARKER);
+        vector.write_f64::<BigEndian>(1_000.0).unwrap();
+        vector.write_i16::<BigEndian>(-60).unwrap();
+        vector.push(markers::LONG_STRING_MARKER);
+        vector.write_u32::<BigEndian>(4).unwrap();
+        vector.extend("long".as_bytes());
+        vector.push(markers::XML_DOCUMENT_MARKER);
+        vector.write_u32::<BigEndian>(4).unwrap();
+        vector.extend("<a/>".as_bytes());
+        vector.push(markers::UNSUPPORTED_MARKER);
+
+        let mut input = Cursor::new(vector);
+        let result = deserialize(&mut input).unwrap();
+
+        let expected = vec![
+            Amf0Value::Date {
+                unix_time: 1_000.0,
+                time_zone: -60,
+            },
+            Amf0Value::LongUtf8String("long".to_string()),
+            Amf0Value::XmlDocument("<a/>".to_string()),
+            Amf0Value::Unsupported,
+        ];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn can_deserialize_typed_object_and_reference() {
+        let mut vector = vec![];
+        vector.push(markers::STRICT_ARRAY_MARKER);
+        vector.write_u32::<BigEndian>(2).unwrap();
+        vector.push(markers::TYPED_OBJECT_MARKER);
+        vector.write_u16::<BigEndian>(5).unwrap();
+        vector.extend("Point".as_bytes());
+        vector.write_u16::<BigEndian>(1).unwrap();
+        vector.extend("x".as_bytes());
+        vector.push(markers::NUMBER_MARKER);
+        vector.write_f64::<BigEndian>(1.0).unwrap();
+        vector
+            .write_u16::<BigEndian>(markers::UTF_8_EMPTY_MARKER)
+            .unwrap();
+        vector.push(markers::OBJECT_END_MARKER);
+        // The array itself takes index 0
+        vector.push(markers::REFERENCE_MARKER);
+        vector.write_u16::<BigEndian>(1).unwrap();
+
+        let mut input = Cursor::new(vector);
+        let result = deserialize(&mut input).unwrap();
+
+        let mut properties = IndexMap::new();
+        properties.insert("x".to_string(), Amf0Value::Number(1.0));
+        let point = Amf0Value::TypedObject {
+            class_name: "Point".to_string(),
+            properties,
+        };
+
+        let expected = vec![Amf0Value::StrictArray(vec![point.clone(), point])];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn references_inside_referenced_values_keep_their_targets() {
+        let mut vector = vec![];
+        // Index 0
+        vector.push(markers::STRICT_ARRAY_MARKER);
+        vector.write_u32::<BigEndian>(4).unwrap();
+        // Index 1
+        vector.push(markers::STRICT_ARRAY_MARKER);
+        vector.write_u32::<BigEndian>(1).unwrap();
+        vector.push(markers::NUMBER_MARKER);
+        vector.write_f64::<BigEndian>(1.0).unwrap();
+        // Index 2, holding a reference to index 1 and an array of its own at index 3
+        vector.push(markers::STRICT_ARRAY_MARKER);
+        vector.write_u32::<BigEndian>(2).unwrap();
+        vector.push(markers::REFERENCE_MARKER);
+        vector.write_u16::<BigEndian>(1).unwrap();
+        vector.push(markers::STRICT_ARRAY_MARKER);
+        vector.write_u32::<BigEndian>(0).unwrap();
+        // Reading index 2 again must not number its inner array as index 4
+        vector.push(markers::REFERENCE_MARKER);
+        vector.write_u16::<BigEndian>(2).unwrap();
+        vector.push(markers::REFERENCE_MARKER);
+        vector.write_u16::<BigEndian>(3).unwrap();
+
+        let mut input = Cursor::new(vector);
+        let result = deserialize(&mut input).unwrap();
+
+        let inner = Amf0Value::StrictArray(vec![Amf0Value::Number(1.0)]);
+        let empty = Amf0Value::StrictArray(vec![]);
+        let outer = Amf0Value::StrictArray(vec![inner.clone(), empty.clone()]);
+        let expected = vec![Amf0Value::StrictArray(vec![
+            inner,
+            outer.clone(),
+            outer,
+            empty,
+        ])];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn error_when_referencing_incomplete_value() {
+        let mut vector = vec![];
+        vector.push(markers::STRICT_ARRAY_MARKER);
+        vector.write_u32::<BigEndian>(1).unwrap();
+        vector.push(markers::REFERENCE_MARKER);
+        vector.write_u16::<BigEndian>(0).unwrap();
+
+        let mut input = Cursor::new(vector);
+        assert!(deserialize(&mut input).is_err());
+    }
+
+    #[test]
+    fn can_deserialize_avm_plus_value() {
+        // AMF3 object with inline dynamic traits, class "", and member a = 1
+        let amf3_value = [0x0A, 0x0B, 0x01, 0x03, b'a', 0x04, 0x01, 0x01];
+
+        let mut vector = vec![markers::AVM_PLUS_MARKER];
+        vector.extend(amf3_value);
+        vector.push(markers::NULL_MARKER);
+
+        let mut input = Cursor::new(vector);
+        let result = deserialize(&mut input).unwrap();
+
+        let expected = vec![Amf0Value::AvmPlus(amf3_value.to_vec()), Amf0Value::Null];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn error_when_references_multiply_into_a_huge_value() {
+        let mut vector = vec![];
+        vector.push(markers::STRICT_ARRAY_MARKER);
+        vector.write_u32::<BigEndian>(1).unwrap();
+        vector.push(markers::NUMBER_MARKER);
+        vector.write_f64::<BigEndian>(1.0).unwrap();
+
+        // Each array holds the previous one twice, doubling the size of the values read
+        for index in 0..64 {
+            vector.push(markers::STRICT_ARRAY_MARKER);
+            vector.write_u32::<BigEndian>(2).unwrap();
+            for _ in 0..2 {
+                vector.push(markers::REFERENCE_MARKER);
+                vector.write_u16::<BigEndian>(index).unwrap();
+            }
+        }
+
+        let limits = ReadLimits {
+            max_allocation: 1024 * 1024,
+            ..ReadLimits::default()
+        };
+        let mut input = Cursor::new(vector.as_slice());
+        let result = super::deserialize(&mut input, &mut Budget::new(&limits));
+        let Err(Amf0DeserializationError::LimitExceeded(err)) = result else {
+            panic!("Expected the references to go over the allocation limit");
+        };
+        assert_eq!(err.limit(), ReadLimit::Allocation);
+    }
+
+    #[test]
+    fn error_when_values_are_nested_too_deeply() {
+        let mut vector = vec![];
+        for _ in 0..1000 {
+            vector.push(markers::STRICT_ARRAY_MARKER);
+            vector.write_u32::<BigEndian>(1).unwrap();
+        }
+        vector.push(markers::NULL_MARKER);
+
+        let mut input = Cursor::new(vector);
+        let Err(Amf0DeserializationError::LimitExceeded(err)) = deserialize(&mut input) else {
+            panic!("Expected the arrays to go over the nesting limit");
+        };
+        assert_eq!(err.limit(), ReadLimit::NestingDepth);
+    }
 }
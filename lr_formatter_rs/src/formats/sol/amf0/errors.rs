@@ -5,6 +5,8 @@
 use std::{io, string};
 use thiserror::Error;
 
+use crate::formats::LimitExceeded;
+
 // Errors that can occur during the deserialization process
 #[derive(Debug, Error)]
 pub enum Amf0DeserializationError {
@@ -25,6 +27,23 @@ pub enum Amf0DeserializationError {
     #[error("Hit end of the byte buffer but was expecting more data")]
     UnexpectedEof,
 
+    // Reference markers point to a previously read object, array or typed object by index.
+    // This error is raised when no such value has been read completely.
+    #[error("Encountered invalid reference to index {index}")]
+    InvalidReference { index: u16 },
+
+    // Values following an AVM+ marker are AMF3 encoded, and start with their own markers.
+    #[error("Encountered unknown AMF3 marker: {marker}")]
+    UnknownAmf3Marker { marker: u8 },
+
+    // AMF3 objects can reuse the traits of a previous object, which must have been read before.
+    #[error("Encountered invalid AMF3 traits reference to index {index}")]
+    InvalidAmf3TraitsReference { index: u32 },
+
+    // Externalizable AMF3 objects are encoded by their class, so their data cannot be read.
+    #[error("Encountered externalizable AMF3 object")]
+    ExternalizableAmf3Object,
+
     // An I/O Error occurred while reading the data buffer
     #[error("Failed to read byte buffer: {0}")]
     BufferReadError(#[from] io::Error),
@@ -33,6 +52,11 @@ pub enum Amf0DeserializationError {
     // UTF-8 this error will be raised.
     #[error("Failed to read a utf8 string from the byte buffer: {0}")]
     StringParseError(#[from] string::FromUtf8Error),
+
+    // The data asks for more than the read limits allow, such as a huge string, deeply nested
+    // values or references that copy a large value many times.
+    #[error("{0}")]
+    LimitExceeded(#[from] LimitExceeded),
 }
 
 // Errors raised during to the serialization process
@@ -43,6 +67,15 @@ pub enum Amf0SerializationError {
     #[error("String length greater than 65,535")]
     NormalStringTooLong,
 
+    // Long strings and XML documents cannot be more than 4,294,967,295 bytes.
+    #[error("String length greater than 4,294,967,295")]
+    LongStringTooLong,
+
+    // Reference values must point to an object, array or typed object written completely
+    // before them.
+    #[error("Encountered invalid reference to index {index}")]
+    InvalidReference { index: u16 },
+
     // An I/O error occurred while writing to the output buffer.
     #[error("Failed to write to byte buffer")]
     BufferWriteError(#[from] io::Error),
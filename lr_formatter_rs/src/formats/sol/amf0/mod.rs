@@ -2,6 +2,7 @@
 //! License: See ./LICENSE-APACHE and ./LICENSE-MIT
 //! Modifications Copyright 2025 Tobias Bessler
 
+mod amf3;
 mod deserialization;
 mod errors;
 mod serialization;
@@ -10,19 +11,41 @@ pub(super) use deserialization::deserialize;
 pub use errors::{Amf0DeserializationError, Amf0SerializationError};
 pub(super) use serialization::serialize;
 
-use std::collections::HashMap;
+use indexmap::IndexMap;
 
-// An Enum representing the different supported types of Amf0 values
+/// An Enum representing the different supported types of Amf0 values.
+/// Object properties keep the order they were read or inserted in, so
+/// serializing the same value always produces the same bytes.
 #[derive(PartialEq, Debug, Clone)]
-pub(super) enum Amf0Value {
+pub enum Amf0Value {
     Number(f64),
     Boolean(bool),
     Utf8String(String),
-    Object(HashMap<String, Amf0Value>),
-    ECMAArray(HashMap<String, Amf0Value>),
+    Object(IndexMap<String, Amf0Value>),
+    ECMAArray(IndexMap<String, Amf0Value>),
     StrictArray(Vec<Amf0Value>),
     Null,
     Undefined,
+    Date {
+        unix_time: f64,
+        time_zone: i16,
+    },
+    LongUtf8String(String),
+    Unsupported,
+    XmlDocument(String),
+    TypedObject {
+        class_name: String,
+        properties: IndexMap<String, Amf0Value>,
+    },
+    /// The encoded bytes of an AMF3 value, which follows an AVM+ marker
+    AvmPlus(Vec<u8>),
+    /// A reference to the object, array or typed object with this index, counting complex
+    /// values in the order they start
+    ///
+    /// Values are only written as references when given as one, so that equal values stay
+    /// separate objects. References are resolved to copies of their value when reading, so
+    /// writing back a file that used references spells out every copy in full.
+    Reference(u16),
 }
 
 impl Amf0Value {
@@ -47,9 +70,37 @@ impl Amf0Value {
         }
     }
 
-    pub fn get_object_properties(self) -> Option<HashMap<String, Amf0Value>> {
+    pub fn get_object_properties(self) -> Option<IndexMap<String, Amf0Value>> {
         match self {
-            Amf0Value::Object(properties) => Some(properties),
+            Amf0Value::Object(properties) | Amf0Value::ECMAArray(properties) => Some(properties),
+            _ => None,
+        }
+    }
+
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Amf0Value::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_boolean(&self) -> Option<bool> {
+        match self {
+            Amf0Value::Boolean(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_string(&self) -> Option<&str> {
+        match self {
+            Amf0Value::Utf8String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_object_properties(&self) -> Option<&IndexMap<String, Amf0Value>> {
+        match self {
+            Amf0Value::Object(properties) | Amf0Value::ECMAArray(properties) => Some(properties),
             _ => None,
         }
     }
@@ -66,5 +117,11 @@ mod markers {
     pub const ECMA_ARRAY_MARKER: u8 = 8;
     pub const OBJECT_END_MARKER: u8 = 9;
     pub const STRICT_ARRAY_MARKER: u8 = 10;
+    pub const DATE_MARKER: u8 = 11;
+    pub const LONG_STRING_MARKER: u8 = 12;
+    pub const UNSUPPORTED_MARKER: u8 = 13;
+    pub const XML_DOCUMENT_MARKER: u8 = 15;
+    pub const TYPED_OBJECT_MARKER: u8 = 16;
+    pub const AVM_PLUS_MARKER: u8 = 17;
     pub const UTF_8_EMPTY_MARKER: u16 = 0;
 }
@@ -4,21 +4,32 @@
 
 use super::{Amf0Value, errors::Amf0SerializationError, markers};
 use byteorder::{BigEndian, WriteBytesExt};
-use std::collections::HashMap;
+use indexmap::IndexMap;
+
+// Whether each complex value written so far has been written completely, by reference index
+type References = Vec<bool>;
 
 // Serializes values into an amf0 encoded vector of bytes
+//
+// Objects, arrays and typed objects are numbered in the order they start, and reference values
+// are written as references to the complex value with their index.
 pub(in crate::formats::sol) fn serialize(
-    values: &Vec<Amf0Value>,
+    values: &[Amf0Value],
 ) -> Result<Vec<u8>, Amf0SerializationError> {
     let mut bytes = vec![];
+    let mut references = References::new();
     for value in values {
-        serialize_value(value, &mut bytes)?;
+        serialize_value(value, &mut bytes, &mut references)?;
     }
 
     Ok(bytes)
 }
 
-fn serialize_value(value: &Amf0Value, bytes: &mut Vec<u8>) -> Result<(), Amf0SerializationError> {
+fn serialize_value(
+    value: &Amf0Value,
+    bytes: &mut Vec<u8>,
+    references: &mut References,
+) -> Result<(), Amf0SerializationError> {
     match *value {
         Amf0Value::Boolean(val) => {
             bytes.push(markers::BOOLEAN_MARKER);
@@ -32,6 +43,10 @@ fn serialize_value(value: &Amf0Value, bytes: &mut Vec<u8>) -> Result<(), Amf0Ser
             bytes.push(markers::UNDEFINED_MARKER);
             Ok(())
         }
+        Amf0Value::Unsupported => {
+            bytes.push(markers::UNSUPPORTED_MARKER);
+            Ok(())
+        }
         Amf0Value::Number(val) => {
             bytes.push(markers::NUMBER_MARKER);
             serialize_number(val, bytes)
@@ -40,18 +55,80 @@ fn serialize_value(value: &Amf0Value, bytes: &mut Vec<u8>) -> Result<(), Amf0Ser
             bytes.push(markers::STRING_MARKER);
             serialize_string(val, bytes)
         }
-        Amf0Value::Object(ref val) => {
+        Amf0Value::LongUtf8String(ref val) => {
+            bytes.push(markers::LONG_STRING_MARKER);
+            serialize_long_string(val, bytes)
+        }
+        Amf0Value::XmlDocument(ref val) => {
+            bytes.push(markers::XML_DOCUMENT_MARKER);
+            serialize_long_string(val, bytes)
+        }
+        Amf0Value::Date {
+            unix_time,
+            time_zone,
+        } => {
+            bytes.push(markers::DATE_MARKER);
+            bytes.write_f64::<BigEndian>(unix_time)?;
+            bytes.write_i16::<BigEndian>(time_zone)?;
+            Ok(())
+        }
+        Amf0Value::AvmPlus(ref val) => {
+            bytes.push(markers::AVM_PLUS_MARKER);
+            bytes.extend(val);
+            Ok(())
+        }
+        Amf0Value::Object(_)
+        | Amf0Value::StrictArray(_)
+        | Amf0Value::ECMAArray(_)
+        | Amf0Value::TypedObject { .. } => {
+            // Values get their index when they start, but can only be referenced once complete
+            let index = references.len();
+            references.push(false);
+            serialize_complex(value, bytes, references)?;
+            references[index] = true;
+            Ok(())
+        }
+        Amf0Value::Reference(index) => {
+            if !references
+                .get(usize::from(index))
+                .is_some_and(|complete| *complete)
+            {
+                return Err(Amf0SerializationError::InvalidReference { index });
+            }
+            bytes.push(markers::REFERENCE_MARKER);
+            bytes.write_u16::<BigEndian>(index)?;
+            Ok(())
+        }
+    }
+}
+
+fn serialize_complex(
+    value: &Amf0Value,
+    bytes: &mut Vec<u8>,
+    references: &mut References,
+) -> Result<(), Amf0SerializationError> {
+    match value {
+        Amf0Value::Object(val) => {
             bytes.push(markers::OBJECT_MARKER);
-            serialize_object(val, bytes)
+            serialize_object(val, bytes, references)
         }
-        Amf0Value::StrictArray(ref val) => {
+        Amf0Value::StrictArray(val) => {
             bytes.push(markers::STRICT_ARRAY_MARKER);
-            serialize_strict_array(val, bytes)
+            serialize_strict_array(val, bytes, references)
         }
-        Amf0Value::ECMAArray(ref val) => {
+        Amf0Value::ECMAArray(val) => {
             bytes.push(markers::ECMA_ARRAY_MARKER);
-            serialize_ecma_array(val, bytes)
+            serialize_ecma_array(val, bytes, references)
+        }
+        Amf0Value::TypedObject {
+            class_name,
+            properties,
+        } => {
+            bytes.push(markers::TYPED_OBJECT_MARKER);
+            serialize_string(class_name, bytes)?;
+            serialize_object(properties, bytes, references)
         }
+        _ => serialize_value(value, bytes, references),
     }
 }
 
@@ -75,14 +152,24 @@ fn serialize_string(value: &String, bytes: &mut Vec<u8>) -> Result<(), Amf0Seria
     Ok(())
 }
 
+fn serialize_long_string(value: &str, bytes: &mut Vec<u8>) -> Result<(), Amf0SerializationError> {
+    let length =
+        u32::try_from(value.len()).map_err(|_| Amf0SerializationError::LongStringTooLong)?;
+
+    bytes.write_u32::<BigEndian>(length)?;
+    bytes.extend(value.as_bytes());
+    Ok(())
+}
+
 fn serialize_object(
-    properties: &HashMap<String, Amf0Value>,
+    properties: &IndexMap<String, Amf0Value>,
     bytes: &mut Vec<u8>,
+    references: &mut References,
 ) -> Result<(), Amf0SerializationError> {
     for (name, value) in properties {
         bytes.write_u16::<BigEndian>(name.len() as u16)?;
         bytes.extend(name.as_bytes());
-        serialize_value(value, bytes)?;
+        serialize_value(value, bytes, references)?;
     }
 
     bytes.write_u16::<BigEndian>(markers::UTF_8_EMPTY_MARKER)?;
@@ -93,23 +180,25 @@ fn serialize_object(
 fn serialize_strict_array(
     array: &Vec<Amf0Value>,
     bytes: &mut Vec<u8>,
+    references: &mut References,
 ) -> Result<(), Amf0SerializationError> {
     bytes.write_u32::<BigEndian>(array.len() as u32)?;
 
     for value in array {
-        serialize_value(value, bytes)?;
+        serialize_value(value, bytes, references)?;
     }
 
     Ok(())
 }
 
 fn serialize_ecma_array(
-    properties: &HashMap<String, Amf0Value>,
+    properties: &IndexMap<String, Amf0Value>,
     bytes: &mut Vec<u8>,
+    references: &mut References,
 ) -> Result<(), Amf0SerializationError> {
     bytes.write_u32::<BigEndian>(properties.len() as u32)?;
 
-    serialize_object(properties, bytes)?;
+    serialize_object(properties, bytes, references)?;
 
     Ok(())
 }
@@ -121,7 +210,7 @@ mod tests {
     use super::markers;
     use super::serialize;
     use byteorder::{BigEndian, WriteBytesExt};
-    use std::collections::HashMap;
+    use indexmap::IndexMap;
 
     #[test]
     fn can_serialize_strict_array() {
@@ -211,7 +300,7 @@ mod tests {
     fn can_serialize_object() {
         const NUMBER: f64 = 332.0;
 
-        let mut properties = HashMap::new();
+        let mut properties = IndexMap::new();
         properties.insert("test".to_string(), Amf0Value::Number(NUMBER));
 
         let input = vec![Amf0Value::Object(properties)];
@@ -255,4 +344,63 @@ mod tests {
 
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn equal_objects_are_written_separately() {
+        let mut properties = IndexMap::new();
+        properties.insert("x".to_string(), Amf0Value::Number(1.0));
+        let object = Amf0Value::Object(properties);
+
+        let repeated = serialize(&[Amf0Value::StrictArray(vec![object.clone(), object.clone()])]);
+        let single = serialize(std::slice::from_ref(&object)).unwrap();
+
+        let mut expected = vec![];
+        expected.push(markers::STRICT_ARRAY_MARKER);
+        expected.write_u32::<BigEndian>(2).unwrap();
+        expected.extend(&single);
+        expected.extend(&single);
+
+        assert_eq!(repeated.unwrap(), expected);
+    }
+
+    #[test]
+    fn references_point_at_complete_values() {
+        let mut properties = IndexMap::new();
+        properties.insert("x".to_string(), Amf0Value::Number(1.0));
+        let object = Amf0Value::Object(properties);
+
+        let input = vec![Amf0Value::StrictArray(vec![
+            object,
+            Amf0Value::Reference(1),
+        ])];
+        let result = serialize(&input).unwrap();
+
+        let mut expected = vec![];
+        expected.push(markers::STRICT_ARRAY_MARKER);
+        expected.write_u32::<BigEndian>(2).unwrap();
+        expected.push(markers::OBJECT_MARKER);
+        expected.write_u16::<BigEndian>(1).unwrap();
+        expected.extend("x".as_bytes());
+        expected.push(markers::NUMBER_MARKER);
+        expected.write_f64::<BigEndian>(1.0).unwrap();
+        expected
+            .write_u16::<BigEndian>(markers::UTF_8_EMPTY_MARKER)
+            .unwrap();
+        expected.push(markers::OBJECT_END_MARKER);
+        expected.push(markers::REFERENCE_MARKER);
+        expected.write_u16::<BigEndian>(1).unwrap();
+
+        assert_eq!(result, expected);
+
+        // The array itself is still being written, and nothing has index 2
+        for index in [0, 2] {
+            let input = vec![Amf0Value::StrictArray(vec![Amf0Value::Reference(index)])];
+            let Err(Amf0SerializationError::InvalidReference { index: invalid }) =
+                serialize(&input)
+            else {
+                panic!("References to incomplete or missing values should fail");
+            };
+            assert_eq!(invalid, index);
+        }
+    }
 }
@@ -6,7 +6,10 @@ use std::{
 use thiserror::Error;
 
 use crate::{
-    formats::sol::{Amf0DeserializationError, Amf0SerializationError},
+    formats::{
+        ErrorContext, LimitExceeded,
+        sol::{Amf0DeserializationError, Amf0SerializationError, SolFlavor},
+    },
     track::{
         TrackBuilderError, layer::layer_group::LayerGroupBuilderError,
         line::line_group::LineGroupBuilderError, rider::rider_group::RiderGroupBuilderError,
@@ -37,7 +40,45 @@ pub enum SolReadError {
     #[error("Invalid value for `{name}`: {value}")]
     InvalidData { name: String, value: String },
     #[error("{0}")]
-    Amf0Deserialization(#[from] Amf0DeserializationError),
+    Amf0Deserialization(Amf0DeserializationError),
+    #[error("{0}")]
+    LimitExceeded(#[from] LimitExceeded),
+    #[error("{source} ({context})")]
+    WithContext {
+        context: ErrorContext,
+        source: Box<SolReadError>,
+    },
+}
+
+// Values over the read limits are reported as read limit errors, not AMF0 errors
+impl From<Amf0DeserializationError> for SolReadError {
+    fn from(err: Amf0DeserializationError) -> Self {
+        match err {
+            Amf0DeserializationError::LimitExceeded(limit) => SolReadError::LimitExceeded(limit),
+            other => SolReadError::Amf0Deserialization(other),
+        }
+    }
+}
+
+impl SolReadError {
+    /// Where in the input the error happened, if known
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            SolReadError::WithContext { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    /// Attach a location to the error, keeping any more precise location it already has
+    pub(crate) fn with_context(self, context: ErrorContext) -> Self {
+        match self {
+            SolReadError::WithContext { .. } => self,
+            _ => SolReadError::WithContext {
+                context,
+                source: Box::new(self),
+            },
+        }
+    }
 }
 
 #[derive(Error, Debug)]
@@ -48,4 +89,6 @@ pub enum SolWriteError {
     IntConversion(#[from] TryFromIntError),
     #[error("{0}")]
     Amf0Serialization(#[from] Amf0SerializationError),
+    #[error("The {flavor:?} SOL flavor can not store grid version {version}")]
+    UnsupportedGridVersion { flavor: SolFlavor, version: String },
 }
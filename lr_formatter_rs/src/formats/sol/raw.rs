@@ -0,0 +1,136 @@
+//! Direct access to the AMF0 values of SOL files, for inspecting and patching the properties of
+//! flash mods that [`read`](super::read) does not model
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{self, Cursor, Read, Seek, Write};
+
+pub use super::amf0::Amf0Value;
+
+use crate::{
+    formats::{
+        Budget, ErrorContext, ReadLimits,
+        sol::{
+            SolReadError, SolWriteError,
+            amf0::{deserialize, serialize},
+        },
+    },
+    util::{StringLength, bytes_to_hex_string, parse_string},
+};
+
+/// Read the track list of a SOL file, an array with one object per track
+pub fn read_raw(data: Vec<u8>) -> Result<Amf0Value, SolReadError> {
+    read_raw_with_limits(data, &ReadLimits::default())
+}
+
+pub fn read_raw_with_limits(data: Vec<u8>, limits: &ReadLimits) -> Result<Amf0Value, SolReadError> {
+    read_raw_with_budget(data, &mut Budget::new(limits))
+}
+
+pub(crate) fn read_raw_with_budget(
+    data: Vec<u8>,
+    budget: &mut Budget,
+) -> Result<Amf0Value, SolReadError> {
+    let data_size = u64::try_from(data.len())?;
+    let mut cursor = Cursor::new(data);
+
+    // Magic number
+    let mut magic_number = [0u8; 2];
+    cursor.read_exact(&mut magic_number)?;
+
+    if magic_number != [0x00, 0xBF] {
+        return Err(SolReadError::InvalidData {
+            name: "magic number".to_string(),
+            value: bytes_to_hex_string(&magic_number),
+        });
+    }
+
+    // Header, whose size is not trusted since the data is read up to the end of the file
+    let _data_size = cursor.read_u32::<BigEndian>()?;
+
+    let mut tag = [0u8; 4];
+    cursor.read_exact(&mut tag)?;
+
+    if tag != [b'T', b'C', b'S', b'O'] {
+        return Err(SolReadError::InvalidData {
+            name: "header tag".to_string(),
+            value: bytes_to_hex_string(&tag),
+        });
+    }
+
+    let mut marker = [0u8; 6];
+    cursor.read_exact(&mut marker)?;
+    if marker != [0x00, 0x04, 0x00, 0x00, 0x00, 0x00] {
+        return Err(SolReadError::InvalidData {
+            name: "header marker".to_string(),
+            value: bytes_to_hex_string(&marker),
+        });
+    }
+
+    let sol_name = parse_string::<BigEndian>(&mut cursor, StringLength::U16, budget)?;
+    if sol_name.as_str() != "savedLines" {
+        return Err(SolReadError::InvalidData {
+            name: "sol name".to_string(),
+            value: sol_name.to_string(),
+        });
+    }
+
+    let _padding = cursor.read_u32::<BigEndian>()?;
+
+    let data_name = parse_string::<BigEndian>(&mut cursor, StringLength::U16, budget)?;
+    if data_name.as_str() != "trackList" {
+        return Err(SolReadError::InvalidData {
+            name: "data name".to_string(),
+            value: data_name.to_string(),
+        });
+    }
+
+    // Track Data
+    let current_pos = cursor.position();
+    // Slice from current position to last byte - 1 contains valid AMF0 format
+    let track_data_size = data_size
+        .checked_sub(1)
+        .and_then(|size| size.checked_sub(current_pos))
+        .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+    let track_data_start = usize::try_from(current_pos)?;
+    let track_data_end = usize::try_from(current_pos + track_data_size)?;
+    let mut trimmed_cursor = Cursor::new(&cursor.get_ref()[track_data_start..track_data_end]);
+    let mut result = deserialize(&mut trimmed_cursor, budget).map_err(|err| {
+        SolReadError::from(err).with_context(ErrorContext::at_offset(
+            current_pos + trimmed_cursor.position(),
+        ))
+    })?;
+
+    if result.is_empty() {
+        return Err(SolReadError::InvalidData {
+            name: "track list".to_string(),
+            value: "none".to_string(),
+        });
+    }
+
+    Ok(result.swap_remove(0))
+}
+
+/// Write a track list, as returned by [`read_raw`], to a SOL file
+pub fn write_raw(track_list: &Amf0Value) -> Result<Vec<u8>, SolWriteError> {
+    let mut cursor = Cursor::new(Vec::new());
+
+    cursor.write_all(b"\x00\xBF")?;
+    cursor.write_all(b"\x00\x00\x00\x00")?;
+    cursor.write_all(b"TCSO")?;
+    cursor.write_all(b"\x00\x04\x00\x00\x00\x00")?;
+    cursor.write_all(b"\x00\x0AsavedLines")?;
+    cursor.write_all(b"\x00\x00\x00\x00")?;
+    cursor.write_all(b"\x00\x09trackList")?;
+
+    // Serialize and write the data
+    let buffer = serialize(std::slice::from_ref(track_list))?;
+    cursor.write_all(&buffer)?;
+    cursor.write_u8(0x00)?;
+
+    // Go back to write file size
+    let file_size = u32::try_from(cursor.position() - 6)?;
+    cursor.seek(std::io::SeekFrom::Start(2))?;
+    cursor.write_u32::<BigEndian>(file_size)?;
+
+    Ok(cursor.into_inner())
+}
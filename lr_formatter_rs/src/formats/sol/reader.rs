@@ -1,80 +1,74 @@
-use byteorder::{BigEndian, ReadBytesExt};
-use std::io::{Cursor, Read};
+use indexmap::IndexMap;
 
 use crate::{
-    formats::sol::{SolReadError, amf0::deserialize},
-    track::{GridVersion, LineType, Track, TrackBuilder, Vec2},
-    util::{StringLength, bytes_to_hex_string, parse_string},
+    formats::{
+        ErrorContext, ReadLimits, ReadWarning, Recovery,
+        sol::{SolFlavor, SolReadError, amf0::Amf0Value, raw::read_raw_with_budget},
+    },
+    track::{GridVersion, LineType, Track, TrackBuilder, Vec2, line::line_adjacency::LineLink},
 };
 
-pub fn read(data: Vec<u8>, track_index: Option<u32>) -> Result<Track, SolReadError> {
-    let track_builder = &mut TrackBuilder::default();
-    let data_size = u64::try_from(data.len())?;
-    let mut cursor = Cursor::new(data);
-
-    // Magic number
-    let mut magic_number = [0u8; 2];
-    cursor.read_exact(&mut magic_number)?;
-
-    if magic_number != [0x00, 0xBF] {
-        return Err(SolReadError::InvalidData {
-            name: "magic number".to_string(),
-            value: bytes_to_hex_string(&magic_number),
-        });
-    }
-
-    // Header
-    let _file_size = cursor.read_u32::<BigEndian>()? + 6;
+/// Track properties read into the track model, anything else is kept as an extra
+const KNOWN_TRACK_PROPERTIES: [&str; 6] = [
+    "label",
+    "version",
+    "startLine",
+    "level",
+    "data",
+    "trackData",
+];
 
-    let mut tag = [0u8; 4];
-    cursor.read_exact(&mut tag)?;
-
-    if tag != [b'T', b'C', b'S', b'O'] {
-        return Err(SolReadError::InvalidData {
-            name: "header tag".to_string(),
-            value: bytes_to_hex_string(&tag),
-        });
-    }
-
-    let mut marker = [0u8; 6];
-    cursor.read_exact(&mut marker)?;
-    if marker != [0x00, 0x04, 0x00, 0x00, 0x00, 0x00] {
-        return Err(SolReadError::InvalidData {
-            name: "header marker".to_string(),
-            value: bytes_to_hex_string(&marker),
-        });
-    }
+pub fn read(data: Vec<u8>, track_index: Option<u32>) -> Result<Track, SolReadError> {
+    read_with_limits(data, track_index, &ReadLimits::default())
+}
 
-    let sol_name = parse_string::<BigEndian>(&mut cursor, StringLength::U16)?;
-    if sol_name.as_str() != "savedLines" {
-        return Err(SolReadError::InvalidData {
-            name: "sol name".to_string(),
-            value: sol_name.to_string(),
-        });
-    }
+pub fn read_with_limits(
+    data: Vec<u8>,
+    track_index: Option<u32>,
+    limits: &ReadLimits,
+) -> Result<Track, SolReadError> {
+    read_with_recovery(data, track_index, &mut Recovery::strict(limits))
+}
 
-    let _padding = cursor.read_u32::<BigEndian>()?;
+/// Read a track, skipping broken lines and properties instead of failing
+pub fn read_lenient(
+    data: Vec<u8>,
+    track_index: Option<u32>,
+) -> Result<(Track, Vec<ReadWarning>), SolReadError> {
+    let mut recovery = Recovery::lenient(&ReadLimits::default());
+    let track = read_with_recovery(data, track_index, &mut recovery)?;
+    Ok((track, recovery.into_warnings()))
+}
 
-    let data_name = parse_string::<BigEndian>(&mut cursor, StringLength::U16)?;
-    if data_name.as_str() != "trackList" {
-        return Err(SolReadError::InvalidData {
-            name: "data name".to_string(),
-            value: data_name.to_string(),
-        });
-    }
+/// Read a track from a track list returned by [`read_raw`](super::raw::read_raw), so that a file
+/// with several tracks only has to be parsed once
+pub fn read_from_raw(
+    track_list: &Amf0Value,
+    track_index: Option<u32>,
+) -> Result<Track, SolReadError> {
+    let mut recovery = Recovery::strict(&ReadLimits::default());
+    read_track_list(track_list, track_index, &mut recovery)
+}
 
-    // Track Data
-    let current_pos = cursor.position();
-    // Slice from current position to last byte - 1 contains valid AMF0 format
-    let mut trimmed_cursor = cursor.take(data_size.saturating_sub(1) - current_pos);
-    let result = &deserialize(&mut trimmed_cursor)?;
+pub(crate) fn read_with_recovery(
+    data: Vec<u8>,
+    track_index: Option<u32>,
+    recovery: &mut Recovery,
+) -> Result<Track, SolReadError> {
+    let track_list = read_raw_with_budget(data, &mut recovery.budget)?;
+    read_track_list(&track_list, track_index, recovery)
+}
 
-    let track_list_amf = &result[0];
+fn read_track_list(
+    track_list_amf: &Amf0Value,
+    track_index: Option<u32>,
+    recovery: &mut Recovery,
+) -> Result<Track, SolReadError> {
+    let track_builder = &mut TrackBuilder::default();
     let track_list =
         track_list_amf
-            .clone()
-            .get_object_properties()
-            .ok_or(SolReadError::InvalidData {
+            .as_object_properties()
+            .ok_or_else(|| SolReadError::InvalidData {
                 name: "track list".to_string(),
                 value: format!("{:?}", track_list_amf),
             })?;
@@ -91,81 +85,78 @@ pub fn read(data: Vec<u8>, track_index: Option<u32>) -> Result<Track, SolReadErr
             value: format!("{:?}", target_track_index),
         })?;
 
+    let mut path = format!("trackList/{}", target_track_index);
+    read_track(track_builder, target_track_amf, &mut path, recovery)
+        .map_err(|err| err.with_context(ErrorContext::at_path(path)))?;
+
+    Ok(track_builder.build()?)
+}
+
+// Reads a track object into the track builder, keeping `path` updated with the location of the
+// value being read
+fn read_track(
+    track_builder: &mut TrackBuilder,
+    target_track_amf: &Amf0Value,
+    path: &mut String,
+    recovery: &mut Recovery,
+) -> Result<(), SolReadError> {
+    let track_path = path.clone();
     let target_track =
         target_track_amf
-            .clone()
-            .get_object_properties()
-            .ok_or(SolReadError::InvalidData {
+            .as_object_properties()
+            .ok_or_else(|| SolReadError::InvalidData {
                 name: "track".to_string(),
                 value: format!("{:?}", target_track_amf),
             })?;
 
+    track_builder
+        .extras()
+        .sol_flavor(detect_flavor(target_track));
+
     if let Some(val) = target_track.get("label") {
-        let title = val.clone().get_string().ok_or(SolReadError::InvalidData {
-            name: "label".to_string(),
-            value: format!("{:?}", val),
+        *path = format!("{}/label", track_path);
+        let title = recovery.recover(parse_label(val), "Skipped label", || {
+            ErrorContext::at_path(path.clone())
         })?;
-        track_builder.metadata().title(title);
+
+        if let Some(title) = title {
+            track_builder.metadata().title(title);
+        }
     }
 
-    if let Some(val) = target_track.get("version") {
-        let version_string = val.clone().get_string().ok_or(SolReadError::InvalidData {
-            name: "grid version".to_string(),
-            value: format!("{:?}", val),
-        })?;
+    let mut grid_version = GridVersion::V6_0;
 
-        let grid_version = match version_string.as_str() {
-            "6.0" => GridVersion::V6_0,
-            "6.1" => GridVersion::V6_1,
-            "6.2" => GridVersion::V6_2,
-            other => {
-                return Err(SolReadError::InvalidData {
-                    name: "grid version".to_string(),
-                    value: other.to_string(),
-                });
-            }
-        };
-        track_builder.metadata().grid_version(grid_version);
-    } else {
-        track_builder.metadata().grid_version(GridVersion::V6_0);
+    if let Some(val) = target_track.get("version") {
+        *path = format!("{}/version", track_path);
+        let version = recovery.recover(
+            parse_grid_version(val),
+            "Used grid version 6.0 instead",
+            || ErrorContext::at_path(path.clone()),
+        )?;
+
+        if let Some(version) = version {
+            grid_version = version;
+        }
     }
 
+    track_builder.metadata().grid_version(grid_version);
+
     if let Some(val) = target_track.get("startLine") {
+        *path = format!("{}/startLine", track_path);
         let start_position =
-            val.clone()
-                .get_object_properties()
-                .ok_or(SolReadError::InvalidData {
-                    name: "start line".to_string(),
-                    value: format!("{:?}", val),
-                })?;
-
-        let start_x_amf = start_position.get("0").ok_or(SolReadError::InvalidData {
-            name: "start line x".to_string(),
-            value: format!("{:?}", start_position),
-        })?;
-        let start_pos_x = start_x_amf
-            .clone()
-            .get_number()
-            .ok_or(SolReadError::InvalidData {
-                name: "start x value".to_string(),
-                value: format!("{:?}", start_x_amf),
+            recovery.recover(parse_start_position(val), "Skipped start position", || {
+                ErrorContext::at_path(path.clone())
             })?;
 
-        let start_y_amf = start_position.get("1").ok_or(SolReadError::InvalidData {
-            name: "start line y".to_string(),
-            value: format!("{:?}", start_position),
-        })?;
-        let start_pos_y = start_y_amf
-            .clone()
-            .get_number()
-            .ok_or(SolReadError::InvalidData {
-                name: "start y value".to_string(),
-                value: format!("{:?}", start_y_amf),
-            })?;
+        if let Some(start_position) = start_position {
+            track_builder.metadata().start_position(start_position);
+        }
+    }
 
-        track_builder
-            .metadata()
-            .start_position(Vec2::new(start_pos_x, start_pos_y));
+    for (name, value) in target_track {
+        if !KNOWN_TRACK_PROPERTIES.contains(&name.as_str()) {
+            track_builder.extras().sol_property(name, value.clone());
+        }
     }
 
     if target_track.contains_key("trackData") {
@@ -173,183 +164,256 @@ pub fn read(data: Vec<u8>, track_index: Option<u32>) -> Result<Track, SolReadErr
     }
 
     if let Some(val) = target_track.get("data") {
+        *path = format!("{}/data", track_path);
         let lines_list = val
-            .clone()
-            .get_object_properties()
-            .ok_or(SolReadError::InvalidData {
+            .as_object_properties()
+            .ok_or_else(|| SolReadError::InvalidData {
                 name: "lines list".to_string(),
                 value: format!("{:?}", val),
             })?;
 
-        for line_amf in lines_list.values() {
-            let line =
-                line_amf
-                    .clone()
-                    .get_object_properties()
-                    .ok_or(SolReadError::InvalidData {
-                        name: "line".to_string(),
-                        value: format!("{:?}", line_amf),
-                    })?;
-
-            let x1_amf = line.get("0").ok_or(SolReadError::InvalidData {
-                name: "line".to_string(),
-                value: format!("{:?}", line),
-            })?;
+        recovery.budget.expect_lines(lines_list.len())?;
 
-            let x1 = x1_amf
-                .clone()
-                .get_number()
-                .ok_or(SolReadError::InvalidData {
-                    name: "line x1".to_string(),
-                    value: format!("{:?}", x1_amf),
-                })?;
-
-            let y1_amf = line.get("1").ok_or(SolReadError::InvalidData {
-                name: "line".to_string(),
-                value: format!("{:?}", line),
-            })?;
+        for (line_key, line_amf) in lines_list {
+            let line_path = format!("{}/data/{}", track_path, line_key);
+            *path = line_path.clone();
+            recovery.budget.add_line()?;
+            let line = read_line(track_builder, line_amf, &line_path, path);
 
-            let y1 = y1_amf
-                .clone()
-                .get_number()
-                .ok_or(SolReadError::InvalidData {
-                    name: "line y1".to_string(),
-                    value: format!("{:?}", y1_amf),
-                })?;
-
-            let x2_amf = line.get("2").ok_or(SolReadError::InvalidData {
-                name: "line".to_string(),
-                value: format!("{:?}", line),
-            })?;
+            recovery.recover(line, "Skipped line", || ErrorContext::at_path(path.clone()))?;
+        }
+    }
 
-            let x2 = x2_amf
-                .clone()
-                .get_number()
-                .ok_or(SolReadError::InvalidData {
-                    name: "line x2".to_string(),
-                    value: format!("{:?}", x2_amf),
-                })?;
-
-            let y2_amf = line.get("3").ok_or(SolReadError::InvalidData {
-                name: "line".to_string(),
-                value: format!("{:?}", line),
-            })?;
+    Ok(())
+}
 
-            let y2 = y2_amf
-                .clone()
-                .get_number()
-                .ok_or(SolReadError::InvalidData {
-                    name: "line y2".to_string(),
-                    value: format!("{:?}", y2_amf),
-                })?;
-
-            let ext_amf = line.get("4").ok_or(SolReadError::InvalidData {
-                name: "line".to_string(),
-                value: format!("{:?}", line),
-            })?;
+/// Work out which flash build wrote a track from the shapes of its values
+fn detect_flavor(target_track: &IndexMap<String, Amf0Value>) -> SolFlavor {
+    let lines = target_track
+        .get("data")
+        .and_then(Amf0Value::as_object_properties);
+    let any_line_slot = |slot: &str, predicate: fn(&Amf0Value) -> bool| {
+        lines.into_iter().flat_map(IndexMap::values).any(|line| {
+            line.as_object_properties()
+                .and_then(|line| line.get(slot))
+                .is_some_and(predicate)
+        })
+    };
 
-            let ext = ext_amf.clone().get_number().unwrap_or(0.0);
+    if matches!(target_track.get("startLine"), Some(Amf0Value::Object(_)))
+        || any_line_slot("5", |flipped| matches!(flipped, Amf0Value::Boolean(_)))
+    {
+        SolFlavor::Lra
+    } else if any_line_slot("4", |ext| matches!(ext, Amf0Value::Undefined)) {
+        SolFlavor::V6_7
+    } else if !target_track.contains_key("version") {
+        SolFlavor::Beta1
+    } else {
+        SolFlavor::Beta2
+    }
+}
 
-            let left_extension = ext == 1.0 || ext == 3.0;
-            let right_extension = ext == 2.0 || ext == 3.0;
+fn parse_label(val: &Amf0Value) -> Result<String, SolReadError> {
+    val.as_string()
+        .map(str::to_string)
+        .ok_or_else(|| SolReadError::InvalidData {
+            name: "label".to_string(),
+            value: format!("{:?}", val),
+        })
+}
 
-            let flipped_amf = line.get("5").ok_or(SolReadError::InvalidData {
-                name: "line".to_string(),
-                value: format!("{:?}", line),
-            })?;
+fn parse_grid_version(val: &Amf0Value) -> Result<GridVersion, SolReadError> {
+    let version_string = val.as_string().ok_or_else(|| SolReadError::InvalidData {
+        name: "grid version".to_string(),
+        value: format!("{:?}", val),
+    })?;
+
+    match version_string {
+        "6.0" => Ok(GridVersion::V6_0),
+        "6.1" => Ok(GridVersion::V6_1),
+        "6.2" => Ok(GridVersion::V6_2),
+        other => Err(SolReadError::InvalidData {
+            name: "grid version".to_string(),
+            value: other.to_string(),
+        }),
+    }
+}
 
-            let flipped = flipped_amf
-                .clone()
-                .get_boolean()
-                .or_else(|| flipped_amf.clone().get_number().map(|num| num == 1.0))
-                .ok_or(SolReadError::InvalidData {
-                    name: "line flipped".to_string(),
-                    value: format!("{:?}", flipped_amf),
-                })?;
-
-            let id_amf = line.get("8").ok_or(SolReadError::InvalidData {
-                name: "line".to_string(),
-                value: format!("{:?}", line),
-            })?;
+fn parse_start_position(val: &Amf0Value) -> Result<Vec2, SolReadError> {
+    let start_position = val
+        .as_object_properties()
+        .ok_or_else(|| SolReadError::InvalidData {
+            name: "start line".to_string(),
+            value: format!("{:?}", val),
+        })?;
 
-            let id_float = id_amf
-                .clone()
-                .get_number()
-                .ok_or(SolReadError::InvalidData {
-                    name: "line id".to_string(),
-                    value: format!("{:?}", id_amf),
-                })?;
-
-            let unsafe_id =
-                if id_float.is_finite() && id_float >= 0.0 && id_float <= f64::from(u32::MAX) {
-                    Some(id_float as u32)
-                } else {
-                    None
-                };
-
-            let id = match unsafe_id {
-                Some(val) => val,
-                None => {
-                    return Err(SolReadError::InvalidData {
-                        name: "line id".to_string(),
-                        value: id_float.to_string(),
-                    });
-                }
-            };
-
-            let line_type_amf = line.get("9").ok_or(SolReadError::InvalidData {
-                name: "line".to_string(),
-                value: format!("{:?}", line),
-            })?;
+    let start_x_amf = start_position
+        .get("0")
+        .ok_or_else(|| SolReadError::InvalidData {
+            name: "start line x".to_string(),
+            value: format!("{:?}", start_position),
+        })?;
+    let start_pos_x = start_x_amf
+        .as_number()
+        .ok_or_else(|| SolReadError::InvalidData {
+            name: "start x value".to_string(),
+            value: format!("{:?}", start_x_amf),
+        })?;
+
+    let start_y_amf = start_position
+        .get("1")
+        .ok_or_else(|| SolReadError::InvalidData {
+            name: "start line y".to_string(),
+            value: format!("{:?}", start_position),
+        })?;
+    let start_pos_y = start_y_amf
+        .as_number()
+        .ok_or_else(|| SolReadError::InvalidData {
+            name: "start y value".to_string(),
+            value: format!("{:?}", start_y_amf),
+        })?;
+
+    Ok(Vec2::new(start_pos_x, start_pos_y))
+}
 
-            let line_type_numeric =
-                line_type_amf
-                    .clone()
-                    .get_number()
-                    .ok_or(SolReadError::InvalidData {
-                        name: "line type".to_string(),
-                        value: format!("{:?}", line_type_amf),
-                    })?;
-
-            let line_type = match line_type_numeric {
-                0.0 => LineType::Standard,
-                1.0 => LineType::Acceleration,
-                2.0 => LineType::Scenery,
-                other => {
-                    return Err(SolReadError::InvalidData {
-                        name: "line type".to_string(),
-                        value: other.to_string(),
-                    });
-                }
-            };
-
-            let endpoints = (Vec2::new(x1, y1), Vec2::new(x2, y2));
-
-            match line_type {
-                LineType::Standard => {
-                    track_builder.line_group().add_standard_line(
-                        id,
-                        endpoints,
-                        flipped,
-                        left_extension,
-                        right_extension,
-                    );
-                }
-                LineType::Acceleration => {
-                    track_builder.line_group().add_acceleration_line(
-                        id,
-                        endpoints,
-                        flipped,
-                        left_extension,
-                        right_extension,
-                    );
-                }
-                LineType::Scenery => {
-                    track_builder.line_group().add_scenery_line(id, endpoints);
-                }
-            }
+// Reads a line array into the track builder, adding it only once every slot has been read
+fn read_line(
+    track_builder: &mut TrackBuilder,
+    line_amf: &Amf0Value,
+    line_path: &str,
+    path: &mut String,
+) -> Result<(), SolReadError> {
+    let line = line_amf
+        .as_object_properties()
+        .ok_or_else(|| SolReadError::InvalidData {
+            name: "line".to_string(),
+            value: format!("{:?}", line_amf),
+        })?;
+
+    let x1 = line_number(line, "0", "line x1", line_path, path)?;
+    let y1 = line_number(line, "1", "line y1", line_path, path)?;
+    let x2 = line_number(line, "2", "line x2", line_path, path)?;
+    let y2 = line_number(line, "3", "line y2", line_path, path)?;
+
+    let ext = line_slot(line, "4", line_path, path)?
+        .as_number()
+        .unwrap_or(0.0);
+
+    let left_extension = ext == 1.0 || ext == 3.0;
+    let right_extension = ext == 2.0 || ext == 3.0;
+
+    let flipped_amf = line_slot(line, "5", line_path, path)?;
+
+    let flipped = flipped_amf
+        .as_boolean()
+        .or_else(|| flipped_amf.as_number().map(|num| num == 1.0))
+        .ok_or_else(|| SolReadError::InvalidData {
+            name: "line flipped".to_string(),
+            value: format!("{:?}", flipped_amf),
+        })?;
+
+    let id_float = line_number(line, "8", "line id", line_path, path)?;
+
+    let id = if id_float.is_finite() && id_float >= 0.0 && id_float <= f64::from(u32::MAX) {
+        id_float as u32
+    } else {
+        return Err(SolReadError::InvalidData {
+            name: "line id".to_string(),
+            value: id_float.to_string(),
+        });
+    };
+
+    let line_type = match line_number(line, "9", "line type", line_path, path)? {
+        0.0 => LineType::Standard,
+        1.0 => LineType::Acceleration,
+        2.0 => LineType::Scenery,
+        other => {
+            return Err(SolReadError::InvalidData {
+                name: "line type".to_string(),
+                value: other.to_string(),
+            });
+        }
+    };
+
+    *path = format!("{}/6", line_path);
+    let previous = parse_linked_id(line.get("6"))?;
+    *path = format!("{}/7", line_path);
+    let next = parse_linked_id(line.get("7"))?;
+
+    *path = line_path.to_string();
+    let endpoints = (Vec2::new(x1, y1), Vec2::new(x2, y2));
+
+    match line_type {
+        LineType::Standard => {
+            track_builder.line_group().add_standard_line(
+                id,
+                endpoints,
+                flipped,
+                left_extension,
+                right_extension,
+            );
+        }
+        LineType::Acceleration => {
+            track_builder.line_group().add_acceleration_line(
+                id,
+                endpoints,
+                flipped,
+                left_extension,
+                right_extension,
+            );
+        }
+        LineType::Scenery => {
+            track_builder.line_group().add_scenery_line(id, endpoints);
         }
     }
 
-    Ok(track_builder.build()?)
+    track_builder
+        .line_group()
+        .adjacency()
+        .set(id, LineLink::new(previous, next));
+
+    Ok(())
+}
+
+fn line_slot<'a>(
+    line: &'a IndexMap<String, Amf0Value>,
+    slot: &str,
+    line_path: &str,
+    path: &mut String,
+) -> Result<&'a Amf0Value, SolReadError> {
+    *path = format!("{}/{}", line_path, slot);
+    line.get(slot).ok_or_else(|| SolReadError::InvalidData {
+        name: "line".to_string(),
+        value: format!("{:?}", line),
+    })
+}
+
+fn line_number(
+    line: &IndexMap<String, Amf0Value>,
+    slot: &str,
+    name: &str,
+    line_path: &str,
+    path: &mut String,
+) -> Result<f64, SolReadError> {
+    let value = line_slot(line, slot, line_path, path)?;
+    value.as_number().ok_or_else(|| SolReadError::InvalidData {
+        name: name.to_string(),
+        value: format!("{:?}", value),
+    })
+}
+
+/// Parse the id of a connected line, which flash leaves null when there is none
+fn parse_linked_id(value: Option<&Amf0Value>) -> Result<Option<u32>, SolReadError> {
+    match value {
+        None | Some(Amf0Value::Null) | Some(Amf0Value::Undefined) => Ok(None),
+        Some(Amf0Value::Number(id))
+            if id.is_finite() && *id >= 0.0 && *id <= f64::from(u32::MAX) =>
+        {
+            Ok(Some(*id as u32))
+        }
+        Some(other) => Err(SolReadError::InvalidData {
+            name: "linked line id".to_string(),
+            value: format!("{:?}", other),
+        }),
+    }
 }
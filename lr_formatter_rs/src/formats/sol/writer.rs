@@ -1,90 +1,51 @@
-use byteorder::{BigEndian, WriteBytesExt};
-use std::{
-    collections::HashMap,
-    io::{Cursor, Seek, Write},
-};
+use indexmap::IndexMap;
 
 use crate::{
-    formats::sol::{
-        SolWriteError,
-        amf0::{Amf0Value, serialize},
-    },
-    track::{GridVersion, Track, Vec2},
+    formats::sol::{SolFlavor, SolWriteError, SolWriteOptions, amf0::Amf0Value, raw::write_raw},
+    track::{GridVersion, LineType, Track, Vec2},
 };
 
 pub fn write(track: &Track) -> Result<Vec<u8>, SolWriteError> {
-    let mut cursor = Cursor::new(Vec::new());
-
-    cursor.write_all(b"\x00\xBF")?;
-    cursor.write_all(b"\x00\x00\x00\x00")?;
-    cursor.write_all(b"TCSO")?;
-    cursor.write_all(b"\x00\x04\x00\x00\x00\x00")?;
-    cursor.write_all(b"\x00\x0AsavedLines")?;
-    cursor.write_all(b"\x00\x00\x00\x00")?;
-    cursor.write_all(b"\x00\x09trackList")?;
+    write_with_options(track, &SolWriteOptions::default())
+}
 
+pub fn write_with_options(
+    track: &Track,
+    options: &SolWriteOptions,
+) -> Result<Vec<u8>, SolWriteError> {
+    let flavor = options
+        .flavor
+        .or(track.extras().sol_flavor())
+        .unwrap_or_default();
     let mut lines_vec = vec![];
 
-    for line in track.line_group().standard_lines() {
-        let ext = if line.left_extension() { 1.0 } else { 0.0 }
-            + if line.right_extension() { 2.0 } else { 0.0 };
-        let inv = if line.flipped() { 1.0 } else { 0.0 };
-        let numeric_line_type = 0.0;
-        let mut line_object = HashMap::new();
-        line_object.insert("0".to_string(), Amf0Value::Number(line.x1()));
-        line_object.insert("1".to_string(), Amf0Value::Number(line.y1()));
-        line_object.insert("2".to_string(), Amf0Value::Number(line.x2()));
-        line_object.insert("3".to_string(), Amf0Value::Number(line.y2()));
-        line_object.insert("4".to_string(), Amf0Value::Number(ext));
-        line_object.insert("5".to_string(), Amf0Value::Number(inv));
-        line_object.insert("6".to_string(), Amf0Value::Number(0.0));
-        line_object.insert("7".to_string(), Amf0Value::Number(0.0));
+    let adjacency = track.line_group().adjacency();
+
+    for line in track.line_group().iter() {
+        let link = adjacency.get(line.id());
+        let (start, end) = line.endpoints();
+        let ext = extension_value(flavor, line.left_extension(), line.right_extension());
+        let inv = flipped_value(flavor, line.flipped());
+        let numeric_line_type = match line.line_type() {
+            LineType::Standard => 0.0,
+            LineType::Acceleration => 1.0,
+            LineType::Scenery => 2.0,
+        };
+        let mut line_object = IndexMap::new();
+        line_object.insert("0".to_string(), Amf0Value::Number(start.x()));
+        line_object.insert("1".to_string(), Amf0Value::Number(start.y()));
+        line_object.insert("2".to_string(), Amf0Value::Number(end.x()));
+        line_object.insert("3".to_string(), Amf0Value::Number(end.y()));
+        line_object.insert("4".to_string(), ext);
+        line_object.insert("5".to_string(), inv);
+        line_object.insert("6".to_string(), linked_id(link.previous()));
+        line_object.insert("7".to_string(), linked_id(link.next()));
         line_object.insert("8".to_string(), Amf0Value::Number(f64::from(line.id())));
         line_object.insert("9".to_string(), Amf0Value::Number(numeric_line_type));
         lines_vec.push(line_object);
     }
 
-    for line in track.line_group().acceleration_lines() {
-        let ext = if line.left_extension() { 1.0 } else { 0.0 }
-            + if line.right_extension() { 2.0 } else { 0.0 };
-        let inv = if line.flipped() { 1.0 } else { 0.0 };
-        let numeric_line_type = 1.0;
-        let mut line_object = HashMap::new();
-        line_object.insert("0".to_string(), Amf0Value::Number(line.x1()));
-        line_object.insert("1".to_string(), Amf0Value::Number(line.y1()));
-        line_object.insert("2".to_string(), Amf0Value::Number(line.x2()));
-        line_object.insert("3".to_string(), Amf0Value::Number(line.y2()));
-        line_object.insert("4".to_string(), Amf0Value::Number(ext));
-        line_object.insert("5".to_string(), Amf0Value::Number(inv));
-        line_object.insert("6".to_string(), Amf0Value::Number(0.0));
-        line_object.insert("7".to_string(), Amf0Value::Number(0.0));
-        line_object.insert("8".to_string(), Amf0Value::Number(f64::from(line.id())));
-        line_object.insert("9".to_string(), Amf0Value::Number(numeric_line_type));
-        lines_vec.push(line_object);
-    }
-
-    for line in track.line_group().scenery_lines() {
-        let mut line_object = HashMap::new();
-        line_object.insert("0".to_string(), Amf0Value::Number(line.x1()));
-        line_object.insert("1".to_string(), Amf0Value::Number(line.y1()));
-        line_object.insert("2".to_string(), Amf0Value::Number(line.x2()));
-        line_object.insert("3".to_string(), Amf0Value::Number(line.y2()));
-        line_object.insert("4".to_string(), Amf0Value::Number(0.0));
-        line_object.insert("5".to_string(), Amf0Value::Number(0.0));
-        line_object.insert("6".to_string(), Amf0Value::Number(0.0));
-        line_object.insert("7".to_string(), Amf0Value::Number(0.0));
-        line_object.insert("8".to_string(), Amf0Value::Number(f64::from(line.id())));
-        line_object.insert("9".to_string(), Amf0Value::Number(2.0));
-        lines_vec.push(line_object);
-    }
-
-    lines_vec.sort_unstable_by(|line_a, line_b| {
-        let id_a = line_a.get("8").unwrap().clone().get_number().unwrap();
-        let id_b = line_b.get("8").unwrap().clone().get_number().unwrap();
-        id_b.partial_cmp(&id_a).unwrap()
-    });
-
-    let mut line_array_object = HashMap::new();
+    let mut line_array_object = IndexMap::new();
 
     for (index, line_object) in lines_vec.iter().enumerate() {
         line_array_object.insert(index.to_string(), Amf0Value::ECMAArray(line_object.clone()));
@@ -97,9 +58,15 @@ pub fn write(track: &Track) -> Result<Vec<u8>, SolWriteError> {
     }
     .to_string();
 
-    let line_count = track.line_group().standard_lines().len()
-        + track.line_group().acceleration_lines().len()
-        + track.line_group().scenery_lines().len();
+    // Beta 1 has no version property, so its tracks are always read as 6.0
+    if flavor == SolFlavor::Beta1 && !matches!(track.metadata().grid_version(), GridVersion::V6_0) {
+        return Err(SolWriteError::UnsupportedGridVersion {
+            flavor,
+            version: string_grid_version,
+        });
+    }
+
+    let line_count = track.line_group().line_count();
 
     let start_position = if let Some(start_pos) = track.metadata().start_position() {
         start_pos
@@ -107,23 +74,23 @@ pub fn write(track: &Track) -> Result<Vec<u8>, SolWriteError> {
         Vec2::new(0.0, 0.0)
     };
 
-    let mut array_start_position = HashMap::new();
+    let mut array_start_position = IndexMap::new();
     array_start_position.insert("0".to_string(), Amf0Value::Number(start_position.x()));
     array_start_position.insert("1".to_string(), Amf0Value::Number(start_position.y()));
 
-    let mut first_null_array = HashMap::new();
+    let mut first_null_array = IndexMap::new();
     first_null_array.insert("0".to_string(), Amf0Value::Null);
     first_null_array.insert("1".to_string(), Amf0Value::Null);
     first_null_array.insert("2".to_string(), Amf0Value::Null);
 
-    let mut second_null_array = HashMap::new();
+    let mut second_null_array = IndexMap::new();
     second_null_array.insert("0".to_string(), Amf0Value::Null);
     second_null_array.insert("1".to_string(), Amf0Value::Null);
     second_null_array.insert("2".to_string(), Amf0Value::Null);
     second_null_array.insert("3".to_string(), Amf0Value::Null);
     second_null_array.insert("4".to_string(), Amf0Value::Boolean(true));
 
-    let mut track_data = HashMap::new();
+    let mut track_data = IndexMap::new();
     track_data.insert("0".to_string(), Amf0Value::Null);
     track_data.insert("1".to_string(), Amf0Value::ECMAArray(first_null_array));
     track_data.insert("2".to_string(), Amf0Value::ECMAArray(second_null_array));
@@ -134,39 +101,66 @@ pub fn write(track: &Track) -> Result<Vec<u8>, SolWriteError> {
         ""
     };
 
-    let mut sol_track = HashMap::new();
+    // Keys are inserted in the order the flash editor saves them in
+    let mut sol_track = IndexMap::new();
+    if flavor != SolFlavor::Beta1 {
+        sol_track.insert(
+            "version".to_string(),
+            Amf0Value::Utf8String(string_grid_version),
+        );
+    }
+    let start_line = if flavor == SolFlavor::Lra {
+        Amf0Value::Object(array_start_position)
+    } else {
+        Amf0Value::ECMAArray(array_start_position)
+    };
+    sol_track.insert("startLine".to_string(), start_line);
+    sol_track.insert("level".to_string(), Amf0Value::Number(line_count as f64));
+    sol_track.insert("data".to_string(), Amf0Value::ECMAArray(line_array_object));
     sol_track.insert(
         "label".to_string(),
         Amf0Value::Utf8String(label.to_string()),
     );
-    sol_track.insert(
-        "version".to_string(),
-        Amf0Value::Utf8String(string_grid_version),
-    );
-    sol_track.insert(
-        "startLine".to_string(),
-        Amf0Value::ECMAArray(array_start_position),
-    );
-    sol_track.insert("level".to_string(), Amf0Value::Number(line_count as f64));
-    sol_track.insert("data".to_string(), Amf0Value::ECMAArray(line_array_object));
 
     if track.metadata().zero_velocity_start_riders() {
         sol_track.insert("trackData".to_string(), Amf0Value::ECMAArray(track_data));
     }
 
-    let mut track_list = HashMap::new();
+    for (name, value) in track.extras().sol_properties() {
+        if !sol_track.contains_key(name) {
+            sol_track.insert(name.clone(), value.clone());
+        }
+    }
+
+    let mut track_list = IndexMap::new();
     track_list.insert("0".to_string(), Amf0Value::Object(sol_track));
 
-    // Serialize and write the data
-    let data = vec![Amf0Value::ECMAArray(track_list)];
-    let buffer = serialize(&data)?;
-    cursor.write_all(&buffer)?;
-    cursor.write_u8(0x00)?;
+    write_raw(&Amf0Value::ECMAArray(track_list))
+}
 
-    // Go back to write file size
-    let file_size = u32::try_from(cursor.position() - 6)?;
-    cursor.seek(std::io::SeekFrom::Start(2))?;
-    cursor.write_u32::<BigEndian>(file_size)?;
+/// Flash stores missing line connections as undefined
+fn linked_id(id: Option<u32>) -> Amf0Value {
+    match id {
+        Some(id) => Amf0Value::Number(f64::from(id)),
+        None => Amf0Value::Undefined,
+    }
+}
+
+/// Bit flags of the line extensions, which 6.7 leaves undefined
+fn extension_value(flavor: SolFlavor, left: bool, right: bool) -> Amf0Value {
+    if flavor == SolFlavor::V6_7 {
+        return Amf0Value::Undefined;
+    }
 
-    Ok(cursor.into_inner())
+    let ext = if left { 1.0 } else { 0.0 } + if right { 2.0 } else { 0.0 };
+    Amf0Value::Number(ext)
+}
+
+/// LRA writes flipped as a boolean where the flash builds use a number
+fn flipped_value(flavor: SolFlavor, flipped: bool) -> Amf0Value {
+    if flavor == SolFlavor::Lra {
+        Amf0Value::Boolean(flipped)
+    } else {
+        Amf0Value::Number(if flipped { 1.0 } else { 0.0 })
+    }
 }
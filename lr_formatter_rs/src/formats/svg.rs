@@ -0,0 +1,132 @@
+//! Vector drawings from editors such as Inkscape, imported as lines
+//!
+//! Only importing is supported. Coordinates are used as they are, so one user unit becomes one
+//! track unit, and groups become layers.
+
+mod error;
+mod path;
+mod reader;
+mod xml;
+
+pub use error::SvgReadError;
+pub use reader::{read, read_with_options};
+
+use std::collections::HashMap;
+
+use crate::{
+    geometry::RideSide,
+    track::{LineType, RGBColor},
+};
+
+/// Options for importing SVG drawings
+#[derive(Debug, Clone)]
+pub struct SvgReadOptions {
+    /// Longest a line may be, curves and circles are split into lines of about this length
+    pub segment_length: f64,
+    /// Type of lines whose stroke color and groups are not mapped to a line type
+    pub default_line_type: LineType,
+    /// Line types of elements by their stroke color, or their fill color if they have no stroke
+    pub colors: Vec<(RGBColor, LineType)>,
+    /// Line types of elements by the id or label of the element or any group it is in, which
+    /// take precedence over colors
+    pub group_line_types: HashMap<String, LineType>,
+    /// Side of standard and acceleration lines riders collide with
+    pub ride_side: RideSide,
+}
+
+impl Default for SvgReadOptions {
+    fn default() -> Self {
+        Self {
+            segment_length: 10.0,
+            default_line_type: LineType::Scenery,
+            // The colors Line Rider draws each line type in, along with their pure equivalents
+            colors: vec![
+                (RGBColor::new(0x00, 0x66, 0xFF), LineType::Standard),
+                (RGBColor::new(0xCC, 0x00, 0x00), LineType::Acceleration),
+                (RGBColor::new(0x00, 0xCC, 0x00), LineType::Scenery),
+                (RGBColor::new(0x00, 0x00, 0xFF), LineType::Standard),
+                (RGBColor::new(0xFF, 0x00, 0x00), LineType::Acceleration),
+                (RGBColor::new(0x00, 0xFF, 0x00), LineType::Scenery),
+            ],
+            group_line_types: HashMap::new(),
+            ride_side: RideSide::Left,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{SvgReadOptions, read_with_options};
+    use crate::track::{LineType, line::line_view::Line};
+
+    const DRAWING: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" xmlns:inkscape="http://www.inkscape.org/namespaces/inkscape">
+  <!-- A loose line is put on its own layer -->
+  <line x1="0" y1="0" x2="30" y2="0" stroke="#0066ff" />
+  <g id="scenery" inkscape:groupmode="layer" inkscape:label="Trees" style="stroke:#00cc00;stroke-width:4">
+    <rect x="0" y="0" width="10" height="20" />
+    <g transform="translate(100 0) scale(2)">
+      <polyline points="0,0 5,0" />
+    </g>
+  </g>
+  <g id="folder" inkscape:groupmode="layer">
+    <g id="ramps" inkscape:groupmode="layer" stroke="red">
+      <path d="M0 0 L20 0 Z" />
+    </g>
+    <g id="hidden" inkscape:groupmode="layer" style="display:none">
+      <circle cx="0" cy="0" r="5" stroke="#000" />
+    </g>
+  </g>
+</svg>"##;
+
+    #[test]
+    fn elements_become_lines_on_the_layers_of_their_groups() {
+        let options = SvgReadOptions {
+            group_line_types: HashMap::from([("hidden".to_string(), LineType::Standard)]),
+            ..Default::default()
+        };
+        let track = read_with_options(DRAWING.as_bytes().to_vec(), &options).unwrap();
+
+        let layer_group = track.layer_group().as_ref().unwrap();
+        let layers = layer_group.layers();
+        let names = layers
+            .iter()
+            .map(|layer| layer.name().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["Base Layer", "Trees", "ramps", "hidden"]);
+        assert_eq!(layers[3].visible(), Some(false));
+        assert_eq!(layers[2].folder_id(), Some(Some(2)));
+        let folders = layer_group.layer_folders().as_ref().unwrap();
+        assert_eq!(folders[0].name().as_deref(), Some("folder"));
+        assert_eq!(folders[0].size(), Some(2));
+
+        let lines = track.line_group().iter().collect::<Vec<Line>>();
+        let types = lines.iter().map(Line::line_type).collect::<Vec<_>>();
+        assert_eq!(types[0], LineType::Standard);
+        assert!(
+            types[1..6]
+                .iter()
+                .all(|line_type| *line_type == LineType::Scenery)
+        );
+        assert!(
+            types[6..8]
+                .iter()
+                .all(|line_type| *line_type == LineType::Acceleration)
+        );
+        assert!(
+            types[8..]
+                .iter()
+                .all(|line_type| *line_type == LineType::Standard)
+        );
+
+        // The rectangle is closed, and strokes are twice the width of scenery lines
+        assert_eq!(lines[4].endpoints().1.y(), 0.0);
+        assert_eq!(lines[1].width(), Some(2.0));
+        // Scaled groups scale their lines and stroke widths
+        assert_eq!(lines[5].endpoints().1.x(), 110.0);
+        assert_eq!(lines[5].width(), Some(4.0));
+        assert_eq!(lines[5].layer(), Some(1));
+    }
+}
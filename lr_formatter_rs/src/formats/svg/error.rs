@@ -0,0 +1,24 @@
+use std::string::FromUtf8Error;
+
+use thiserror::Error;
+
+use crate::{
+    geometry::ShapeError,
+    track::{TrackBuilderError, layer::layer_group::LayerGroupBuilderError},
+};
+
+#[derive(Error, Debug)]
+pub enum SvgReadError {
+    #[error("{0}")]
+    Utf8(#[from] FromUtf8Error),
+    #[error("Invalid XML at byte {position}: {message}")]
+    Xml { position: usize, message: String },
+    #[error("Invalid value for `{name}`: {value}")]
+    InvalidData { name: String, value: String },
+    #[error("{0}")]
+    Shape(#[from] ShapeError),
+    #[error("{0}")]
+    TrackGroup(#[from] TrackBuilderError),
+    #[error("{0}")]
+    LayerGroup(#[from] LayerGroupBuilderError),
+}
@@ -0,0 +1,378 @@
+//! Flattening of SVG path data into lists of points
+
+use std::f64::consts::TAU;
+
+use crate::{
+    formats::svg::SvgReadError,
+    geometry::{Shape, ShapeError},
+    track::Vec2,
+};
+
+/// Most points a single arc may be split into
+const MAX_ARC_SEGMENTS: f64 = 1_000_000.0;
+
+/// A connected run of points from one move command to the next
+#[derive(Debug)]
+pub(super) struct Subpath {
+    pub points: Vec<Vec2>,
+    pub closed: bool,
+}
+
+/// Flatten path data into subpaths, splitting curves into segments of about `segment_length`
+pub(super) fn parse_path(data: &str, segment_length: f64) -> Result<Vec<Subpath>, SvgReadError> {
+    let mut scanner = Scanner { data, position: 0 };
+    let mut subpaths: Vec<Subpath> = vec![];
+    let mut current = Vec2::new(0.0, 0.0);
+    let mut start = current;
+    // Reflected by the smooth curve commands
+    let mut last_control: Option<(u8, Vec2)> = None;
+    let mut command = None;
+
+    loop {
+        scanner.skip_separators();
+        let Some(next) = scanner.peek() else {
+            break;
+        };
+
+        let letter = if next.is_ascii_alphabetic() {
+            scanner.position += 1;
+            next
+        } else {
+            // Numbers after a command repeat it, except that a move is followed by lines
+            match command {
+                Some(b'M') => b'L',
+                Some(b'm') => b'l',
+                Some(b'Z' | b'z') | None => return Err(scanner.error("Expected a command")),
+                Some(command) => command,
+            }
+        };
+        command = Some(letter);
+
+        let relative = letter.is_ascii_lowercase();
+        let origin = if relative {
+            current
+        } else {
+            Vec2::new(0.0, 0.0)
+        };
+        let point = |scanner: &mut Scanner| -> Result<Vec2, SvgReadError> {
+            let x = scanner.number()?;
+            let y = scanner.number()?;
+            Ok(Vec2::new(origin.x() + x, origin.y() + y))
+        };
+
+        let mut points = vec![];
+        let mut control = None;
+
+        match letter.to_ascii_uppercase() {
+            b'M' => {
+                current = point(&mut scanner)?;
+                start = current;
+                subpaths.push(Subpath {
+                    points: vec![current],
+                    closed: false,
+                });
+            }
+            b'L' => points.push(point(&mut scanner)?),
+            b'H' => {
+                let x = scanner.number()? + if relative { current.x() } else { 0.0 };
+                points.push(Vec2::new(x, current.y()));
+            }
+            b'V' => {
+                let y = scanner.number()? + if relative { current.y() } else { 0.0 };
+                points.push(Vec2::new(current.x(), y));
+            }
+            b'C' | b'S' => {
+                let control_1 = if letter.eq_ignore_ascii_case(&b'C') {
+                    point(&mut scanner)?
+                } else {
+                    reflect(last_control, b'C', current)
+                };
+                let control_2 = point(&mut scanner)?;
+                let end = point(&mut scanner)?;
+                let curve = Shape::CubicBezier(current, control_1, control_2, end);
+                points.extend(curve_points(&curve, segment_length)?);
+                control = Some((b'C', control_2));
+            }
+            b'Q' | b'T' => {
+                let control_1 = if letter.eq_ignore_ascii_case(&b'Q') {
+                    point(&mut scanner)?
+                } else {
+                    reflect(last_control, b'Q', current)
+                };
+                let end = point(&mut scanner)?;
+                let curve = Shape::QuadraticBezier(current, control_1, end);
+                points.extend(curve_points(&curve, segment_length)?);
+                control = Some((b'Q', control_1));
+            }
+            b'A' => {
+                let radii = (scanner.number()?, scanner.number()?);
+                let rotation = scanner.number()?;
+                let large_arc = scanner.flag()?;
+                let sweep = scanner.flag()?;
+                let end = point(&mut scanner)?;
+                points.extend(arc_points(
+                    current,
+                    radii,
+                    rotation,
+                    (large_arc, sweep),
+                    end,
+                    segment_length,
+                )?);
+            }
+            b'Z' => {
+                if let Some(subpath) = subpaths.last_mut() {
+                    subpath.closed = true;
+                }
+                current = start;
+            }
+            _ => return Err(scanner.error("Unknown command")),
+        }
+
+        last_control = control;
+
+        if let Some(end) = points.last() {
+            current = *end;
+            // Drawing without a move first starts from the origin
+            if subpaths.last().is_none_or(|subpath| subpath.closed) {
+                subpaths.push(Subpath {
+                    points: vec![start],
+                    closed: false,
+                });
+            }
+            if let Some(subpath) = subpaths.last_mut() {
+                subpath.points.extend(points);
+            }
+        }
+    }
+
+    Ok(subpaths)
+}
+
+/// Reflect the last control point of a curve of the same kind around the current point
+fn reflect(last_control: Option<(u8, Vec2)>, kind: u8, current: Vec2) -> Vec2 {
+    match last_control {
+        Some((last_kind, control)) if last_kind == kind => Vec2::new(
+            2.0 * current.x() - control.x(),
+            2.0 * current.y() - control.y(),
+        ),
+        _ => current,
+    }
+}
+
+/// Points of a curve after its first point, which is the current point
+fn curve_points(curve: &Shape, segment_length: f64) -> Result<Vec<Vec2>, SvgReadError> {
+    let mut points = curve.tessellate(segment_length)?;
+    points.remove(0);
+    Ok(points)
+}
+
+/// Points of an elliptical arc after its first point, following the endpoint to center
+/// conversion in the SVG specification
+fn arc_points(
+    from: Vec2,
+    (rx, ry): (f64, f64),
+    rotation: f64,
+    (large_arc, sweep): (bool, bool),
+    to: Vec2,
+    segment_length: f64,
+) -> Result<Vec<Vec2>, SvgReadError> {
+    let (mut rx, mut ry) = (rx.abs(), ry.abs());
+    if rx == 0.0 || ry == 0.0 {
+        return Ok(vec![to]);
+    }
+
+    let (sin, cos) = rotation.to_radians().sin_cos();
+    let dx = (from.x() - to.x()) / 2.0;
+    let dy = (from.y() - to.y()) / 2.0;
+    let x1 = cos * dx + sin * dy;
+    let y1 = -sin * dx + cos * dy;
+
+    let scale = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+    if scale > 1.0 {
+        rx *= scale.sqrt();
+        ry *= scale.sqrt();
+    }
+
+    let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+    let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+    let sign = if large_arc == sweep { -1.0 } else { 1.0 };
+    let coefficient = if denominator > 0.0 {
+        sign * (numerator / denominator).max(0.0).sqrt()
+    } else {
+        0.0
+    };
+    let center_x1 = coefficient * rx * y1 / ry;
+    let center_y1 = -coefficient * ry * x1 / rx;
+    let center = Vec2::new(
+        cos * center_x1 - sin * center_y1 + (from.x() + to.x()) / 2.0,
+        sin * center_x1 + cos * center_y1 + (from.y() + to.y()) / 2.0,
+    );
+
+    let angle = |ux: f64, uy: f64, vx: f64, vy: f64| (ux * vy - uy * vx).atan2(ux * vx + uy * vy);
+    let start_x = (x1 - center_x1) / rx;
+    let start_y = (y1 - center_y1) / ry;
+    let start_angle = angle(1.0, 0.0, start_x, start_y);
+    let mut sweep_angle = angle(
+        start_x,
+        start_y,
+        (-x1 - center_x1) / rx,
+        (-y1 - center_y1) / ry,
+    );
+    if !sweep && sweep_angle > 0.0 {
+        sweep_angle -= TAU;
+    } else if sweep && sweep_angle < 0.0 {
+        sweep_angle += TAU;
+    }
+
+    let count = (sweep_angle.abs() * rx.max(ry) / segment_length)
+        .ceil()
+        .max(1.0);
+    if !count.is_finite() || count > MAX_ARC_SEGMENTS {
+        return Err(ShapeError::TooManySegments(MAX_ARC_SEGMENTS as usize).into());
+    }
+    let count = count as usize;
+
+    let mut points = (1..count)
+        .map(|i| {
+            let (sin_t, cos_t) = (start_angle + sweep_angle * i as f64 / count as f64).sin_cos();
+            Vec2::new(
+                center.x() + rx * cos_t * cos - ry * sin_t * sin,
+                center.y() + rx * cos_t * sin + ry * sin_t * cos,
+            )
+        })
+        .collect::<Vec<_>>();
+    points.push(to);
+    Ok(points)
+}
+
+/// Parse a list of numbers, as used by `points` and `viewBox` attributes
+pub(super) fn parse_numbers(data: &str) -> Result<Vec<f64>, SvgReadError> {
+    let mut scanner = Scanner { data, position: 0 };
+    let mut numbers = vec![];
+
+    loop {
+        scanner.skip_separators();
+        if scanner.peek().is_none() {
+            return Ok(numbers);
+        }
+        numbers.push(scanner.number()?);
+    }
+}
+
+struct Scanner<'a> {
+    data: &'a str,
+    position: usize,
+}
+
+impl Scanner<'_> {
+    fn error(&self, message: &str) -> SvgReadError {
+        SvgReadError::InvalidData {
+            name: "path data".to_string(),
+            value: format!("{} at {} in \"{}\"", message, self.position, self.data),
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.data.as_bytes().get(self.position).copied()
+    }
+
+    fn skip_separators(&mut self) {
+        while self
+            .peek()
+            .is_some_and(|byte| byte.is_ascii_whitespace() || byte == b',')
+        {
+            self.position += 1;
+        }
+    }
+
+    fn number(&mut self) -> Result<f64, SvgReadError> {
+        self.skip_separators();
+        let bytes = self.data.as_bytes();
+        let start = self.position;
+        let mut end = start;
+
+        if matches!(bytes.get(end), Some(b'+' | b'-')) {
+            end += 1;
+        }
+        let mut seen_point = false;
+        while let Some(byte) = bytes.get(end) {
+            match byte {
+                b'0'..=b'9' => end += 1,
+                // A second decimal point starts the next number, as in "0.5.5"
+                b'.' if !seen_point => {
+                    seen_point = true;
+                    end += 1;
+                }
+                _ => break,
+            }
+        }
+        if matches!(bytes.get(end), Some(b'e' | b'E')) {
+            let mut exponent_end = end + 1;
+            if matches!(bytes.get(exponent_end), Some(b'+' | b'-')) {
+                exponent_end += 1;
+            }
+            if bytes.get(exponent_end).is_some_and(u8::is_ascii_digit) {
+                end = exponent_end;
+                while bytes.get(end).is_some_and(u8::is_ascii_digit) {
+                    end += 1;
+                }
+            }
+        }
+
+        let number = self.data[start..end]
+            .parse::<f64>()
+            .ok()
+            .filter(|number| number.is_finite())
+            .ok_or_else(|| self.error("Expected a number"))?;
+        self.position = end;
+        Ok(number)
+    }
+
+    // Arc flags are single digits that may be written without separators, as in "a1 1 0 011 1"
+    fn flag(&mut self) -> Result<bool, SvgReadError> {
+        self.skip_separators();
+        let flag = match self.peek() {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => return Err(self.error("Expected a flag")),
+        };
+        self.position += 1;
+        Ok(flag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_path;
+
+    #[test]
+    fn commands_are_relative_repeated_and_closed() {
+        let subpaths = parse_path("m10 10 10 0 v10 h-10z M0,0L.5.5", 1.0).unwrap();
+        assert_eq!(subpaths.len(), 2);
+        assert!(subpaths[0].closed);
+
+        let points = subpaths[0]
+            .points
+            .iter()
+            .map(|point| (point.x(), point.y()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            points,
+            [(10.0, 10.0), (20.0, 10.0), (20.0, 20.0), (10.0, 20.0)]
+        );
+
+        let end = subpaths[1].points[1];
+        assert_eq!((end.x(), end.y()), (0.5, 0.5));
+    }
+
+    #[test]
+    fn arcs_end_on_their_endpoint() {
+        let subpaths = parse_path("M0 0 A10 10 0 1 1 20 0", 1.0).unwrap();
+        let points = &subpaths[0].points;
+        let last = points[points.len() - 1];
+        assert_eq!((last.x(), last.y()), (20.0, 0.0));
+        // Half a circle of radius 10, which sweeps clockwise on screen so passes above its center
+        assert!(points.iter().any(|point| (point.y() + 10.0).abs() < 0.5));
+        assert!(points.len() > 30);
+    }
+}
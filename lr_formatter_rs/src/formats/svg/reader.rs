@@ -0,0 +1,560 @@
+use crate::{
+    formats::svg::{
+        SvgReadError, SvgReadOptions,
+        path::{Subpath, parse_numbers, parse_path},
+        xml::{self, Element},
+    },
+    geometry::{Shape, ShapeError, ShapeOptions, add_points},
+    track::{GridVersion, LineType, RGBColor, Track, TrackBuilder, Vec2},
+};
+
+/// Elements holding definitions or metadata rather than drawn shapes
+const SKIPPED_ELEMENTS: [&str; 12] = [
+    "defs",
+    "clipPath",
+    "mask",
+    "symbol",
+    "marker",
+    "pattern",
+    "title",
+    "desc",
+    "metadata",
+    "style",
+    "script",
+    "namedview",
+];
+
+const SHAPE_ELEMENTS: [&str; 6] = ["path", "line", "polyline", "polygon", "rect", "circle"];
+
+const BASE_LAYER_NAME: &str = "Base Layer";
+
+pub fn read(data: Vec<u8>) -> Result<Track, SvgReadError> {
+    read_with_options(data, &SvgReadOptions::default())
+}
+
+/// Import a drawing as a track
+///
+/// Top level groups become layers, and shapes outside of them are drawn on a base layer. Groups
+/// within layers are drawn on the layer they are in. When groups are marked as layers, as
+/// Inkscape does, only marked groups become layers, and layers holding sublayers become folders.
+/// Drawings without groups have no layers.
+pub fn read_with_options(data: Vec<u8>, options: &SvgReadOptions) -> Result<Track, SvgReadError> {
+    if !options.segment_length.is_finite() || options.segment_length <= 0.0 {
+        return Err(ShapeError::SegmentLength(options.segment_length).into());
+    }
+
+    let text = String::from_utf8(data)?;
+    let root = xml::parse(&text)?;
+    if root.name != "svg" {
+        return Err(SvgReadError::InvalidData {
+            name: "root element".to_string(),
+            value: root.name,
+        });
+    }
+
+    let track_builder = &mut TrackBuilder::default();
+    track_builder.metadata().grid_version(GridVersion::V6_2);
+
+    let mut importer = Importer {
+        options,
+        track_builder,
+        next_layer_id: 0,
+        marked_layers: root.children.iter().any(is_marked_layer),
+    };
+    let style = Style::default().child(&root, options)?;
+    importer.read_root(&root, &style)?;
+
+    Ok(track_builder.build()?)
+}
+
+struct Importer<'a> {
+    options: &'a SvgReadOptions,
+    track_builder: &'a mut TrackBuilder,
+    // Layers and folders share ids, which are also their indices
+    next_layer_id: u32,
+    // Whether groups are marked as layers, as Inkscape does, rather than every group being one
+    marked_layers: bool,
+}
+
+impl Importer<'_> {
+    fn read_root(&mut self, root: &Element, style: &Style) -> Result<(), SvgReadError> {
+        let has_layers = root.children.iter().any(|child| self.is_layer(child));
+        let mut base_layer = None;
+
+        for child in &root.children {
+            if SKIPPED_ELEMENTS.contains(&child.name.as_str()) {
+                continue;
+            }
+
+            let mut child_style = style.child(child, self.options)?;
+            if !has_layers {
+                self.read_shapes(child, &child_style, None)?;
+            } else if !self.is_layer(child) {
+                if base_layer.is_none() && has_shapes(child) {
+                    base_layer = Some(self.add_layer(BASE_LAYER_NAME.to_string(), None, false)?);
+                }
+                self.read_shapes(child, &child_style, base_layer)?;
+            } else if self.marked_layers && child.children.iter().any(is_marked_layer) {
+                let hidden = std::mem::take(&mut child_style.hidden);
+                self.read_folder(child, &child_style, hidden)?;
+            } else {
+                let hidden = std::mem::take(&mut child_style.hidden);
+                let layer = self.add_layer(self.group_name(child), None, hidden)?;
+                self.read_shapes(child, &child_style, Some(layer))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_folder(
+        &mut self,
+        folder: &Element,
+        style: &Style,
+        hidden: bool,
+    ) -> Result<(), SvgReadError> {
+        let name = self.group_name(folder);
+        let folder_id = self.next_layer_id;
+        self.next_layer_id += 1;
+        self.track_builder
+            .layer_group()
+            .add_layer_folder(folder_id, folder_id as usize)?
+            .name(name)
+            .visible(!hidden);
+
+        // Shapes outside of the groups of the folder get a layer named after it
+        let mut loose_layer = None;
+
+        for child in &folder.children {
+            if SKIPPED_ELEMENTS.contains(&child.name.as_str()) {
+                continue;
+            }
+
+            let mut child_style = style.child(child, self.options)?;
+            if self.is_layer(child) {
+                let hidden = std::mem::take(&mut child_style.hidden);
+                let layer = self.add_layer(self.group_name(child), Some(folder_id), hidden)?;
+                self.read_shapes(child, &child_style, Some(layer))?;
+            } else {
+                if loose_layer.is_none() && has_shapes(child) {
+                    loose_layer =
+                        Some(self.add_layer(self.group_name(folder), Some(folder_id), false)?);
+                }
+                self.read_shapes(child, &child_style, loose_layer)?;
+            }
+        }
+
+        let size = self.next_layer_id - folder_id - 1;
+        if let Some(folder_builder) = self.track_builder.layer_group().get_layer_folders()?.last() {
+            folder_builder.size(size);
+        }
+
+        Ok(())
+    }
+
+    fn add_layer(
+        &mut self,
+        name: String,
+        folder_id: Option<u32>,
+        hidden: bool,
+    ) -> Result<u32, SvgReadError> {
+        let id = self.next_layer_id;
+        self.next_layer_id += 1;
+        let layer_builder = self
+            .track_builder
+            .layer_group()
+            .add_layer(id, id as usize)?
+            .name(name)
+            .visible(!hidden);
+
+        if let Some(folder_id) = folder_id {
+            layer_builder.folder_id(Some(folder_id));
+        }
+
+        Ok(id)
+    }
+
+    fn is_layer(&self, element: &Element) -> bool {
+        element.name == "g" && (!self.marked_layers || is_marked_layer(element))
+    }
+
+    fn group_name(&self, group: &Element) -> String {
+        group
+            .attribute("inkscape:label")
+            .or(group.attribute("id"))
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("Layer {}", self.next_layer_id + 1))
+    }
+
+    /// Add the lines of an element and everything in it
+    fn read_shapes(
+        &mut self,
+        element: &Element,
+        style: &Style,
+        layer: Option<u32>,
+    ) -> Result<(), SvgReadError> {
+        if style.hidden || SKIPPED_ELEMENTS.contains(&element.name.as_str()) {
+            return Ok(());
+        }
+
+        if !SHAPE_ELEMENTS.contains(&element.name.as_str()) {
+            for child in &element.children {
+                let child_style = style.child(child, self.options)?;
+                self.read_shapes(child, &child_style, layer)?;
+            }
+            return Ok(());
+        }
+
+        let scale = style.transform.scale();
+        if scale == 0.0 {
+            return Ok(());
+        }
+
+        let line_type = style
+            .line_type
+            .or_else(|| {
+                let color = style.stroke.or(style.fill)?;
+                self.options
+                    .colors
+                    .iter()
+                    .find(|(mapped_color, _)| *mapped_color == color)
+                    .map(|(_, line_type)| *line_type)
+            })
+            .unwrap_or(self.options.default_line_type);
+        let shape_options = ShapeOptions {
+            line_type,
+            segment_length: self.options.segment_length,
+            ride_side: self.options.ride_side,
+            layer,
+            // Scenery lines of width 1 are as thick as a stroke 2 units wide
+            scenery_width: Some(style.stroke_width * scale / 2.0),
+            ..Default::default()
+        };
+
+        for subpath in subpaths(element, self.options.segment_length / scale)? {
+            let mut points = subpath
+                .points
+                .iter()
+                .map(|point| style.transform.apply(*point))
+                .collect::<Vec<_>>();
+            if let (true, Some(first), Some(last)) = (subpath.closed, points.first(), points.last())
+                && (first.x(), first.y()) != (last.x(), last.y())
+            {
+                points.push(*first);
+            }
+
+            add_points(
+                self.track_builder.line_group(),
+                &points,
+                subpath.closed,
+                &shape_options,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+fn is_marked_layer(element: &Element) -> bool {
+    element.name == "g" && element.attribute("inkscape:groupmode") == Some("layer")
+}
+
+/// Whether an element is or contains a shape that is drawn
+fn has_shapes(element: &Element) -> bool {
+    if SKIPPED_ELEMENTS.contains(&element.name.as_str()) {
+        return false;
+    }
+
+    SHAPE_ELEMENTS.contains(&element.name.as_str()) || element.children.iter().any(has_shapes)
+}
+
+/// Points of a shape element before it is transformed
+fn subpaths(element: &Element, segment_length: f64) -> Result<Vec<Subpath>, SvgReadError> {
+    let polyline = |points: Vec<Vec2>, closed: bool| vec![Subpath { points, closed }];
+
+    Ok(match element.name.as_str() {
+        "path" => parse_path(element.attribute("d").unwrap_or_default(), segment_length)?,
+        "line" => polyline(
+            vec![
+                Vec2::new(length(element, "x1")?, length(element, "y1")?),
+                Vec2::new(length(element, "x2")?, length(element, "y2")?),
+            ],
+            false,
+        ),
+        "polyline" | "polygon" => {
+            let numbers = parse_numbers(element.attribute("points").unwrap_or_default())?;
+            // An odd number out is ignored, as browsers do
+            let points = numbers
+                .chunks_exact(2)
+                .map(|pair| Vec2::new(pair[0], pair[1]))
+                .collect();
+            polyline(points, element.name == "polygon")
+        }
+        "rect" => {
+            let (x, y) = (length(element, "x")?, length(element, "y")?);
+            let (width, height) = (length(element, "width")?, length(element, "height")?);
+            if width <= 0.0 || height <= 0.0 {
+                return Ok(vec![]);
+            }
+            polyline(
+                vec![
+                    Vec2::new(x, y),
+                    Vec2::new(x + width, y),
+                    Vec2::new(x + width, y + height),
+                    Vec2::new(x, y + height),
+                ],
+                true,
+            )
+        }
+        "circle" => {
+            let radius = length(element, "r")?;
+            if radius <= 0.0 {
+                return Ok(vec![]);
+            }
+            let center = Vec2::new(length(element, "cx")?, length(element, "cy")?);
+            let circle = Shape::Circle { center, radius };
+            polyline(circle.tessellate(segment_length)?, true)
+        }
+        _ => vec![],
+    })
+}
+
+/// A coordinate or size attribute in user units, zero if missing
+fn length(element: &Element, name: &str) -> Result<f64, SvgReadError> {
+    match element.attribute(name) {
+        None => Ok(0.0),
+        Some(value) => parse_length(value).ok_or_else(|| SvgReadError::InvalidData {
+            name: name.to_string(),
+            value: value.to_string(),
+        }),
+    }
+}
+
+fn parse_length(value: &str) -> Option<f64> {
+    let value = value.trim();
+    value
+        .strip_suffix("px")
+        .unwrap_or(value)
+        .trim_end()
+        .parse::<f64>()
+        .ok()
+        .filter(|length| length.is_finite())
+}
+
+/// Presentation properties that groups pass on to what they contain
+#[derive(Debug, Clone)]
+struct Style {
+    stroke: Option<RGBColor>,
+    fill: Option<RGBColor>,
+    stroke_width: f64,
+    transform: Transform,
+    line_type: Option<LineType>,
+    hidden: bool,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            stroke: None,
+            fill: Some(RGBColor::new(0, 0, 0)),
+            stroke_width: 1.0,
+            transform: Transform::IDENTITY,
+            line_type: None,
+            hidden: false,
+        }
+    }
+}
+
+impl Style {
+    /// The style of an element within an element of this style
+    fn child(&self, element: &Element, options: &SvgReadOptions) -> Result<Style, SvgReadError> {
+        let mut style = self.clone();
+
+        if let Some(stroke) = property(element, "stroke") {
+            style.stroke = parse_color(stroke);
+        }
+        if let Some(fill) = property(element, "fill") {
+            style.fill = parse_color(fill);
+        }
+        // Units other than pixels and percentages are not supported, and keep the inherited width
+        if let Some(stroke_width) = property(element, "stroke-width").and_then(parse_length) {
+            style.stroke_width = stroke_width.max(0.0);
+        }
+        if property(element, "display") == Some("none")
+            || matches!(property(element, "visibility"), Some("hidden" | "collapse"))
+        {
+            style.hidden = true;
+        }
+        if let Some(transform) = element.attribute("transform") {
+            style.transform = style.transform.then(Transform::parse(transform)?);
+        }
+
+        let line_type = ["id", "inkscape:label"]
+            .iter()
+            .filter_map(|name| element.attribute(name))
+            .find_map(|name| options.group_line_types.get(name));
+        if let Some(line_type) = line_type {
+            style.line_type = Some(*line_type);
+        }
+
+        Ok(style)
+    }
+}
+
+/// A property from the style attribute of an element, or else its presentation attribute
+fn property<'a>(element: &'a Element, name: &str) -> Option<&'a str> {
+    let from_style = element.attribute("style").and_then(|style| {
+        style
+            .split(';')
+            .filter_map(|declaration| declaration.split_once(':'))
+            .filter(|(property, _)| property.trim() == name)
+            .map(|(_, value)| value.trim())
+            .next_back()
+    });
+
+    from_style
+        .or(element.attribute(name).map(str::trim))
+        .filter(|value| *value != "inherit")
+}
+
+/// Parse a color, returning None for `none` and for paints that are not plain colors
+fn parse_color(value: &str) -> Option<RGBColor> {
+    let value = value.trim().to_ascii_lowercase();
+
+    if let Some(hex) = value.strip_prefix('#') {
+        if !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return None;
+        }
+        let digit = |index: usize, length: usize| {
+            u8::from_str_radix(&hex[index * length..(index + 1) * length], 16).ok()
+        };
+        return match hex.len() {
+            // Each digit is repeated, so #abc is #aabbcc
+            3 => Some(RGBColor::new(
+                digit(0, 1)? * 17,
+                digit(1, 1)? * 17,
+                digit(2, 1)? * 17,
+            )),
+            6 => Some(RGBColor::new(digit(0, 2)?, digit(1, 2)?, digit(2, 2)?)),
+            _ => None,
+        };
+    }
+
+    if let Some(arguments) = value
+        .strip_prefix("rgb(")
+        .and_then(|value| value.strip_suffix(')'))
+    {
+        let channels = arguments
+            .split(|char: char| char == ',' || char.is_whitespace())
+            .filter(|channel| !channel.is_empty())
+            .map(|channel| match channel.strip_suffix('%') {
+                Some(percentage) => percentage.parse::<f64>().ok().map(|p| p * 2.55),
+                None => channel.parse::<f64>().ok(),
+            })
+            .map(|channel| channel.map(|channel| channel.round().clamp(0.0, 255.0) as u8))
+            .collect::<Option<Vec<_>>>()?;
+        return match channels[..] {
+            [red, green, blue] => Some(RGBColor::new(red, green, blue)),
+            _ => None,
+        };
+    }
+
+    let (red, green, blue) = match value.as_str() {
+        "black" => (0, 0, 0),
+        "white" => (255, 255, 255),
+        "gray" | "grey" => (128, 128, 128),
+        "red" => (255, 0, 0),
+        "lime" => (0, 255, 0),
+        "green" => (0, 128, 0),
+        "blue" => (0, 0, 255),
+        "yellow" => (255, 255, 0),
+        "cyan" | "aqua" => (0, 255, 255),
+        "magenta" | "fuchsia" => (255, 0, 255),
+        _ => return None,
+    };
+    Some(RGBColor::new(red, green, blue))
+}
+
+/// An affine transform `[a, b, c, d, e, f]`, mapping x and y to `a x + c y + e` and
+/// `b x + d y + f`
+#[derive(Debug, Clone, Copy)]
+struct Transform([f64; 6]);
+
+impl Transform {
+    const IDENTITY: Transform = Transform([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+
+    /// Parse a transform list, such as `translate(10 20) rotate(45)`
+    fn parse(value: &str) -> Result<Transform, SvgReadError> {
+        let invalid = || SvgReadError::InvalidData {
+            name: "transform".to_string(),
+            value: value.to_string(),
+        };
+        let mut transform = Transform::IDENTITY;
+        let mut rest = value;
+
+        loop {
+            rest = rest.trim_start_matches(|char: char| char.is_whitespace() || char == ',');
+            if rest.is_empty() {
+                return Ok(transform);
+            }
+
+            let (name, arguments) = rest.split_once('(').ok_or_else(invalid)?;
+            let (arguments, after) = arguments.split_once(')').ok_or_else(invalid)?;
+            rest = after;
+
+            let numbers = parse_numbers(arguments).map_err(|_| invalid())?;
+            let next = match (name.trim(), &numbers[..]) {
+                ("matrix", &[a, b, c, d, e, f]) => Transform([a, b, c, d, e, f]),
+                ("translate", &[x]) => Transform([1.0, 0.0, 0.0, 1.0, x, 0.0]),
+                ("translate", &[x, y]) => Transform([1.0, 0.0, 0.0, 1.0, x, y]),
+                ("scale", &[x]) => Transform([x, 0.0, 0.0, x, 0.0, 0.0]),
+                ("scale", &[x, y]) => Transform([x, 0.0, 0.0, y, 0.0, 0.0]),
+                ("rotate", &[angle]) => Transform::rotation(angle),
+                ("rotate", &[angle, x, y]) => Transform([1.0, 0.0, 0.0, 1.0, x, y])
+                    .then(Transform::rotation(angle))
+                    .then(Transform([1.0, 0.0, 0.0, 1.0, -x, -y])),
+                ("skewX", &[angle]) => {
+                    Transform([1.0, 0.0, angle.to_radians().tan(), 1.0, 0.0, 0.0])
+                }
+                ("skewY", &[angle]) => {
+                    Transform([1.0, angle.to_radians().tan(), 0.0, 1.0, 0.0, 0.0])
+                }
+                _ => return Err(invalid()),
+            };
+            transform = transform.then(next);
+        }
+    }
+
+    fn rotation(degrees: f64) -> Transform {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Transform([cos, sin, -sin, cos, 0.0, 0.0])
+    }
+
+    /// This transform applied after `inner`, as when `inner` is written on a child element
+    fn then(self, inner: Transform) -> Transform {
+        let [a, b, c, d, e, f] = self.0;
+        let [na, nb, nc, nd, ne, nf] = inner.0;
+        Transform([
+            a * na + c * nb,
+            b * na + d * nb,
+            a * nc + c * nd,
+            b * nc + d * nd,
+            a * ne + c * nf + e,
+            b * ne + d * nf + f,
+        ])
+    }
+
+    fn apply(&self, point: Vec2) -> Vec2 {
+        let [a, b, c, d, e, f] = self.0;
+        Vec2::new(
+            a * point.x() + c * point.y() + e,
+            b * point.x() + d * point.y() + f,
+        )
+    }
+
+    /// How much the transform scales lengths, on average over every direction
+    fn scale(&self) -> f64 {
+        let [a, b, c, d, ..] = self.0;
+        (a * d - b * c).abs().sqrt()
+    }
+}
@@ -0,0 +1,255 @@
+//! Just enough of an XML parser to read the elements and attributes of SVG documents
+
+use crate::formats::svg::SvgReadError;
+
+/// Deepest element nesting accepted, to keep recursive walks of the tree off the end of the stack
+const MAX_DEPTH: usize = 256;
+
+#[derive(Debug, Clone)]
+pub(super) struct Element {
+    /// Name without any namespace prefix
+    pub name: String,
+    /// Attributes by their full name, including any namespace prefix
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Element>,
+}
+
+impl Element {
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(attribute, _)| attribute == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Parse a document into its root element, skipping text, comments and declarations
+pub(super) fn parse(text: &str) -> Result<Element, SvgReadError> {
+    let mut parser = Parser { text, position: 0 };
+    let mut stack: Vec<Element> = vec![];
+    let mut root = None;
+
+    while parser.position < text.len() {
+        let rest = parser.rest();
+
+        if rest.starts_with("<!--") {
+            parser.skip_past("-->")?;
+        } else if rest.starts_with("<![CDATA[") {
+            parser.skip_past("]]>")?;
+        } else if rest.starts_with("<?") {
+            parser.skip_past("?>")?;
+        } else if rest.starts_with("<!") {
+            parser.skip_declaration()?;
+        } else if rest.starts_with("</") {
+            parser.position += 2;
+            let name = parser.name()?;
+            parser.skip_whitespace();
+            parser.expect(">")?;
+
+            let element = stack
+                .pop()
+                .filter(|element| element.name == local_name(&name))
+                .ok_or_else(|| parser.error(format!("Unexpected closing tag {}", name)))?;
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(element),
+                None => root = Some(element),
+            }
+        } else if rest.starts_with('<') {
+            parser.position += 1;
+            let name = parser.name()?;
+            let mut element = Element {
+                name: local_name(&name).to_string(),
+                attributes: vec![],
+                children: vec![],
+            };
+
+            let self_closing = loop {
+                parser.skip_whitespace();
+                if parser.rest().starts_with("/>") {
+                    parser.position += 2;
+                    break true;
+                }
+                if parser.rest().starts_with('>') {
+                    parser.position += 1;
+                    break false;
+                }
+
+                let attribute = parser.name()?;
+                parser.skip_whitespace();
+                parser.expect("=")?;
+                parser.skip_whitespace();
+                let value = parser.quoted()?;
+                element.attributes.push((attribute, value));
+            };
+
+            if root.is_some() {
+                return Err(parser.error("Content after the root element".to_string()));
+            }
+
+            if self_closing {
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => root = Some(element),
+                }
+            } else {
+                if stack.len() >= MAX_DEPTH {
+                    return Err(parser.error(format!("Elements nested over {} deep", MAX_DEPTH)));
+                }
+                stack.push(element);
+            }
+        } else {
+            // Text content is not needed
+            let next_tag = rest.find('<').unwrap_or(rest.len());
+            parser.position += next_tag;
+        }
+    }
+
+    if let Some(element) = stack.last() {
+        return Err(parser.error(format!("Missing closing tag for {}", element.name)));
+    }
+
+    root.ok_or_else(|| parser.error("Missing root element".to_string()))
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn rest(&self) -> &str {
+        &self.text[self.position..]
+    }
+
+    fn error(&self, message: String) -> SvgReadError {
+        SvgReadError::Xml {
+            position: self.position,
+            message,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn skip_past(&mut self, end: &str) -> Result<(), SvgReadError> {
+        match self.rest().find(end) {
+            Some(index) => {
+                self.position += index + end.len();
+                Ok(())
+            }
+            None => Err(self.error(format!("Missing {}", end))),
+        }
+    }
+
+    // Doctypes may hold an internal subset in brackets, which can contain '>'
+    fn skip_declaration(&mut self) -> Result<(), SvgReadError> {
+        let mut in_brackets = false;
+        for (index, char) in self.rest().char_indices() {
+            match char {
+                '[' => in_brackets = true,
+                ']' => in_brackets = false,
+                '>' if !in_brackets => {
+                    self.position += index + 1;
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        Err(self.error("Missing > after declaration".to_string()))
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), SvgReadError> {
+        if !self.rest().starts_with(expected) {
+            return Err(self.error(format!("Expected {}", expected)));
+        }
+
+        self.position += expected.len();
+        Ok(())
+    }
+
+    fn name(&mut self) -> Result<String, SvgReadError> {
+        let length = self
+            .rest()
+            .find(|char: char| {
+                char.is_whitespace() || matches!(char, '=' | '>' | '/' | '<' | '"' | '\'')
+            })
+            .unwrap_or(self.rest().len());
+
+        if length == 0 {
+            return Err(self.error("Expected a name".to_string()));
+        }
+
+        let name = self.rest()[..length].to_string();
+        self.position += length;
+        Ok(name)
+    }
+
+    fn quoted(&mut self) -> Result<String, SvgReadError> {
+        let quote = match self.rest().chars().next() {
+            Some(quote @ ('"' | '\'')) => quote,
+            _ => return Err(self.error("Expected a quoted value".to_string())),
+        };
+        self.position += 1;
+
+        let length = self
+            .rest()
+            .find(quote)
+            .ok_or_else(|| self.error("Missing closing quote".to_string()))?;
+        let value = decode_entities(&self.rest()[..length]);
+        self.position += length + 1;
+        Ok(value)
+    }
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let entity = rest
+            .find(';')
+            .map(|end| (&rest[1..end], end))
+            .and_then(|(name, end)| Some((decode_entity(name)?, end)));
+        match entity {
+            Some((char, end)) => {
+                decoded.push(char);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    decoded.push_str(rest);
+    decoded
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        _ => {
+            let code = if let Some(hex) = name.strip_prefix("#x") {
+                u32::from_str_radix(hex, 16).ok()?
+            } else {
+                name.strip_prefix('#')?.parse().ok()?
+            };
+            char::from_u32(code)
+        }
+    }
+}
@@ -2,9 +2,12 @@
 
 mod error;
 mod reader;
+mod writer;
 
-pub use error::TrkReadError;
-pub use reader::read;
+pub use error::{TrkReadError, TrkWriteError};
+pub(crate) use reader::read_with_recovery;
+pub use reader::{read, read_lenient, read_with_limits};
+pub use writer::write;
 
 // These string literals are implementation-specific, do not modify
 const FEATURE_RED_MULTIPLIER: &str = "REDMULTIPLIER";
@@ -26,3 +29,210 @@ const FEATURE_LINE_COLOR_R: &str = "LINECOLORR";
 const FEATURE_LINE_COLOR_G: &str = "LINECOLORG";
 const FEATURE_LINE_COLOR_B: &str = "LINECOLORB";
 const FEATURE_TRIGGERS: &str = "TRIGGERS";
+
+const KNOWN_FEATURES: [&str; 8] = [
+    FEATURE_RED_MULTIPLIER,
+    FEATURE_SCENERY_WIDTH,
+    FEATURE_SONG_INFO,
+    FEATURE_IGNORABLE_TRIGGER,
+    FEATURE_6_1,
+    FEATURE_ZERO_START,
+    FEATURE_REMOUNT,
+    FEATURE_FRICTIONLESS,
+];
+
+const KNOWN_METADATA_KEYS: [&str; 11] = [
+    FEATURE_START_ZOOM,
+    FEATURE_X_GRAVITY,
+    FEATURE_Y_GRAVITY,
+    FEATURE_GRAVITY_WELL_SIZE,
+    FEATURE_BACKGROUND_COLOR_R,
+    FEATURE_BACKGROUND_COLOR_G,
+    FEATURE_BACKGROUND_COLOR_B,
+    FEATURE_LINE_COLOR_R,
+    FEATURE_LINE_COLOR_G,
+    FEATURE_LINE_COLOR_B,
+    FEATURE_TRIGGERS,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::{TrkReadError, read, read_lenient, read_with_limits, write};
+    use crate::formats::ReadLimits;
+    use crate::track::{
+        CameraZoomEvent, GridVersion, LineHitTrigger, TrackBuilder, Vec2,
+        line::line_adjacency::LineLink,
+    };
+
+    #[test]
+    fn round_trip_keeps_lines_and_adjacency() {
+        let mut track_builder = TrackBuilder::default();
+        track_builder.metadata().grid_version(GridVersion::V6_1);
+        track_builder.line_group().add_standard_line(
+            1,
+            (Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0)),
+            false,
+            true,
+            false,
+        );
+        track_builder.line_group().add_standard_line(
+            2,
+            (Vec2::new(10.0, 0.0), Vec2::new(20.0, 5.0)),
+            true,
+            false,
+            true,
+        );
+        track_builder
+            .line_group()
+            .add_acceleration_line(
+                3,
+                (Vec2::new(20.0, 5.0), Vec2::new(30.0, 5.0)),
+                false,
+                false,
+                false,
+            )
+            .multiplier(2.0);
+        track_builder
+            .line_group()
+            .add_scenery_line(4, (Vec2::new(0.0, 10.0), Vec2::new(5.0, 10.0)));
+        track_builder.line_group().recompute_adjacency(0.0);
+        track_builder
+            .legacy_camera_zoom_group()
+            .add_trigger()
+            .trigger(LineHitTrigger::new(2, 40))
+            .event(CameraZoomEvent::new(1.0));
+
+        let bytes = write(&track_builder.build().unwrap()).unwrap();
+        let track = read(bytes).unwrap();
+        let line_group = track.line_group();
+
+        assert_eq!(line_group.standard_lines().len(), 2);
+        assert_eq!(line_group.acceleration_lines()[0].multiplier(), Some(2.0));
+        assert_eq!(line_group.scenery_lines()[0].id(), 4);
+        assert_eq!(line_group.adjacency().get(1), LineLink::new(None, Some(2)));
+        assert_eq!(
+            line_group.adjacency().get(2),
+            LineLink::new(Some(1), Some(3))
+        );
+        // Unextended lines do not store their connections
+        assert_eq!(line_group.adjacency().get(3), LineLink::default());
+        assert_eq!(
+            track
+                .legacy_camera_zoom_group()
+                .as_ref()
+                .unwrap()
+                .triggers()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn round_trip_keeps_unknown_features_and_metadata() {
+        let mut track_builder = TrackBuilder::default();
+        track_builder.metadata().grid_version(GridVersion::V6_2);
+        track_builder
+            .extras()
+            .trk_feature("NEWFEATURE")
+            .trk_metadata_entry("NEWKEY", "1.5");
+
+        let bytes = write(&track_builder.build().unwrap()).unwrap();
+        let track = read(bytes).unwrap();
+        let bytes = write(&track).unwrap();
+
+        assert_eq!(track.extras().trk_features(), &["NEWFEATURE"]);
+        assert_eq!(
+            track
+                .extras()
+                .trk_metadata()
+                .get("NEWKEY")
+                .map(String::as_str),
+            Some("1.5")
+        );
+        assert_eq!(write(&read(bytes.clone()).unwrap()).unwrap(), bytes);
+    }
+
+    #[test]
+    fn read_errors_point_at_broken_line() {
+        let mut track_builder = TrackBuilder::default();
+        track_builder.metadata().grid_version(GridVersion::V6_2);
+        for id in 1..=2 {
+            track_builder.line_group().add_standard_line(
+                id,
+                (Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0)),
+                false,
+                false,
+                false,
+            );
+        }
+        let mut bytes = write(&track_builder.build().unwrap()).unwrap();
+
+        // Corrupt the type of the second line, which starts after the header and first line
+        let header_length = 4 + 1 + 2 + 8 + 8 + 4;
+        let line_length = 1 + 4 + 32;
+        bytes[header_length + line_length] = 0x1F;
+
+        let Err(err) = read(bytes) else {
+            panic!("Expected corrupted line to fail to read");
+        };
+        let context = err.context().unwrap();
+        assert_eq!(context.path(), Some("lines[1]"));
+        assert_eq!(
+            context.offset(),
+            Some((header_length + line_length + 1) as u64)
+        );
+    }
+
+    #[test]
+    fn lenient_read_skips_broken_lines() {
+        let mut track_builder = TrackBuilder::default();
+        track_builder.metadata().grid_version(GridVersion::V6_2);
+        for id in 1..=2 {
+            track_builder.line_group().add_standard_line(
+                id,
+                (Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0)),
+                false,
+                false,
+                false,
+            );
+        }
+        let mut bytes = write(&track_builder.build().unwrap()).unwrap();
+
+        let header_length = 4 + 1 + 2 + 8 + 8 + 4;
+        let line_length = 1 + 4 + 32;
+        bytes[header_length + line_length] = 0x1F;
+
+        let (track, warnings) = read_lenient(bytes.clone()).unwrap();
+        assert_eq!(track.line_group().standard_lines().len(), 1);
+        assert_eq!(warnings[0].context().path(), Some("lines[1]"));
+
+        // A truncated META section keeps the lines read before it
+        bytes.truncate(bytes.len() - 3);
+        let (track, warnings) = read_lenient(bytes).unwrap();
+        assert_eq!(track.line_group().standard_lines().len(), 1);
+        assert!(!warnings.is_empty());
+    }
+
+    #[test]
+    fn line_count_over_the_read_limit_fails_before_reading_lines() {
+        let mut track_builder = TrackBuilder::default();
+        track_builder.metadata().grid_version(GridVersion::V6_2);
+        let mut bytes = write(&track_builder.build().unwrap()).unwrap();
+
+        // Claim billions of lines in an otherwise empty track
+        let line_count_offset = 4 + 1 + 2 + 8 + 8;
+        bytes[line_count_offset..line_count_offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        let Err(err) = read(bytes.clone()) else {
+            panic!("Expected the line count to go over the read limit");
+        };
+        assert!(matches!(err, TrkReadError::WithContext { source, .. }
+            if matches!(*source, TrkReadError::LimitExceeded(_))));
+
+        let limits = ReadLimits {
+            max_lines: 0,
+            ..ReadLimits::default()
+        };
+        assert!(read_with_limits(bytes, &limits).is_err());
+    }
+}
@@ -6,6 +6,7 @@ use std::{
 use thiserror::Error;
 
 use crate::{
+    formats::{ErrorContext, LimitExceeded},
     track::{
         TrackBuilderError, layer::layer_group::LayerGroupBuilderError,
         line::line_group::LineGroupBuilderError, rider::rider_group::RiderGroupBuilderError,
@@ -24,7 +25,7 @@ pub enum TrkReadError {
     #[error("{0}")]
     FloatConversion(#[from] ParseFloatError),
     #[error("{0}")]
-    StringParsing(#[from] ParseLengthPrefixedStringError),
+    StringParsing(ParseLengthPrefixedStringError),
     #[error("{0}")]
     TrackGroup(#[from] TrackBuilderError),
     #[error("{0}")]
@@ -35,4 +36,54 @@ pub enum TrkReadError {
     LayerGroup(#[from] LayerGroupBuilderError),
     #[error("Invalid value for `{name}`: {value}")]
     InvalidData { name: String, value: String },
+    #[error("{0}")]
+    LimitExceeded(#[from] LimitExceeded),
+    #[error("{source} ({context})")]
+    WithContext {
+        context: ErrorContext,
+        source: Box<TrkReadError>,
+    },
+}
+
+// Strings over the read limits are reported as read limit errors, not string errors
+impl From<ParseLengthPrefixedStringError> for TrkReadError {
+    fn from(err: ParseLengthPrefixedStringError) -> Self {
+        match err {
+            ParseLengthPrefixedStringError::LimitExceeded(limit) => {
+                TrkReadError::LimitExceeded(limit)
+            }
+            other => TrkReadError::StringParsing(other),
+        }
+    }
+}
+
+impl TrkReadError {
+    /// Where in the input the error happened, if known
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            TrkReadError::WithContext { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    /// Attach a location to the error, keeping any more precise location it already has
+    pub(crate) fn with_context(self, context: ErrorContext) -> Self {
+        match self {
+            TrkReadError::WithContext { .. } => self,
+            _ => TrkReadError::WithContext {
+                context,
+                source: Box::new(self),
+            },
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum TrkWriteError {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error("{0}")]
+    IntConversion(#[from] TryFromIntError),
+    #[error("Invalid value for `{name}`: {value}")]
+    InvalidData { name: String, value: String },
 }
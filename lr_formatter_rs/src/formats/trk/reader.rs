@@ -6,17 +6,21 @@ use std::{
 use byteorder::{LittleEndian, ReadBytesExt};
 
 use crate::{
-    formats::trk::{
-        FEATURE_6_1, FEATURE_BACKGROUND_COLOR_B, FEATURE_BACKGROUND_COLOR_G,
-        FEATURE_BACKGROUND_COLOR_R, FEATURE_FRICTIONLESS, FEATURE_GRAVITY_WELL_SIZE,
-        FEATURE_IGNORABLE_TRIGGER, FEATURE_LINE_COLOR_B, FEATURE_LINE_COLOR_G,
-        FEATURE_LINE_COLOR_R, FEATURE_RED_MULTIPLIER, FEATURE_REMOUNT, FEATURE_SCENERY_WIDTH,
-        FEATURE_SONG_INFO, FEATURE_START_ZOOM, FEATURE_TRIGGERS, FEATURE_X_GRAVITY,
-        FEATURE_Y_GRAVITY, FEATURE_ZERO_START, TrkReadError,
+    formats::{
+        ErrorContext, ReadLimits, ReadWarning, Recovery,
+        trk::{
+            FEATURE_6_1, FEATURE_BACKGROUND_COLOR_B, FEATURE_BACKGROUND_COLOR_G,
+            FEATURE_BACKGROUND_COLOR_R, FEATURE_FRICTIONLESS, FEATURE_GRAVITY_WELL_SIZE,
+            FEATURE_IGNORABLE_TRIGGER, FEATURE_LINE_COLOR_B, FEATURE_LINE_COLOR_G,
+            FEATURE_LINE_COLOR_R, FEATURE_RED_MULTIPLIER, FEATURE_REMOUNT, FEATURE_SCENERY_WIDTH,
+            FEATURE_SONG_INFO, FEATURE_START_ZOOM, FEATURE_TRIGGERS, FEATURE_X_GRAVITY,
+            FEATURE_Y_GRAVITY, FEATURE_ZERO_START, KNOWN_FEATURES, TrkReadError,
+        },
     },
     track::{
         BackgroundColorEvent, CameraZoomEvent, FrameBoundsTrigger, GridVersion, LineColorEvent,
         LineHitTrigger, LineType, RGBColor, Track, TrackBuilder, Vec2,
+        line::line_adjacency::LineLink,
     },
     util::{
         StringLength, bytes_to_hex_string, parse_string,
@@ -24,10 +28,70 @@ use crate::{
     },
 };
 
+// Start properties of the metadata section, which LRA fills with defaults when missing
+struct StartProperties {
+    zoom: f64,
+    gravity_x: f64,
+    gravity_y: f64,
+    gravity_well_size: f64,
+    line_color: [u8; 3],
+    background_color: [u8; 3],
+}
+
+impl Default for StartProperties {
+    fn default() -> Self {
+        Self {
+            zoom: from_lra_zoom(4.0),
+            gravity_x: 0.0,
+            gravity_y: 1.0,
+            gravity_well_size: 10.0,
+            line_color: [0, 0, 0],
+            background_color: [244, 245, 249],
+        }
+    }
+}
+
 pub fn read(data: Vec<u8>) -> Result<Track, TrkReadError> {
+    read_with_limits(data, &ReadLimits::default())
+}
+
+pub fn read_with_limits(data: Vec<u8>, limits: &ReadLimits) -> Result<Track, TrkReadError> {
+    read_with_recovery(data, &mut Recovery::strict(limits))
+}
+
+/// Read a track, skipping broken line records and metadata entries instead of failing
+///
+/// Lines are not length prefixed, so the lines after a broken one cannot be read. The metadata
+/// section is still searched for past them.
+pub fn read_lenient(data: Vec<u8>) -> Result<(Track, Vec<ReadWarning>), TrkReadError> {
+    let mut recovery = Recovery::lenient(&ReadLimits::default());
+    let track = read_with_recovery(data, &mut recovery)?;
+    Ok((track, recovery.into_warnings()))
+}
+
+pub(crate) fn read_with_recovery(
+    data: Vec<u8>,
+    recovery: &mut Recovery,
+) -> Result<Track, TrkReadError> {
     let track_builder = &mut TrackBuilder::default();
     let mut cursor = Cursor::new(data);
+    let mut record = String::from("header");
 
+    read_records(&mut cursor, track_builder, &mut record, recovery).map_err(|err| {
+        err.with_context(ErrorContext::new(Some(cursor.position()), Some(record)))
+    })?;
+
+    Ok(track_builder.build()?)
+}
+
+// Reads the file into the track builder, keeping `record` updated with the name of the record
+// being read so that errors can point at it
+fn read_records(
+    cursor: &mut Cursor<Vec<u8>>,
+    track_builder: &mut TrackBuilder,
+    record: &mut String,
+    recovery: &mut Recovery,
+) -> Result<(), TrkReadError> {
     // Magic number
     let mut magic_number = [0u8; 4];
     cursor.read_exact(&mut magic_number)?;
@@ -49,10 +113,14 @@ pub fn read(data: Vec<u8>) -> Result<Track, TrkReadError> {
         });
     }
 
-    let feature_string = parse_string::<LittleEndian>(&mut cursor, StringLength::U16)?;
+    let feature_string =
+        parse_string::<LittleEndian>(cursor, StringLength::U16, &mut recovery.budget)?;
     let mut included_features: HashSet<&str> = Default::default();
 
     for feature in feature_string.split(';').filter(|s| !s.is_empty()) {
+        if !KNOWN_FEATURES.contains(&feature) {
+            track_builder.extras().trk_feature(feature);
+        }
         included_features.insert(feature);
     }
 
@@ -64,6 +132,7 @@ pub fn read(data: Vec<u8>) -> Result<Track, TrkReadError> {
     track_builder.metadata().grid_version(grid_version);
 
     if included_features.contains(FEATURE_SONG_INFO) {
+        *record = String::from("song info");
         let mut song_string_length = 0;
         let mut bit_shift = 0;
 
@@ -77,122 +146,67 @@ pub fn read(data: Vec<u8>) -> Result<Track, TrkReadError> {
             }
 
             bit_shift += 7;
-        }
 
-        let song_string =
-            parse_string::<LittleEndian>(&mut cursor, StringLength::Fixed(song_string_length))?;
-        let song_data: Vec<&str> = song_string
-            .split("\r\n")
-            .filter(|s| !s.is_empty())
-            .collect();
-
-        if song_data.len() != 2 {
-            return Err(TrkReadError::InvalidData {
-                name: "song data".to_string(),
-                value: song_data.join(","),
-            });
+            // A 7 bit encoded int32 takes at most 5 bytes
+            if bit_shift > 28 {
+                return Err(TrkReadError::InvalidData {
+                    name: "song info length".to_string(),
+                    value: format!("more than {} bytes", bit_shift / 7),
+                });
+            }
         }
 
-        let name = song_data[0];
-        let seconds_offset = song_data[1].parse::<f64>()?;
-        track_builder
-            .metadata()
-            .audio_filename(name)
-            .audio_offset_until_start(-seconds_offset);
+        let song_string = parse_string::<LittleEndian>(
+            cursor,
+            StringLength::Fixed(song_string_length),
+            &mut recovery.budget,
+        )?;
+
+        let song_info =
+            recovery.recover(parse_song_info(&song_string), "Skipped song info", || {
+                ErrorContext::new(Some(cursor.position()), Some(record.clone()))
+            })?;
+
+        if let Some((name, seconds_offset)) = song_info {
+            track_builder
+                .metadata()
+                .audio_filename(name)
+                .audio_offset_until_start(-seconds_offset);
+        }
     }
 
+    *record = String::from("start position");
     let start_pos_x = cursor.read_f64::<LittleEndian>()?;
     let start_pos_y = cursor.read_f64::<LittleEndian>()?;
     track_builder
         .metadata()
         .start_position(Vec2::new(start_pos_x, start_pos_y));
 
+    *record = String::from("line count");
     let line_count = cursor.read_u32::<LittleEndian>()?;
+    recovery.budget.expect_lines(usize::try_from(line_count)?)?;
 
     let mut max_id = 0;
-
-    for _ in 0..line_count {
-        let mut line_id: u32 = 0;
-        let flags = cursor.read_u8()?;
-
-        let line_type = match flags & 0x1F {
-            1 => LineType::Standard,
-            2 => LineType::Acceleration,
-            0 => LineType::Scenery,
-            other => {
-                return Err(TrkReadError::InvalidData {
-                    name: "line type".to_string(),
-                    value: other.to_string(),
-                });
-            }
-        };
-
-        let line_inv = (flags >> 7) != 0;
-        let line_ext = (flags >> 5) & 0x3;
-
-        let mut line_multiplier = 1.0;
-        let mut line_scenery_width = 1.0;
-
-        if line_type == LineType::Acceleration && included_features.contains(FEATURE_RED_MULTIPLIER)
-        {
-            line_multiplier = f64::from(cursor.read_u8()?);
-        }
-
-        if line_type == LineType::Scenery {
-            if included_features.contains(FEATURE_SCENERY_WIDTH) {
-                line_scenery_width = from_lra_scenery_width(cursor.read_u8()?);
-            }
-        } else {
-            line_id = cursor.read_u32::<LittleEndian>()?;
-            max_id = max_id.max(line_id);
-
-            if line_ext != 0 {
-                _ = cursor.read_i32::<LittleEndian>()?; // Prev line id or -1
-                _ = cursor.read_i32::<LittleEndian>()?; // Next line id or -1
-            }
-
-            if included_features.contains(FEATURE_IGNORABLE_TRIGGER) {
-                let has_zoom_trigger = cursor.read_u8()?;
-                if has_zoom_trigger == 1 {
-                    let target_zoom = from_lra_zoom(cursor.read_f32::<LittleEndian>()?);
-                    let length = u32::try_from(cursor.read_i16::<LittleEndian>()?)?;
-                    let zoom_event = CameraZoomEvent::new(target_zoom);
-                    let line_hit = LineHitTrigger::new(line_id, length);
-                    track_builder
-                        .legacy_camera_zoom_group()
-                        .add_trigger()
-                        .trigger(line_hit)
-                        .event(zoom_event);
-                }
-            }
-        }
-
-        let line_x1 = cursor.read_f64::<LittleEndian>()?;
-        let line_y1 = cursor.read_f64::<LittleEndian>()?;
-        let line_x2 = cursor.read_f64::<LittleEndian>()?;
-        let line_y2 = cursor.read_f64::<LittleEndian>()?;
-        let endpoints = (Vec2::new(line_x1, line_y1), Vec2::new(line_x2, line_y2));
-        let left_ext = line_ext & 0x1 != 0;
-        let right_ext = line_ext & 0x2 != 0;
-
-        match line_type {
-            LineType::Standard => {
-                track_builder
-                    .line_group()
-                    .add_standard_line(line_id, endpoints, line_inv, left_ext, right_ext);
-            }
-            LineType::Acceleration => {
-                track_builder
-                    .line_group()
-                    .add_acceleration_line(line_id, endpoints, line_inv, left_ext, right_ext)
-                    .multiplier(line_multiplier);
-            }
-            LineType::Scenery => {
-                track_builder
-                    .line_group()
-                    .add_scenery_line(line_id, endpoints)
-                    .width(line_scenery_width);
+    let mut metadata_lost = false;
+
+    for line_index in 0..line_count {
+        *record = format!("lines[{}]", line_index);
+        let line_start = cursor.position();
+        recovery.budget.add_line()?;
+        let line = read_line(cursor, &included_features, track_builder, &mut max_id);
+        let action = format!("Skipped {} lines", line_count - line_index);
+
+        let recovered = recovery.recover(line, &action, || {
+            ErrorContext::new(Some(line_start), Some(record.clone()))
+        })?;
+
+        if recovered.is_none() {
+            // The end of a broken line is unknown, so continue from the metadata section
+            match find_metadata(cursor, line_start) {
+                Some(position) => cursor.set_position(position),
+                None => metadata_lost = true,
             }
+            break;
         }
     }
 
@@ -218,12 +232,183 @@ pub fn read(data: Vec<u8>) -> Result<Track, TrkReadError> {
     let end = cursor.seek(SeekFrom::End(0))?;
     cursor.seek(SeekFrom::Start(current))?;
 
-    if current == end {
-        return Ok(track_builder.build()?);
+    if current == end || metadata_lost {
+        return Ok(());
     }
 
     // Metadata section
 
+    *record = String::from("metadata");
+    let mut start_properties = StartProperties::default();
+    let metadata = read_metadata(
+        cursor,
+        track_builder,
+        &mut start_properties,
+        record,
+        recovery,
+    );
+
+    recovery.recover(metadata, "Skipped the rest of the metadata", || {
+        ErrorContext::new(Some(cursor.position()), Some(record.clone()))
+    })?;
+
+    let [red, green, blue] = start_properties.background_color;
+    let background_color = RGBColor::new(red, green, blue);
+    let [red, green, blue] = start_properties.line_color;
+    let line_color = RGBColor::new(red, green, blue);
+
+    track_builder
+        .metadata()
+        .start_zoom(start_properties.zoom)
+        .start_gravity(Vec2::new(
+            start_properties.gravity_x,
+            start_properties.gravity_y,
+        ))
+        .gravity_well_size(start_properties.gravity_well_size)
+        .start_background_color(background_color)
+        .start_line_color(line_color);
+
+    Ok(())
+}
+
+fn parse_song_info(song_string: &str) -> Result<(String, f64), TrkReadError> {
+    let song_data: Vec<&str> = song_string
+        .split("\r\n")
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    if song_data.len() != 2 {
+        return Err(TrkReadError::InvalidData {
+            name: "song data".to_string(),
+            value: song_data.join(","),
+        });
+    }
+
+    Ok((song_data[0].to_string(), song_data[1].parse::<f64>()?))
+}
+
+// Reads a line record, adding it to the track builder only once it has been read completely
+fn read_line(
+    cursor: &mut Cursor<Vec<u8>>,
+    included_features: &HashSet<&str>,
+    track_builder: &mut TrackBuilder,
+    max_id: &mut u32,
+) -> Result<(), TrkReadError> {
+    let mut line_id: u32 = 0;
+    let flags = cursor.read_u8()?;
+
+    let line_type = match flags & 0x1F {
+        1 => LineType::Standard,
+        2 => LineType::Acceleration,
+        0 => LineType::Scenery,
+        other => {
+            return Err(TrkReadError::InvalidData {
+                name: "line type".to_string(),
+                value: other.to_string(),
+            });
+        }
+    };
+
+    let line_inv = (flags >> 7) != 0;
+    let line_ext = (flags >> 5) & 0x3;
+
+    let mut line_multiplier = 1.0;
+    let mut line_scenery_width = 1.0;
+    let mut line_link = LineLink::default();
+    let mut zoom_trigger = None;
+
+    if line_type == LineType::Acceleration && included_features.contains(FEATURE_RED_MULTIPLIER) {
+        line_multiplier = f64::from(cursor.read_u8()?);
+    }
+
+    if line_type == LineType::Scenery {
+        if included_features.contains(FEATURE_SCENERY_WIDTH) {
+            line_scenery_width = from_lra_scenery_width(cursor.read_u8()?);
+        }
+    } else {
+        line_id = cursor.read_u32::<LittleEndian>()?;
+
+        if line_ext != 0 {
+            // Prev and next line ids, or -1 when unconnected
+            let previous = u32::try_from(cursor.read_i32::<LittleEndian>()?).ok();
+            let next = u32::try_from(cursor.read_i32::<LittleEndian>()?).ok();
+            line_link = LineLink::new(previous, next);
+        }
+
+        if included_features.contains(FEATURE_IGNORABLE_TRIGGER) {
+            let has_zoom_trigger = cursor.read_u8()?;
+            if has_zoom_trigger == 1 {
+                let target_zoom = from_lra_zoom(cursor.read_f32::<LittleEndian>()?);
+                let length = u32::try_from(cursor.read_i16::<LittleEndian>()?)?;
+                zoom_trigger = Some((target_zoom, length));
+            }
+        }
+    }
+
+    let line_x1 = cursor.read_f64::<LittleEndian>()?;
+    let line_y1 = cursor.read_f64::<LittleEndian>()?;
+    let line_x2 = cursor.read_f64::<LittleEndian>()?;
+    let line_y2 = cursor.read_f64::<LittleEndian>()?;
+    let endpoints = (Vec2::new(line_x1, line_y1), Vec2::new(line_x2, line_y2));
+    let left_ext = line_ext & 0x1 != 0;
+    let right_ext = line_ext & 0x2 != 0;
+
+    match line_type {
+        LineType::Standard => {
+            track_builder
+                .line_group()
+                .add_standard_line(line_id, endpoints, line_inv, left_ext, right_ext);
+        }
+        LineType::Acceleration => {
+            track_builder
+                .line_group()
+                .add_acceleration_line(line_id, endpoints, line_inv, left_ext, right_ext)
+                .multiplier(line_multiplier);
+        }
+        LineType::Scenery => {
+            track_builder
+                .line_group()
+                .add_scenery_line(line_id, endpoints)
+                .width(line_scenery_width);
+        }
+    }
+
+    if line_type != LineType::Scenery {
+        *max_id = (*max_id).max(line_id);
+        track_builder
+            .line_group()
+            .adjacency()
+            .set(line_id, line_link);
+    }
+
+    if let Some((target_zoom, length)) = zoom_trigger {
+        track_builder
+            .legacy_camera_zoom_group()
+            .add_trigger()
+            .trigger(LineHitTrigger::new(line_id, length))
+            .event(CameraZoomEvent::new(target_zoom));
+    }
+
+    Ok(())
+}
+
+// Position of the metadata section after a broken line, found by its magic number
+fn find_metadata(cursor: &Cursor<Vec<u8>>, from: u64) -> Option<u64> {
+    let data = cursor.get_ref();
+    let from = usize::try_from(from).ok()?.min(data.len());
+    data[from..]
+        .windows(4)
+        .position(|window| window == b"META")
+        .and_then(|position| u64::try_from(from + position).ok())
+}
+
+fn read_metadata(
+    cursor: &mut Cursor<Vec<u8>>,
+    track_builder: &mut TrackBuilder,
+    start_properties: &mut StartProperties,
+    record: &mut String,
+    recovery: &mut Recovery,
+) -> Result<(), TrkReadError> {
     let mut meta_magic_number = [0u8; 4];
     cursor.read_exact(&mut meta_magic_number)?;
 
@@ -236,151 +421,162 @@ pub fn read(data: Vec<u8>) -> Result<Track, TrkReadError> {
 
     let num_entries = cursor.read_u16::<LittleEndian>()?;
 
-    let mut start_zoom = from_lra_zoom(4.0);
-    let mut start_gravity_x = 0.0;
-    let mut start_gravity_y = 1.0;
-    let mut gravity_well_size = 10.0;
-    let mut start_line_color_red = 0;
-    let mut start_line_color_green = 0;
-    let mut start_line_color_blue = 0;
-    let mut start_bg_color_red = 244;
-    let mut start_bg_color_green = 245;
-    let mut start_bg_color_blue = 249;
-
-    for _ in 0..num_entries {
-        let meta_string = parse_string::<LittleEndian>(&mut cursor, StringLength::U16)?;
-        let key_value_pair: Vec<&str> = meta_string.split("=").filter(|s| !s.is_empty()).collect();
-
-        if key_value_pair.len() != 2 {
+    for entry_index in 0..num_entries {
+        *record = format!("metadata[{}]", entry_index);
+        let entry_start = cursor.position();
+        let meta_string =
+            parse_string::<LittleEndian>(cursor, StringLength::U16, &mut recovery.budget)?;
+        let entry = read_metadata_entry(
+            &meta_string,
+            track_builder,
+            start_properties,
+            recovery,
+            || ErrorContext::new(Some(entry_start), Some(record.clone())),
+        );
+
+        recovery.recover(entry, "Skipped metadata entry", || {
+            ErrorContext::new(Some(entry_start), Some(record.clone()))
+        })?;
+    }
+
+    Ok(())
+}
+
+fn read_metadata_entry(
+    meta_string: &str,
+    track_builder: &mut TrackBuilder,
+    start_properties: &mut StartProperties,
+    recovery: &mut Recovery,
+    context: impl Fn() -> ErrorContext,
+) -> Result<(), TrkReadError> {
+    let key_value_pair: Vec<&str> = meta_string.split("=").filter(|s| !s.is_empty()).collect();
+
+    if key_value_pair.len() != 2 {
+        return Err(TrkReadError::InvalidData {
+            name: "metadata key value pair".to_string(),
+            value: key_value_pair.join(","),
+        });
+    }
+
+    let key = key_value_pair[0];
+    let value = key_value_pair[1];
+
+    match key {
+        FEATURE_START_ZOOM => {
+            start_properties.zoom = from_lra_zoom(value.parse::<f32>()?);
+        }
+        FEATURE_X_GRAVITY => {
+            start_properties.gravity_x = f64::from(value.parse::<f32>()?);
+        }
+        FEATURE_Y_GRAVITY => {
+            start_properties.gravity_y = f64::from(value.parse::<f32>()?);
+        }
+        FEATURE_GRAVITY_WELL_SIZE => {
+            start_properties.gravity_well_size = value.parse::<f64>()?;
+        }
+        FEATURE_BACKGROUND_COLOR_R => {
+            start_properties.background_color[0] = u8::try_from(value.parse::<i32>()?)?;
+        }
+        FEATURE_BACKGROUND_COLOR_G => {
+            start_properties.background_color[1] = u8::try_from(value.parse::<i32>()?)?;
+        }
+        FEATURE_BACKGROUND_COLOR_B => {
+            start_properties.background_color[2] = u8::try_from(value.parse::<i32>()?)?;
+        }
+        FEATURE_LINE_COLOR_R => {
+            start_properties.line_color[0] = u8::try_from(value.parse::<i32>()?)?;
+        }
+        FEATURE_LINE_COLOR_G => {
+            start_properties.line_color[1] = u8::try_from(value.parse::<i32>()?)?;
+        }
+        FEATURE_LINE_COLOR_B => {
+            start_properties.line_color[2] = u8::try_from(value.parse::<i32>()?)?;
+        }
+        FEATURE_TRIGGERS => {
+            for (i, trigger) in value.split('&').filter(|s| !s.is_empty()).enumerate() {
+                let trigger = read_trigger(i, trigger, track_builder);
+                recovery.recover(trigger, &format!("Skipped trigger {}", i), &context)?;
+            }
+        }
+        other => {
+            track_builder.extras().trk_metadata_entry(other, value);
+        }
+    }
+
+    Ok(())
+}
+
+fn read_trigger(
+    index: usize,
+    trigger: &str,
+    track_builder: &mut TrackBuilder,
+) -> Result<(), TrkReadError> {
+    let values: Vec<&str> = trigger.split(':').filter(|s| !s.is_empty()).collect();
+
+    let expected_size = match values.first() {
+        Some(&"0") => 4,
+        Some(&"1") | Some(&"2") => 6,
+        Some(other) => {
             return Err(TrkReadError::InvalidData {
-                name: "metadata key value pair".to_string(),
-                value: key_value_pair.join(","),
+                name: format!("triggers {} type", index),
+                value: other.to_string(),
             });
         }
+        None => 0,
+    };
 
-        let key = key_value_pair[0];
-        let value = key_value_pair[1];
+    if values.is_empty() || values.len() < expected_size {
+        return Err(TrkReadError::InvalidData {
+            name: "size of trigger data".to_string(),
+            value: values.len().to_string(),
+        });
+    }
 
-        match key {
-            FEATURE_START_ZOOM => {
-                start_zoom = from_lra_zoom(value.parse::<f32>()?);
-            }
-            FEATURE_X_GRAVITY => {
-                start_gravity_x = f64::from(value.parse::<f32>()?);
-            }
-            FEATURE_Y_GRAVITY => {
-                start_gravity_y = f64::from(value.parse::<f32>()?);
-            }
-            FEATURE_GRAVITY_WELL_SIZE => {
-                gravity_well_size = value.parse::<f64>()?;
-            }
-            FEATURE_BACKGROUND_COLOR_R => {
-                start_bg_color_red = u8::try_from(value.parse::<i32>()?)?;
-            }
-            FEATURE_BACKGROUND_COLOR_G => {
-                start_bg_color_green = u8::try_from(value.parse::<i32>()?)?;
-            }
-            FEATURE_BACKGROUND_COLOR_B => {
-                start_bg_color_blue = u8::try_from(value.parse::<i32>()?)?;
-            }
-            FEATURE_LINE_COLOR_R => {
-                start_line_color_red = u8::try_from(value.parse::<i32>()?)?;
-            }
-            FEATURE_LINE_COLOR_G => {
-                start_line_color_green = u8::try_from(value.parse::<i32>()?)?;
-            }
-            FEATURE_LINE_COLOR_B => {
-                start_line_color_blue = u8::try_from(value.parse::<i32>()?)?;
-            }
-            FEATURE_TRIGGERS => {
-                for (i, trigger) in value.split('&').filter(|s| !s.is_empty()).enumerate() {
-                    let values: Vec<&str> = trigger.split(':').filter(|s| !s.is_empty()).collect();
-
-                    if values.is_empty() {
-                        return Err(TrkReadError::InvalidData {
-                            name: "size of trigger data".to_string(),
-                            value: "0".to_string(),
-                        });
-                    }
-
-                    match values[0] {
-                        "0" => {
-                            // Zoom
-                            let target_zoom = from_lra_zoom(values[1].parse::<f32>()?);
-                            let start_frame = u32::try_from(values[2].parse::<i32>()?)?;
-                            let end_frame = u32::try_from(values[3].parse::<i32>()?)?;
-                            let zoom_event = CameraZoomEvent::new(target_zoom);
-                            let frame_bounds = FrameBoundsTrigger::new(start_frame, end_frame);
-                            track_builder
-                                .camera_zoom_group()
-                                .add_trigger()
-                                .trigger(frame_bounds)
-                                .event(zoom_event);
-                        }
-                        "1" => {
-                            // Background Color
-                            let red = u8::try_from(values[1].parse::<i32>()?)?;
-                            let green = u8::try_from(values[2].parse::<i32>()?)?;
-                            let blue = u8::try_from(values[3].parse::<i32>()?)?;
-                            let start_frame = u32::try_from(values[4].parse::<i32>()?)?;
-                            let end_frame = u32::try_from(values[5].parse::<i32>()?)?;
-                            let bg_color_event =
-                                BackgroundColorEvent::new(RGBColor::new(red, green, blue));
-                            let frame_bounds = FrameBoundsTrigger::new(start_frame, end_frame);
-                            track_builder
-                                .background_color_group()
-                                .add_trigger()
-                                .trigger(frame_bounds)
-                                .event(bg_color_event);
-                        }
-                        "2" => {
-                            // Line Color
-                            let red = u8::try_from(values[1].parse::<i32>()?)?;
-                            let green = u8::try_from(values[2].parse::<i32>()?)?;
-                            let blue = u8::try_from(values[3].parse::<i32>()?)?;
-                            let start_frame = u32::try_from(values[4].parse::<i32>()?)?;
-                            let end_frame = u32::try_from(values[5].parse::<i32>()?)?;
-                            let line_color_event =
-                                LineColorEvent::new(RGBColor::new(red, green, blue));
-                            let frame_bounds = FrameBoundsTrigger::new(start_frame, end_frame);
-                            track_builder
-                                .line_color_group()
-                                .add_trigger()
-                                .trigger(frame_bounds)
-                                .event(line_color_event);
-                        }
-                        other => {
-                            return Err(TrkReadError::InvalidData {
-                                name: format!("triggers {} type", i),
-                                value: other.to_string(),
-                            });
-                        }
-                    }
-                }
-            }
-            _ => {}
+    match values[0] {
+        "0" => {
+            // Zoom
+            let target_zoom = from_lra_zoom(values[1].parse::<f32>()?);
+            let start_frame = u32::try_from(values[2].parse::<i32>()?)?;
+            let end_frame = u32::try_from(values[3].parse::<i32>()?)?;
+            let zoom_event = CameraZoomEvent::new(target_zoom);
+            let frame_bounds = FrameBoundsTrigger::new(start_frame, end_frame);
+            track_builder
+                .camera_zoom_group()
+                .add_trigger()
+                .trigger(frame_bounds)
+                .event(zoom_event);
+        }
+        "1" => {
+            // Background Color
+            let red = u8::try_from(values[1].parse::<i32>()?)?;
+            let green = u8::try_from(values[2].parse::<i32>()?)?;
+            let blue = u8::try_from(values[3].parse::<i32>()?)?;
+            let start_frame = u32::try_from(values[4].parse::<i32>()?)?;
+            let end_frame = u32::try_from(values[5].parse::<i32>()?)?;
+            let bg_color_event = BackgroundColorEvent::new(RGBColor::new(red, green, blue));
+            let frame_bounds = FrameBoundsTrigger::new(start_frame, end_frame);
+            track_builder
+                .background_color_group()
+                .add_trigger()
+                .trigger(frame_bounds)
+                .event(bg_color_event);
+        }
+        _ => {
+            // Line Color
+            let red = u8::try_from(values[1].parse::<i32>()?)?;
+            let green = u8::try_from(values[2].parse::<i32>()?)?;
+            let blue = u8::try_from(values[3].parse::<i32>()?)?;
+            let start_frame = u32::try_from(values[4].parse::<i32>()?)?;
+            let end_frame = u32::try_from(values[5].parse::<i32>()?)?;
+            let line_color_event = LineColorEvent::new(RGBColor::new(red, green, blue));
+            let frame_bounds = FrameBoundsTrigger::new(start_frame, end_frame);
+            track_builder
+                .line_color_group()
+                .add_trigger()
+                .trigger(frame_bounds)
+                .event(line_color_event);
         }
     }
 
-    track_builder.metadata().start_zoom(start_zoom);
-    track_builder
-        .metadata()
-        .start_gravity(Vec2::new(start_gravity_x, start_gravity_y));
-    track_builder
-        .metadata()
-        .gravity_well_size(gravity_well_size);
-    track_builder
-        .metadata()
-        .start_background_color(RGBColor::new(
-            start_bg_color_red,
-            start_bg_color_green,
-            start_bg_color_blue,
-        ));
-    track_builder.metadata().start_line_color(RGBColor::new(
-        start_line_color_red,
-        start_line_color_green,
-        start_line_color_blue,
-    ));
-
-    Ok(track_builder.build()?)
+    Ok(())
 }
@@ -0,0 +1,283 @@
+use std::{collections::HashMap, io::Write};
+
+use byteorder::{LittleEndian, WriteBytesExt};
+
+use crate::{
+    formats::trk::{
+        FEATURE_6_1, FEATURE_BACKGROUND_COLOR_B, FEATURE_BACKGROUND_COLOR_G,
+        FEATURE_BACKGROUND_COLOR_R, FEATURE_FRICTIONLESS, FEATURE_GRAVITY_WELL_SIZE,
+        FEATURE_IGNORABLE_TRIGGER, FEATURE_LINE_COLOR_B, FEATURE_LINE_COLOR_G,
+        FEATURE_LINE_COLOR_R, FEATURE_RED_MULTIPLIER, FEATURE_REMOUNT, FEATURE_SCENERY_WIDTH,
+        FEATURE_SONG_INFO, FEATURE_START_ZOOM, FEATURE_TRIGGERS, FEATURE_X_GRAVITY,
+        FEATURE_Y_GRAVITY, FEATURE_ZERO_START, KNOWN_FEATURES, KNOWN_METADATA_KEYS, TrkWriteError,
+    },
+    track::{
+        GridVersion, Track, Vec2,
+        line::{line_adjacency::LineLink, line_view::Line},
+    },
+    util::scale_factor::{to_lra_scenery_width, to_lra_zoom},
+};
+
+pub fn write(track: &Track) -> Result<Vec<u8>, TrkWriteError> {
+    let mut bytes = vec![];
+    let metadata = track.metadata();
+    let line_group = track.line_group();
+
+    let has_red_multiplier = line_group
+        .acceleration_lines()
+        .iter()
+        .any(|line| line.multiplier().is_some());
+    let has_scenery_width = line_group
+        .scenery_lines()
+        .iter()
+        .any(|line| line.width().is_some());
+    let has_song_info = metadata.audio_filename().is_some();
+    let has_ignorable_trigger = track.legacy_camera_zoom_group().is_some();
+
+    let grid_version_feature = match metadata.grid_version() {
+        GridVersion::V6_0 => {
+            return Err(TrkWriteError::InvalidData {
+                name: "grid version".to_string(),
+                value: "6.0".to_string(),
+            });
+        }
+        GridVersion::V6_1 => true,
+        GridVersion::V6_2 => false,
+    };
+
+    let mut features = vec![];
+    for (feature, included) in [
+        (FEATURE_RED_MULTIPLIER, has_red_multiplier),
+        (FEATURE_SCENERY_WIDTH, has_scenery_width),
+        (FEATURE_SONG_INFO, has_song_info),
+        (FEATURE_IGNORABLE_TRIGGER, has_ignorable_trigger),
+        (FEATURE_6_1, grid_version_feature),
+        (FEATURE_ZERO_START, metadata.zero_velocity_start_riders()),
+        (FEATURE_REMOUNT, metadata.remount_riders()),
+        (FEATURE_FRICTIONLESS, metadata.zero_friction_riders()),
+    ] {
+        if included {
+            features.push(format!("{};", feature));
+        }
+    }
+
+    for feature in track.extras().trk_features() {
+        if !KNOWN_FEATURES.contains(&feature.as_str()) {
+            features.push(format!("{};", feature));
+        }
+    }
+
+    // Magic number and version
+    bytes.write_all(b"TRK\xF2")?;
+    bytes.write_u8(1)?;
+    write_string(&mut bytes, &features.concat())?;
+
+    if let Some(audio_filename) = metadata.audio_filename() {
+        let seconds_offset = -metadata.audio_offset_until_start().unwrap_or(0.0);
+        let song_string = format!("{}\r\n{}", audio_filename, seconds_offset);
+
+        // Write 7BitEncodedInt song string length
+        let mut length = song_string.len();
+        while length >= 0x80 {
+            bytes.write_u8(u8::try_from(length & 0x7F)? | 0x80)?;
+            length >>= 7;
+        }
+        bytes.write_u8(u8::try_from(length)?)?;
+        bytes.write_all(song_string.as_bytes())?;
+    }
+
+    let start_position = metadata.start_position().unwrap_or(Vec2::new(0.0, 0.0));
+    bytes.write_f64::<LittleEndian>(start_position.x())?;
+    bytes.write_f64::<LittleEndian>(start_position.y())?;
+
+    let line_count = line_group.line_count();
+    bytes.write_u32::<LittleEndian>(u32::try_from(line_count)?)?;
+
+    let mut zoom_triggers = HashMap::new();
+    if let Some(legacy_camera_zoom_group) = track.legacy_camera_zoom_group() {
+        for trigger in legacy_camera_zoom_group.triggers().iter().rev() {
+            zoom_triggers.insert(trigger.trigger().id(), trigger);
+        }
+    }
+
+    let adjacency = line_group.adjacency();
+    for line in line_group.iter() {
+        let endpoints = line.endpoints();
+
+        if let Line::Scenery(line) = line {
+            // Scenery lines do not store their ids, they get reassigned on read
+            bytes.write_u8(0)?;
+
+            if has_scenery_width {
+                bytes.write_u8(to_lra_scenery_width(line.width().unwrap_or(1.0)))?;
+            }
+
+            write_endpoints(&mut bytes, endpoints)?;
+            continue;
+        }
+
+        let id = line.id();
+        let (line_type, multiplier) = match line {
+            Line::Acceleration(line) => (
+                2,
+                has_red_multiplier.then(|| line.multiplier().unwrap_or(1.0)),
+            ),
+            _ => (1, None),
+        };
+        let extension = u8::from(line.left_extension()) | u8::from(line.right_extension()) << 1;
+        bytes.write_u8(line_type | extension << 5 | u8::from(line.flipped()) << 7)?;
+
+        if let Some(multiplier) = multiplier {
+            bytes.write_u8(multiplier.round().clamp(0.0, f64::from(u8::MAX)) as u8)?;
+        }
+
+        bytes.write_u32::<LittleEndian>(id)?;
+
+        if extension != 0 {
+            write_linked_ids(&mut bytes, adjacency.get(id))?;
+        }
+
+        if has_ignorable_trigger {
+            match zoom_triggers.get(&id) {
+                Some(trigger) => {
+                    bytes.write_u8(1)?;
+                    bytes.write_f32::<LittleEndian>(to_lra_zoom(trigger.event().zoom()))?;
+                    bytes.write_i16::<LittleEndian>(i16::try_from(
+                        trigger.trigger().frame_length(),
+                    )?)?;
+                }
+                None => bytes.write_u8(0)?,
+            }
+        }
+
+        write_endpoints(&mut bytes, endpoints)?;
+    }
+
+    // Metadata section
+
+    let mut meta_entries = vec![];
+
+    if let Some(start_zoom) = metadata.start_zoom() {
+        meta_entries.push(format!(
+            "{}={}",
+            FEATURE_START_ZOOM,
+            to_lra_zoom(start_zoom)
+        ));
+    }
+
+    if let Some(start_gravity) = metadata.start_gravity() {
+        meta_entries.push(format!(
+            "{}={}",
+            FEATURE_X_GRAVITY,
+            start_gravity.x() as f32
+        ));
+        meta_entries.push(format!(
+            "{}={}",
+            FEATURE_Y_GRAVITY,
+            start_gravity.y() as f32
+        ));
+    }
+
+    if let Some(gravity_well_size) = metadata.gravity_well_size() {
+        meta_entries.push(format!(
+            "{}={}",
+            FEATURE_GRAVITY_WELL_SIZE, gravity_well_size
+        ));
+    }
+
+    if let Some(color) = metadata.start_background_color() {
+        meta_entries.push(format!("{}={}", FEATURE_BACKGROUND_COLOR_R, color.red()));
+        meta_entries.push(format!("{}={}", FEATURE_BACKGROUND_COLOR_G, color.green()));
+        meta_entries.push(format!("{}={}", FEATURE_BACKGROUND_COLOR_B, color.blue()));
+    }
+
+    if let Some(color) = metadata.start_line_color() {
+        meta_entries.push(format!("{}={}", FEATURE_LINE_COLOR_R, color.red()));
+        meta_entries.push(format!("{}={}", FEATURE_LINE_COLOR_G, color.green()));
+        meta_entries.push(format!("{}={}", FEATURE_LINE_COLOR_B, color.blue()));
+    }
+
+    let mut triggers = vec![];
+
+    if let Some(camera_zoom_group) = track.camera_zoom_group() {
+        for trigger in camera_zoom_group.triggers() {
+            triggers.push(format!(
+                "0:{}:{}:{}",
+                to_lra_zoom(trigger.event().zoom()),
+                trigger.trigger().start(),
+                trigger.trigger().end()
+            ));
+        }
+    }
+
+    if let Some(background_color_group) = track.background_color_group() {
+        for trigger in background_color_group.triggers() {
+            let color = trigger.event().color();
+            triggers.push(format!(
+                "1:{}:{}:{}:{}:{}",
+                color.red(),
+                color.green(),
+                color.blue(),
+                trigger.trigger().start(),
+                trigger.trigger().end()
+            ));
+        }
+    }
+
+    if let Some(line_color_group) = track.line_color_group() {
+        for trigger in line_color_group.triggers() {
+            let color = trigger.event().color();
+            triggers.push(format!(
+                "2:{}:{}:{}:{}:{}",
+                color.red(),
+                color.green(),
+                color.blue(),
+                trigger.trigger().start(),
+                trigger.trigger().end()
+            ));
+        }
+    }
+
+    if !triggers.is_empty() {
+        meta_entries.push(format!("{}={}", FEATURE_TRIGGERS, triggers.join("&")));
+    }
+
+    for (key, value) in track.extras().trk_metadata() {
+        if !KNOWN_METADATA_KEYS.contains(&key.as_str()) {
+            meta_entries.push(format!("{}={}", key, value));
+        }
+    }
+
+    bytes.write_all(b"META")?;
+    bytes.write_u16::<LittleEndian>(u16::try_from(meta_entries.len())?)?;
+    for entry in &meta_entries {
+        write_string(&mut bytes, entry)?;
+    }
+
+    Ok(bytes)
+}
+
+fn write_string(bytes: &mut Vec<u8>, string: &str) -> Result<(), TrkWriteError> {
+    bytes.write_u16::<LittleEndian>(u16::try_from(string.len())?)?;
+    bytes.write_all(string.as_bytes())?;
+    Ok(())
+}
+
+fn write_linked_ids(bytes: &mut Vec<u8>, link: LineLink) -> Result<(), TrkWriteError> {
+    for linked_id in [link.previous(), link.next()] {
+        let linked_id = match linked_id {
+            Some(id) => i32::try_from(id)?,
+            None => -1,
+        };
+        bytes.write_i32::<LittleEndian>(linked_id)?;
+    }
+    Ok(())
+}
+
+fn write_endpoints(bytes: &mut Vec<u8>, endpoints: (Vec2, Vec2)) -> Result<(), TrkWriteError> {
+    bytes.write_f64::<LittleEndian>(endpoints.0.x())?;
+    bytes.write_f64::<LittleEndian>(endpoints.0.y())?;
+    bytes.write_f64::<LittleEndian>(endpoints.1.x())?;
+    bytes.write_f64::<LittleEndian>(endpoints.1.y())?;
+    Ok(())
+}
@@ -0,0 +1,326 @@
+//! Generators that split curves, shapes and text into connected lines
+
+mod font;
+mod shape;
+mod text;
+
+pub use shape::Shape;
+pub use text::{TextAlign, TextOptions, add_text};
+
+use thiserror::Error;
+
+use crate::track::{
+    LineType, Vec2,
+    line::{
+        line_adjacency::LineLink,
+        line_extensions::{ExtensionLine, ExtensionOptions, compute_extensions},
+        line_group::LineGroupBuilder,
+        line_sequence::LineIdError,
+    },
+};
+
+/// Which side of generated lines riders collide with
+#[derive(Debug, Clone, Copy)]
+pub enum RideSide {
+    /// Left of the direction the shape is drawn in as seen on screen, which is the top of a line
+    /// drawn from left to right
+    Left,
+    /// Right of the direction the shape is drawn in as seen on screen
+    Right,
+    /// The side facing a point, such as the inside of a loop around it
+    Toward(Vec2),
+    /// The side facing away from a point, such as the outside of a hill around it
+    AwayFrom(Vec2),
+}
+
+/// Options for adding a shape to a line group
+#[derive(Debug, Clone, Copy)]
+pub struct ShapeOptions {
+    pub line_type: LineType,
+    /// Longest a generated line may be, curves are split into lines of about this length
+    pub segment_length: f64,
+    /// Ignored for scenery lines
+    pub ride_side: RideSide,
+    /// Extend physics lines at joints that bend toward the ridable side, so riders cannot fall
+    /// through the gap between them
+    pub extensions: bool,
+    /// Id of the layer to draw the lines on
+    pub layer: Option<u32>,
+    /// Width of scenery lines
+    pub scenery_width: Option<f64>,
+}
+
+impl Default for ShapeOptions {
+    fn default() -> Self {
+        Self {
+            line_type: LineType::Standard,
+            segment_length: 10.0,
+            ride_side: RideSide::Left,
+            extensions: true,
+            layer: None,
+            scenery_width: None,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ShapeError {
+    #[error("Segment length must be positive, not {0}")]
+    SegmentLength(f64),
+    #[error("Shape needs at least 2 points, but has {0}")]
+    NotEnoughPoints(usize),
+    #[error("Shape would need more than {0} lines")]
+    TooManySegments(usize),
+    #[error("Text size must be positive, not {0}")]
+    TextSize(f64),
+    #[error("{0}")]
+    LineId(#[from] LineIdError),
+}
+
+/// Add the lines of a shape with the next free ids, connected to each other in order
+///
+/// Returns the ids of the lines that were added, skipping zero length segments.
+pub fn add_shape(
+    line_group: &mut LineGroupBuilder,
+    shape: &Shape,
+    options: &ShapeOptions,
+) -> Result<Vec<u32>, ShapeError> {
+    let points = shape.tessellate(options.segment_length)?;
+    add_points(line_group, &points, shape.is_closed(), options)
+}
+
+/// Add lines joining each point to the next, like `add_shape` does once a shape is tessellated
+///
+/// The segment length is not used, the points are joined as they are. No lines are added if
+/// there are not enough free ids for all of them.
+pub fn add_points(
+    line_group: &mut LineGroupBuilder,
+    points: &[Vec2],
+    closed: bool,
+    options: &ShapeOptions,
+) -> Result<Vec<u32>, ShapeError> {
+    let segments = points
+        .windows(2)
+        .map(|pair| (pair[0], pair[1]))
+        .filter(|(start, end)| shape::distance(*start, *end) > 0.0)
+        .collect::<Vec<_>>();
+    let ids = next_line_ids(line_group, segments.len())?;
+
+    if options.line_type == LineType::Scenery {
+        for (id, endpoints) in ids.iter().zip(segments) {
+            let line = line_group.add_scenery_line(*id, endpoints);
+            if let Some(width) = options.scenery_width {
+                line.width(width);
+            }
+            if let Some(layer) = options.layer {
+                line.layer(layer);
+            }
+        }
+        return Ok(ids);
+    }
+
+    let lines = segments
+        .iter()
+        .map(|&endpoints| ExtensionLine {
+            endpoints,
+            flipped: is_flipped(endpoints, options.ride_side),
+        })
+        .collect::<Vec<_>>();
+
+    let extensions = if options.extensions {
+        compute_extensions(&lines, &ExtensionOptions::default())
+    } else {
+        vec![(false, false); lines.len()]
+    };
+
+    for ((line, (left, right)), id) in lines.iter().zip(extensions).zip(ids.iter().copied()) {
+        if options.line_type == LineType::Acceleration {
+            let line =
+                line_group.add_acceleration_line(id, line.endpoints, line.flipped, left, right);
+            if let Some(layer) = options.layer {
+                line.layer(layer);
+            }
+        } else {
+            let line = line_group.add_standard_line(id, line.endpoints, line.flipped, left, right);
+            if let Some(layer) = options.layer {
+                line.layer(layer);
+            }
+        }
+    }
+
+    let closed = closed && ids.len() > 1;
+    for (index, id) in ids.iter().enumerate() {
+        let previous = match index {
+            0 if closed => ids.last().copied(),
+            0 => None,
+            _ => Some(ids[index - 1]),
+        };
+        let next = match ids.get(index + 1) {
+            Some(next) => Some(*next),
+            None if closed => Some(ids[0]),
+            None => None,
+        };
+        line_group
+            .adjacency()
+            .set(*id, LineLink::new(previous, next));
+    }
+
+    Ok(ids)
+}
+
+/// The next free ids for a number of lines, which follow each other as every added line takes
+/// the id after it
+fn next_line_ids(line_group: &mut LineGroupBuilder, count: usize) -> Result<Vec<u32>, LineIdError> {
+    let Some(last_offset) = count.checked_sub(1) else {
+        return Ok(vec![]);
+    };
+    let first_id = line_group.next_line_id()?;
+    let last_id = u32::try_from(last_offset)
+        .ok()
+        .and_then(|offset| first_id.checked_add(offset))
+        .ok_or(LineIdError::Exhausted)?;
+    Ok((first_id..=last_id).collect())
+}
+
+/// Whether a line needs flipping for riders to collide with the requested side
+fn is_flipped((start, end): (Vec2, Vec2), ride_side: RideSide) -> bool {
+    // Unflipped lines are ridden on the side of the normal (dy, -dx)
+    let normal = (end.y() - start.y(), start.x() - end.x());
+    let middle = Vec2::new((start.x() + end.x()) / 2.0, (start.y() + end.y()) / 2.0);
+    let facing =
+        |point: Vec2| normal.0 * (point.x() - middle.x()) + normal.1 * (point.y() - middle.y());
+
+    match ride_side {
+        RideSide::Left => false,
+        RideSide::Right => true,
+        RideSide::Toward(point) => facing(point) < 0.0,
+        RideSide::AwayFrom(point) => facing(point) > 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use super::{RideSide, Shape, ShapeError, ShapeOptions, TextOptions, add_shape, add_text};
+    use crate::track::{
+        GridVersion, LineType, TrackBuilder, Vec2, line::line_sequence::LineIdError,
+    };
+
+    #[test]
+    fn loops_are_connected_and_ridden_from_the_inside() {
+        let mut track_builder = TrackBuilder::default();
+        track_builder.metadata().grid_version(GridVersion::V6_2);
+        let center = Vec2::new(0.0, 0.0);
+        let options = ShapeOptions {
+            segment_length: 5.0,
+            ride_side: RideSide::Toward(center),
+            ..Default::default()
+        };
+        let circle = Shape::Circle {
+            center,
+            radius: 50.0,
+        };
+        let ids = add_shape(track_builder.line_group(), &circle, &options).unwrap();
+        let track = track_builder.build().unwrap();
+        let line_group = track.line_group();
+
+        // 2 pi 50 is just under 315
+        assert_eq!(ids.len(), 63);
+        for id in &ids {
+            let line = line_group.line(*id).unwrap();
+            let (start, _) = line.endpoints();
+            let normal = line.normal();
+            assert!(normal.x() * start.x() + normal.y() * start.y() < 0.0);
+            assert!(line.left_extension() && line.right_extension());
+            assert!(line.length() <= 5.0);
+        }
+
+        let link = line_group.adjacency().get(ids[0]);
+        assert_eq!(link.previous(), Some(ids[62]));
+        assert_eq!(link.next(), Some(ids[1]));
+    }
+
+    #[test]
+    fn curves_end_on_their_last_control_point() {
+        let curve = Shape::CubicBezier(
+            Vec2::new(0.0, 0.0),
+            Vec2::new(0.0, -100.0),
+            Vec2::new(100.0, -100.0),
+            Vec2::new(100.0, 0.0),
+        );
+        let points = curve.tessellate(10.0).unwrap();
+        let last = points[points.len() - 1];
+        assert_eq!((last.x(), last.y()), (100.0, 0.0));
+
+        let spline = Shape::Spline {
+            points: vec![
+                Vec2::new(0.0, 0.0),
+                Vec2::new(50.0, -20.0),
+                Vec2::new(100.0, 0.0),
+            ],
+            closed: false,
+        };
+        let points = spline.tessellate(10.0).unwrap();
+        assert!(points.windows(2).all(|pair| {
+            (pair[0].x() - pair[1].x()).hypot(pair[0].y() - pair[1].y()) <= 10.0 + 1e-9
+        }));
+    }
+
+    #[test]
+    fn text_is_rotated_around_its_position_on_a_named_layer() {
+        let mut track_builder = TrackBuilder::default();
+        track_builder.metadata().grid_version(GridVersion::V6_2);
+        let layer = track_builder.layer_group().layer_named("Credits").unwrap();
+        assert_eq!(
+            track_builder.layer_group().layer_named("Credits").unwrap(),
+            layer
+        );
+
+        let options = TextOptions {
+            position: Vec2::new(100.0, 100.0),
+            size: 9.0,
+            rotation: FRAC_PI_2,
+            layer: Some(layer),
+            ..Default::default()
+        };
+        let ids = add_text(track_builder.line_group(), "Hi\n\u{e9}", &options).unwrap();
+        let track = track_builder.build().unwrap();
+        let line_group = track.line_group();
+
+        // H has 3 strokes, i has 2 and the unknown character is drawn as ? with 2 more
+        assert_eq!(ids.len(), 3 + 2 + 7);
+        let first = line_group.line(ids[0]).unwrap();
+        assert_eq!(first.line_type(), LineType::Scenery);
+        assert_eq!(first.layer(), Some(layer));
+
+        // Turned a quarter clockwise on screen, the stem of H points from the top of the letter
+        // at the right down to the baseline at the position
+        let (start, end) = first.endpoints();
+        assert!((start.x() - 109.0).abs() < 1e-9 && (start.y() - 100.0).abs() < 1e-9);
+        assert!((end.x() - 100.0).abs() < 1e-9 && (end.y() - 100.0).abs() < 1e-9);
+
+        // The second line of text is one line height further from the baseline
+        let question_mark = line_group.line(ids[5]).unwrap();
+        assert!((question_mark.endpoints().0.x() - (100.0 - 15.0 + 8.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn shapes_without_enough_free_ids_add_no_lines() {
+        let mut track_builder = TrackBuilder::default();
+        let line_group = track_builder.line_group();
+        let point = Vec2::new(0.0, 0.0);
+        line_group.add_scenery_line(u32::MAX - 1, (point, point));
+
+        let shape = Shape::Polyline {
+            points: vec![point, Vec2::new(10.0, 0.0), Vec2::new(10.0, 10.0)],
+            closed: false,
+        };
+        let Err(ShapeError::LineId(LineIdError::Exhausted)) =
+            add_shape(line_group, &shape, &ShapeOptions::default())
+        else {
+            panic!("Expected the shape to need more ids than are free");
+        };
+        assert_eq!(line_group.get_standard_lines().count(), 0);
+    }
+}
@@ -0,0 +1,169 @@
+//! Single stroke font in the style of the Hershey fonts
+
+/// Height of capital letters, from their top at y 0 down to the baseline
+pub(super) const CAP_HEIGHT: f64 = 9.0;
+/// Distance between the baselines of lines of text
+pub(super) const LINE_HEIGHT: f64 = 15.0;
+pub(super) const SPACE_WIDTH: f64 = 4.0;
+/// Gap between the widest points of neighbouring characters
+pub(super) const LETTER_SPACING: f64 = 2.0;
+
+/// Strokes of each printable ASCII character from `!` to `~`
+///
+/// Each stroke is a run of points written as two base 36 digits, x then y, and strokes are
+/// separated by spaces. Capitals span from y 0 down to the baseline at y 9, lowercase letters
+/// start at y 3 and descenders reach down to y 12.
+const GLYPHS: [&str; 94] = [
+    "0006 0809",                           // !
+    "0002 2022",                           // "
+    "2128 4148 0363 0666",                 // #
+    "615010010314546568591908 3039",       // $
+    "0960 1021120110 5768594857",          // %
+    "691311203041420608193966",            // &
+    "0002",                                // '
+    "201103071829",                        // (
+    "001123271809",                        // )
+    "3036 0165 0561",                      // *
+    "3238 0565",                           // +
+    "18190a",                              // ,
+    "0545",                                // -
+    "0809",                                // .
+    "0950",                                // /
+    "104051584919080110 5108",             // 0
+    "123039 1959",                         // 1
+    "01104051530959",                      // 2
+    "01104051534424 445558491908",         // 3
+    "49400656",                            // 4
+    "500004445558491908",                  // 5
+    "5020030819495855441405",              // 6
+    "005029",                              // 7
+    "104051534414030110 1405081949585544", // 8
+    "0939565140100104154554",              // 9
+    "0304 0809",                           // :
+    "1314 18190a",                         // ;
+    "610569",                              // <
+    "0363 0767",                           // =
+    "016509",                              // >
+    "01104051533536 3839",                 // ?
+    "46432314152646566561501001081959",    // @
+    "093069 1656",                         // A
+    "090040515344045465685909",            // B
+    "6150100108195968",                    // C
+    "00406168490900",                      // D
+    "60000969 0444",                       // E
+    "600009 0444",                         // F
+    "61501001081959686535",                // G
+    "0009 6069 0464",                      // H
+    "1050 3039 1959",                      // I
+    "5058491908",                          // J
+    "0009 6006 2469",                      // K
+    "000969",                              // L
+    "0900356069",                          // M
+    "09006960",                            // N
+    "105061685919080110",                  // O
+    "09005061635404",                      // P
+    "105061685919080110 4769",             // Q
+    "09005061635404 3469",                 // R
+    "615010010314546568591908",            // S
+    "0060 3039",                           // T
+    "000819596860",                        // U
+    "003960",                              // V
+    "0019345960",                          // W
+    "0069 6009",                           // X
+    "003460 3439",                         // Y
+    "00600969",                            // Z
+    "20000929",                            // [
+    "0059",                                // \
+    "00202909",                            // ]
+    "033063",                              // ^
+    "0a6a",                                // _
+    "0011",                                // `
+    "5359 5443130408194958",               // a
+    "0009 0413435458491908",               // b
+    "5443130408194958",                    // c
+    "5059 5443130408194958",               // d
+    "06565443130408194958",                // e
+    "40201119 0333",                       // f
+    "535b4c1c0b 5443130408194958",         // g
+    "0009 0413435459",                     // h
+    "1319 1112",                           // i
+    "333b2c0c 3132",                       // j
+    "0009 4307 2559",                      // k
+    "000819",                              // l
+    "0309 0413233439 3443536469",          // m
+    "0309 0413435459",                     // n
+    "134354584919080413",                  // o
+    "030c 0413435458491908",               // p
+    "535c 5443130408194958",               // q
+    "0309 052343",                         // r
+    "544313040516465758491908",            // s
+    "10182939 0333",                       // t
+    "0308194958 5359",                     // u
+    "033963",                              // v
+    "0319355963",                          // w
+    "0359 5309",                           // x
+    "0339 63391c",                         // y
+    "03530959",                            // z
+    "302011130415182939",                  // {
+    "000a",                                // |
+    "001021233425281909",                  // }
+    "051424455564",                        // ~
+];
+
+/// Strokes of a character, moved so the leftmost point is at x 0
+pub(super) struct Glyph {
+    pub strokes: Vec<Vec<(f64, f64)>>,
+    pub width: f64,
+}
+
+/// The glyph of a printable ASCII character other than space
+pub(super) fn glyph(char: char) -> Option<Glyph> {
+    let index = (char as usize).checked_sub('!' as usize)?;
+    let encoded = GLYPHS.get(index)?;
+
+    let strokes = encoded
+        .split(' ')
+        .map(|stroke| {
+            stroke
+                .as_bytes()
+                .chunks_exact(2)
+                .map(|pair| (digit(pair[0]), digit(pair[1])))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let points = || strokes.iter().flatten().map(|(x, _)| *x);
+    let left = points().fold(f64::INFINITY, f64::min);
+    let right = points().fold(f64::NEG_INFINITY, f64::max);
+
+    Some(Glyph {
+        strokes: strokes
+            .iter()
+            .map(|stroke| stroke.iter().map(|(x, y)| (x - left, *y)).collect())
+            .collect(),
+        width: right - left,
+    })
+}
+
+fn digit(byte: u8) -> f64 {
+    f64::from(char::from(byte).to_digit(36).unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GLYPHS, glyph};
+
+    #[test]
+    fn every_printable_character_has_a_glyph() {
+        for encoded in GLYPHS {
+            for stroke in encoded.split(' ') {
+                assert!(stroke.len() >= 4 && stroke.len() % 2 == 0, "{}", encoded);
+                assert!(stroke.chars().all(|char| char.is_digit(13)), "{}", encoded);
+            }
+        }
+
+        assert!(('!'..='~').all(|char| glyph(char).is_some()));
+        assert!(glyph(' ').is_none() && glyph('é').is_none());
+        assert_eq!(glyph('i').unwrap().width, 0.0);
+    }
+}
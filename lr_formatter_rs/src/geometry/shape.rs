@@ -0,0 +1,246 @@
+use std::f64::consts::TAU;
+
+use crate::{geometry::ShapeError, track::Vec2};
+
+/// Most lines a single shape may be split into
+const MAX_SEGMENTS: usize = 10_000_000;
+
+/// Samples per segment used to measure curves before splitting them evenly
+const SAMPLES_PER_SEGMENT: usize = 8;
+const MAX_SAMPLES: usize = 1 << 20;
+
+/// A path that can be split into straight lines
+///
+/// Angles are in radians, measured the same way as `Line::angle`, so with y pointing down a
+/// positive sweep turns clockwise on screen.
+#[derive(Debug, Clone)]
+pub enum Shape {
+    /// Straight segments through each point, with long segments split to the segment length
+    Polyline {
+        points: Vec<Vec2>,
+        closed: bool,
+    },
+    /// Part of a circle, from the start angle to the end angle
+    Arc {
+        center: Vec2,
+        radius: f64,
+        start_angle: f64,
+        end_angle: f64,
+    },
+    Circle {
+        center: Vec2,
+        radius: f64,
+    },
+    QuadraticBezier(Vec2, Vec2, Vec2),
+    CubicBezier(Vec2, Vec2, Vec2, Vec2),
+    /// Catmull-Rom spline passing through each point
+    Spline {
+        points: Vec<Vec2>,
+        closed: bool,
+    },
+}
+
+impl Shape {
+    /// Whether the last point joins back onto the first
+    pub fn is_closed(&self) -> bool {
+        match self {
+            Shape::Polyline { closed, .. } | Shape::Spline { closed, .. } => *closed,
+            Shape::Arc {
+                start_angle,
+                end_angle,
+                ..
+            } => (end_angle - start_angle).abs() >= TAU,
+            Shape::Circle { .. } => true,
+            Shape::QuadraticBezier(..) | Shape::CubicBezier(..) => false,
+        }
+    }
+
+    /// Points along the shape, spaced at most about `segment_length` apart
+    ///
+    /// Closed shapes repeat their first point at the end.
+    pub fn tessellate(&self, segment_length: f64) -> Result<Vec<Vec2>, ShapeError> {
+        if !segment_length.is_finite() || segment_length <= 0.0 {
+            return Err(ShapeError::SegmentLength(segment_length));
+        }
+
+        match self {
+            Shape::Polyline { points, closed } => {
+                let points = closed_points(points, *closed)?;
+                let mut tessellated = vec![points[0]];
+
+                for pair in points.windows(2) {
+                    let count = segment_count(distance(pair[0], pair[1]), segment_length)?;
+                    tessellated.extend(
+                        (1..=count).map(|i| lerp(pair[0], pair[1], i as f64 / count as f64)),
+                    );
+                }
+
+                Ok(tessellated)
+            }
+            Shape::Arc {
+                center,
+                radius,
+                start_angle,
+                end_angle,
+            } => arc(*center, *radius, *start_angle, *end_angle, segment_length),
+            Shape::Circle { center, radius } => arc(*center, *radius, 0.0, TAU, segment_length),
+            Shape::QuadraticBezier(p0, p1, p2) => sample_curve(
+                |t| {
+                    let u = 1.0 - t;
+                    weighted(&[(*p0, u * u), (*p1, 2.0 * u * t), (*p2, t * t)])
+                },
+                segment_length,
+            ),
+            Shape::CubicBezier(p0, p1, p2, p3) => sample_curve(
+                |t| {
+                    let u = 1.0 - t;
+                    weighted(&[
+                        (*p0, u * u * u),
+                        (*p1, 3.0 * u * u * t),
+                        (*p2, 3.0 * u * t * t),
+                        (*p3, t * t * t),
+                    ])
+                },
+                segment_length,
+            ),
+            Shape::Spline { points, closed } => {
+                let points = closed_points(points, *closed)?;
+                let spans = points.len() - 1;
+                // Neighbours of the first and last points, wrapping around for closed splines
+                let point = |index: isize| -> Vec2 {
+                    if *closed {
+                        points[index.rem_euclid(spans as isize) as usize]
+                    } else {
+                        points[index.clamp(0, spans as isize) as usize]
+                    }
+                };
+
+                sample_curve(
+                    |t| {
+                        let position = t * spans as f64;
+                        let span = (position.floor() as isize).min(spans as isize - 1);
+                        catmull_rom(
+                            [
+                                point(span - 1),
+                                point(span),
+                                point(span + 1),
+                                point(span + 2),
+                            ],
+                            position - span as f64,
+                        )
+                    },
+                    segment_length,
+                )
+            }
+        }
+    }
+}
+
+fn closed_points(points: &[Vec2], closed: bool) -> Result<Vec<Vec2>, ShapeError> {
+    if points.len() < 2 {
+        return Err(ShapeError::NotEnoughPoints(points.len()));
+    }
+
+    let mut points = points.to_vec();
+    if closed {
+        points.push(points[0]);
+    }
+
+    Ok(points)
+}
+
+fn arc(
+    center: Vec2,
+    radius: f64,
+    start_angle: f64,
+    end_angle: f64,
+    segment_length: f64,
+) -> Result<Vec<Vec2>, ShapeError> {
+    let sweep = (end_angle - start_angle).clamp(-TAU, TAU);
+    let count = segment_count((sweep * radius).abs(), segment_length)?;
+
+    Ok((0..=count)
+        .map(|i| {
+            let angle = start_angle + sweep * i as f64 / count as f64;
+            Vec2::new(
+                center.x() + radius * angle.cos(),
+                center.y() + radius * angle.sin(),
+            )
+        })
+        .collect())
+}
+
+/// Split a curve over `t` in 0 to 1 into segments of equal length along the curve
+fn sample_curve(curve: impl Fn(f64) -> Vec2, segment_length: f64) -> Result<Vec<Vec2>, ShapeError> {
+    let measure = |samples: usize| -> (Vec<Vec2>, Vec<f64>) {
+        let points = (0..=samples)
+            .map(|i| curve(i as f64 / samples as f64))
+            .collect::<Vec<_>>();
+        let mut lengths = vec![0.0];
+        for pair in points.windows(2) {
+            lengths.push(lengths[lengths.len() - 1] + distance(pair[0], pair[1]));
+        }
+        (points, lengths)
+    };
+
+    let (_, rough_lengths) = measure(64);
+    let rough_count = segment_count(rough_lengths[64], segment_length)?;
+    let (points, lengths) = measure((rough_count * SAMPLES_PER_SEGMENT).clamp(64, MAX_SAMPLES));
+
+    let total_length = lengths[lengths.len() - 1];
+    let count = segment_count(total_length, segment_length)?;
+    let mut tessellated = vec![points[0]];
+    let mut sample = 0;
+
+    for i in 1..count {
+        let target = total_length * i as f64 / count as f64;
+        while lengths[sample + 1] < target {
+            sample += 1;
+        }
+
+        let span = lengths[sample + 1] - lengths[sample];
+        let along = if span > 0.0 {
+            (target - lengths[sample]) / span
+        } else {
+            0.0
+        };
+        tessellated.push(lerp(points[sample], points[sample + 1], along));
+    }
+
+    tessellated.push(points[points.len() - 1]);
+    Ok(tessellated)
+}
+
+fn segment_count(length: f64, segment_length: f64) -> Result<usize, ShapeError> {
+    let count = (length / segment_length).ceil().max(1.0);
+    if !count.is_finite() || count > MAX_SEGMENTS as f64 {
+        return Err(ShapeError::TooManySegments(MAX_SEGMENTS));
+    }
+
+    Ok(count as usize)
+}
+
+fn catmull_rom([p0, p1, p2, p3]: [Vec2; 4], t: f64) -> Vec2 {
+    let (t2, t3) = (t * t, t * t * t);
+    weighted(&[
+        (p0, 0.5 * (-t3 + 2.0 * t2 - t)),
+        (p1, 0.5 * (3.0 * t3 - 5.0 * t2 + 2.0)),
+        (p2, 0.5 * (-3.0 * t3 + 4.0 * t2 + t)),
+        (p3, 0.5 * (t3 - t2)),
+    ])
+}
+
+fn weighted(points: &[(Vec2, f64)]) -> Vec2 {
+    let (x, y) = points.iter().fold((0.0, 0.0), |(x, y), (point, weight)| {
+        (x + point.x() * weight, y + point.y() * weight)
+    });
+    Vec2::new(x, y)
+}
+
+fn lerp(a: Vec2, b: Vec2, t: f64) -> Vec2 {
+    Vec2::new(a.x() + (b.x() - a.x()) * t, a.y() + (b.y() - a.y()) * t)
+}
+
+pub(super) fn distance(a: Vec2, b: Vec2) -> f64 {
+    (a.x() - b.x()).hypot(a.y() - b.y())
+}
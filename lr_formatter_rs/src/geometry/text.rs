@@ -0,0 +1,128 @@
+use crate::{
+    geometry::{
+        RideSide, ShapeError, ShapeOptions, add_points,
+        font::{CAP_HEIGHT, LETTER_SPACING, LINE_HEIGHT, SPACE_WIDTH, glyph},
+    },
+    track::{LineType, Vec2, line::line_group::LineGroupBuilder},
+};
+
+/// Where each line of text is placed relative to the text position
+#[derive(Debug, Clone, Copy, Default)]
+pub enum TextAlign {
+    /// Lines start at the position
+    #[default]
+    Left,
+    /// Lines are centered on the position
+    Center,
+    /// Lines end at the position
+    Right,
+}
+
+/// Options for adding text to a line group
+#[derive(Debug, Clone, Copy)]
+pub struct TextOptions {
+    /// Point on the baseline of the first line of text that lines are aligned to
+    pub position: Vec2,
+    /// Height of capital letters
+    pub size: f64,
+    /// Angle of the baseline in radians, measured the same way as `Line::angle`
+    pub rotation: f64,
+    pub align: TextAlign,
+    pub line_type: LineType,
+    /// Ignored for scenery lines
+    pub ride_side: RideSide,
+    /// Id of the layer to draw the lines on
+    pub layer: Option<u32>,
+    /// Width of scenery lines
+    pub scenery_width: Option<f64>,
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        Self {
+            position: Vec2::new(0.0, 0.0),
+            size: 20.0,
+            rotation: 0.0,
+            align: TextAlign::default(),
+            line_type: LineType::Scenery,
+            ride_side: RideSide::Left,
+            layer: None,
+            scenery_width: None,
+        }
+    }
+}
+
+/// Write text with a built in single stroke font, adding the lines with the next free ids
+///
+/// Lines of text are separated by `\n`, and characters other than printable ASCII are drawn as
+/// `?`. Returns the ids of the lines that were added.
+pub fn add_text(
+    line_group: &mut LineGroupBuilder,
+    text: &str,
+    options: &TextOptions,
+) -> Result<Vec<u32>, ShapeError> {
+    if !options.size.is_finite() || options.size <= 0.0 {
+        return Err(ShapeError::TextSize(options.size));
+    }
+
+    let shape_options = ShapeOptions {
+        line_type: options.line_type,
+        ride_side: options.ride_side,
+        layer: options.layer,
+        scenery_width: options.scenery_width,
+        ..Default::default()
+    };
+    let scale = options.size / CAP_HEIGHT;
+    let (sin, cos) = options.rotation.sin_cos();
+    // Font units along and below the baseline to track coordinates
+    let place = |x: f64, y: f64| {
+        let (x, y) = (x * scale, y * scale);
+        Vec2::new(
+            options.position.x() + x * cos - y * sin,
+            options.position.y() + x * sin + y * cos,
+        )
+    };
+
+    let mut ids = vec![];
+
+    for (row, line) in text.lines().enumerate() {
+        let glyphs = line
+            .chars()
+            .map(|char| match char {
+                ' ' | '\t' => None,
+                char => glyph(char).or_else(|| glyph('?')),
+            })
+            .collect::<Vec<_>>();
+
+        let advances = glyphs
+            .iter()
+            .map(|glyph| match glyph {
+                Some(glyph) => glyph.width + LETTER_SPACING,
+                None => SPACE_WIDTH,
+            })
+            .collect::<Vec<_>>();
+        let width = (advances.iter().sum::<f64>() - LETTER_SPACING).max(0.0);
+        let mut x = match options.align {
+            TextAlign::Left => 0.0,
+            TextAlign::Center => -width / 2.0,
+            TextAlign::Right => -width,
+        };
+        let baseline = row as f64 * LINE_HEIGHT;
+
+        for (glyph, advance) in glyphs.iter().zip(advances) {
+            for stroke in glyph.iter().flat_map(|glyph| &glyph.strokes) {
+                let points = stroke
+                    .iter()
+                    .map(|(stroke_x, stroke_y)| {
+                        place(x + stroke_x, baseline + stroke_y - CAP_HEIGHT)
+                    })
+                    .collect::<Vec<_>>();
+                let closed = stroke.len() > 2 && stroke.first() == stroke.last();
+                ids.extend(add_points(line_group, &points, closed, &shape_options)?);
+            }
+            x += advance;
+        }
+    }
+
+    Ok(ids)
+}
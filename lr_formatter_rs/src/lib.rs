@@ -1,6 +1,6 @@
 //! This crate provides safe, compatible reading and writing of Line Rider track file formats.
 //!
-//! Supported formats: SOL (read/write), JSON (read/write), TRK (read)
+//! Supported formats: SOL (read/write), JSON (read/write), TRK (read/write)
 //!
 //! # Quickstart
 //!
@@ -15,5 +15,6 @@
 //! ```
 
 pub mod formats;
+pub mod geometry;
 pub mod track;
 pub(crate) mod util;
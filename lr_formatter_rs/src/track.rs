@@ -8,7 +8,7 @@ use std::collections::HashSet;
 
 pub use grid_version::GridVersion;
 pub use group_builder::group_builder_error::{GroupBuilderError, IntoGroupResult};
-pub use groups::{layer, line, metadata, rider, trigger};
+pub use groups::{extras, layer, line, metadata, rider, trigger};
 pub use line_type::LineType;
 pub use primitives::{
     BackgroundColorEvent, CameraZoomEvent, FrameBoundsTrigger, FrameReachedTrigger, LineColorEvent,
@@ -16,6 +16,7 @@ pub use primitives::{
 };
 
 use crate::track::{
+    extras::{Extras, ExtrasBuilder, ExtrasBuilderError},
     group_builder::{
         group_builder_base::{GroupBuilder, GroupBuilderBase},
         group_builder_macro::define_group_builder,
@@ -30,8 +31,15 @@ use crate::track::{
         },
         line_color_group::{LineColorGroup, LineColorGroupBuilder, LineColorGroupBuilderError},
     },
-    layer::layer_group::{LayerGroup, LayerGroupBuilder, LayerGroupBuilderError},
-    line::line_group::{LineGroup, LineGroupBuilder, LineGroupBuilderError},
+    layer::{
+        layer_group::{LayerGroup, LayerGroupBuilder, LayerGroupBuilderError},
+        layer_tree::{LayerLines, LayerTreeError},
+    },
+    line::{
+        line_cleanup::{CleanupOptions, CleanupReport},
+        line_extensions::ExtensionOptions,
+        line_group::{LineGroup, LineGroupBuilder, LineGroupBuilderError},
+    },
     metadata::{Metadata, MetadataBuilder, MetadataBuilderError},
     rider::rider_group::{RiderGroup, RiderGroupBuilder, RiderGroupBuilderError},
 };
@@ -55,6 +63,7 @@ define_group_builder!(
         line_color_group: Option<LineColorGroup>, Option<LineColorGroupBuilder>, LineColorGroupBuilderError,
         camera_zoom_group: Option<CameraZoomGroup>, Option<CameraZoomGroupBuilder>, CameraZoomGroupBuilderError,
         legacy_camera_zoom_group: Option<LegacyCameraZoomGroup>, Option<LegacyCameraZoomGroupBuilder>, LegacyCameraZoomGroupBuilderError,
+        extras: Extras, ExtrasBuilder, ExtrasBuilderError,
     }
 );
 
@@ -97,6 +106,8 @@ impl GroupBuilder for TrackBuilder {
             None => None,
         };
 
+        let extras = self.extras.build().map_group_err()?;
+
         Ok(Track {
             features: self.features.clone(),
             metadata,
@@ -107,6 +118,7 @@ impl GroupBuilder for TrackBuilder {
             line_color_group,
             camera_zoom_group,
             legacy_camera_zoom_group,
+            extras,
         })
     }
 }
@@ -174,6 +186,106 @@ impl TrackBuilder {
         )
     }
 
+    pub fn extras(&mut self) -> &mut ExtrasBuilder {
+        &mut self.extras
+    }
+
+    /// Recompute line extensions from geometry, like the editors do when lines get connected
+    pub fn compute_line_extensions(&mut self, options: ExtensionOptions) -> &mut Self {
+        self.line_group.compute_extensions(options);
+        self
+    }
+
+    /// Remove and merge redundant lines, moving legacy zoom triggers on removed lines onto the
+    /// lines that replaced them
+    pub fn clean_up_lines(&mut self, options: &CleanupOptions) -> CleanupReport {
+        let report = self.line_group.clean_up(options);
+        let replacements = report.replacements();
+
+        if let Some(legacy_camera_zoom_group) = self.legacy_camera_zoom_group.as_mut() {
+            for trigger in legacy_camera_zoom_group.get_triggers() {
+                let replaced = trigger.get_trigger().and_then(|line_hit| {
+                    let id = *replacements.get(&line_hit.id())?;
+                    Some(LineHitTrigger::new(id, line_hit.frame_length()))
+                });
+                if let Some(line_hit) = replaced {
+                    trigger.trigger(line_hit);
+                }
+            }
+        }
+
+        report
+    }
+
+    /// Convert the legacy zoom triggers the rider sets off into frame based zoom triggers
+    ///
+    /// See `LegacyCameraZoomGroupBuilder::remove_fired_triggers` for how `frames` is used. Returns
+    /// the line ids of the legacy triggers that never went off, which are kept.
+    pub fn convert_legacy_zoom_triggers<F>(
+        &mut self,
+        frames: impl IntoIterator<Item = F>,
+    ) -> Vec<u32>
+    where
+        F: IntoIterator<Item = u32>,
+    {
+        let Some(legacy_camera_zoom_group) = self.legacy_camera_zoom_group.as_mut() else {
+            return vec![];
+        };
+        let zooms = legacy_camera_zoom_group.remove_fired_triggers(frames);
+        let unfired = legacy_camera_zoom_group
+            .get_triggers()
+            .filter_map(|trigger| Some(trigger.get_trigger()?.id()))
+            .collect::<Vec<_>>();
+
+        if legacy_camera_zoom_group.get_triggers().next().is_none() {
+            self.legacy_camera_zoom_group = None;
+            self.features
+                .remove(&TrackFeature::LegacyCameraZoomTriggers);
+        }
+
+        if !zooms.is_empty() {
+            let camera_zoom_group = self.camera_zoom_group();
+            for (event, frame_bounds) in zooms {
+                camera_zoom_group
+                    .add_trigger()
+                    .event(event)
+                    .trigger(frame_bounds);
+            }
+        }
+
+        unfired
+    }
+
+    /// Delete a layer, or a folder along with its layers, and move or delete the lines on them
+    ///
+    /// Lines without a layer are on the layer with id 0. Returns the ids of the deleted lines.
+    pub fn delete_layer(&mut self, id: u32, lines: LayerLines) -> Result<Vec<u32>, LayerTreeError> {
+        let layer_group = self
+            .layer_group
+            .as_mut()
+            .ok_or(LayerTreeError::Missing(id))?;
+        let layers = layer_group
+            .layers_in(id)
+            .ok_or(LayerTreeError::Missing(id))?;
+        if let LayerLines::MoveTo(target) = lines
+            && (layers.contains(&target) || !layer_group.is_layer(target))
+        {
+            return Err(LayerTreeError::InvalidLineTarget(target));
+        }
+        let layers = layer_group
+            .remove_layer(id)?
+            .into_iter()
+            .collect::<HashSet<_>>();
+
+        match lines {
+            LayerLines::MoveTo(target) => {
+                self.line_group.move_lines_to_layer(&layers, target);
+                Ok(vec![])
+            }
+            LayerLines::Delete => Ok(self.line_group.remove_lines_on_layers(&layers)),
+        }
+    }
+
     pub fn build(&mut self) -> Result<Track, GroupBuilderError<TrackSubBuilderError>> {
         self.build_group()
     }
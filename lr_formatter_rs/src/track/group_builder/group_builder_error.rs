@@ -13,6 +13,8 @@ where
 {
     /// Error from a sub-builder
     SubBuilderError(SubError),
+    /// More than one item of the group uses this id
+    DuplicateId(u32),
 }
 
 impl<SubError> Display for GroupBuilderError<SubError>
@@ -22,6 +24,7 @@ where
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             GroupBuilderError::SubBuilderError(err) => write!(f, "{}", err),
+            GroupBuilderError::DuplicateId(id) => write!(f, "Id {} is used more than once", id),
         }
     }
 }
@@ -33,6 +36,7 @@ where
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
             GroupBuilderError::SubBuilderError(e) => Some(e),
+            GroupBuilderError::DuplicateId(_) => None,
         }
     }
 }
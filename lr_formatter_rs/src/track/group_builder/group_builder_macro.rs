@@ -11,6 +11,18 @@ macro_rules! define_group_builder {
                 $field_name:ident : $field_ty:ty, $builder_ty:ty, $error_ty:ty
             ),* $(,)?
         }
+
+        $(
+            private {
+                $($private_name:ident : $private_ty:ty),* $(,)?
+            }
+        )?
+
+        $(
+            private_builder {
+                $($private_builder_name:ident : $private_builder_ty:ty),* $(,)?
+            }
+        )?
     ) => {
         paste::paste! {
             #[derive(Debug, derive_more::Display, PartialEq, Eq, Hash, Clone, Copy)]
@@ -22,13 +34,18 @@ macro_rules! define_group_builder {
             #[getset(get = "pub")]
             pub struct $name {
                 features: HashSet<$feature_ty>,
-                $($field_name: $field_ty),*
+                $($field_name: $field_ty,)*
+                $($(
+                    #[getset(skip)]
+                    $private_name: $private_ty,
+                )*)?
             }
 
             #[derive(Default, Clone)]
             pub struct [<$name Builder>] {
                 features: HashSet<$feature_ty>,
-                $($field_name: $builder_ty),*
+                $($field_name: $builder_ty,)*
+                $($($private_builder_name: $private_builder_ty,)*)?
             }
 
             #[derive(Debug, thiserror::Error)]
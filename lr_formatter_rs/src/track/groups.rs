@@ -1,3 +1,4 @@
+pub mod extras;
 pub mod layer;
 pub mod line;
 pub mod metadata;
@@ -0,0 +1,71 @@
+use derive_builder::Builder;
+use getset::Getters;
+use indexmap::IndexMap;
+use serde_json::{Map, Value};
+
+use crate::formats::sol::{SolFlavor, raw::Amf0Value};
+
+/// Properties that formats store but tracks do not model, kept so that writing back to the
+/// same format re-emits them
+///
+/// Each format has its own bag, keyed by the names the format uses.
+#[derive(Getters, Debug, Clone, Default, Builder)]
+#[getset(get = "pub")]
+pub struct Extras {
+    /// Unknown flags of a TRK feature string
+    #[builder(setter(custom), default)]
+    trk_features: Vec<String>,
+    /// Unknown entries of a TRK metadata section, keyed by metadata key
+    #[builder(setter(custom), default)]
+    trk_metadata: IndexMap<String, String>,
+    /// Unknown top level keys of a JSON track
+    #[builder(setter(custom), default)]
+    json_properties: Map<String, Value>,
+    /// Unknown properties of a SOL track object, keyed by property name
+    #[builder(setter(custom), default)]
+    sol_properties: IndexMap<String, Amf0Value>,
+    /// Layout of the SOL file the track was read from
+    #[builder(setter(strip_option), default)]
+    #[getset(skip)]
+    sol_flavor: Option<SolFlavor>,
+}
+
+impl Extras {
+    pub fn sol_flavor(&self) -> Option<SolFlavor> {
+        self.sol_flavor
+    }
+}
+
+impl ExtrasBuilder {
+    pub fn trk_feature(&mut self, feature: impl Into<String>) -> &mut Self {
+        self.trk_features
+            .get_or_insert_with(Vec::new)
+            .push(feature.into());
+        self
+    }
+
+    pub fn trk_metadata_entry(
+        &mut self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> &mut Self {
+        self.trk_metadata
+            .get_or_insert_with(IndexMap::new)
+            .insert(key.into(), value.into());
+        self
+    }
+
+    pub fn json_property(&mut self, name: impl Into<String>, value: Value) -> &mut Self {
+        self.json_properties
+            .get_or_insert_with(Map::new)
+            .insert(name.into(), value);
+        self
+    }
+
+    pub fn sol_property(&mut self, name: impl Into<String>, value: Amf0Value) -> &mut Self {
+        self.sol_properties
+            .get_or_insert_with(IndexMap::new)
+            .insert(name.into(), value);
+        self
+    }
+}
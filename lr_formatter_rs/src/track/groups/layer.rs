@@ -1,3 +1,4 @@
 pub mod layer_base;
 pub mod layer_folder;
 pub mod layer_group;
+pub mod layer_tree;
@@ -15,3 +15,21 @@ pub struct Layer {
     #[builder(setter(strip_option), default)]
     folder_id: Option<Option<u32>>,
 }
+
+impl LayerBuilder {
+    pub(in crate::track) fn get_id(&self) -> Option<u32> {
+        self.id
+    }
+
+    pub(in crate::track) fn get_index(&self) -> Option<usize> {
+        self.index
+    }
+
+    pub(in crate::track) fn get_name(&self) -> Option<&str> {
+        self.name.as_ref()?.as_deref()
+    }
+
+    pub(in crate::track) fn get_folder_id(&self) -> Option<u32> {
+        self.folder_id.flatten().flatten()
+    }
+}
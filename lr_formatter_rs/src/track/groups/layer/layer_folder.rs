@@ -15,3 +15,13 @@ pub struct LayerFolder {
     #[builder(setter(strip_option), default)]
     size: Option<u32>,
 }
+
+impl LayerFolderBuilder {
+    pub(in crate::track) fn get_id(&self) -> Option<u32> {
+        self.id
+    }
+
+    pub(in crate::track) fn get_index(&self) -> Option<usize> {
+        self.index
+    }
+}
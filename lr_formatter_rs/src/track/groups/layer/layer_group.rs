@@ -8,9 +8,10 @@ use crate::track::{
     groups::layer::{
         layer_base::{Layer, LayerBuilder, LayerBuilderError},
         layer_folder::{LayerFolder, LayerFolderBuilder, LayerFolderBuilderError},
+        layer_tree::{LayerNode, LayerTreeError, TreeEntry, arrange},
     },
 };
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 define_group_builder!(
   enum LayerFeature {
@@ -75,6 +76,42 @@ impl GroupBuilder for LayerGroupBuilder {
     }
 }
 
+impl LayerGroup {
+    /// Layers and folders in the order the editor lists them, with the layers of each folder
+    pub fn tree(&self) -> Vec<LayerNode<'_>> {
+        let layer_folders = self.layer_folders.as_deref().unwrap_or_default();
+        let layers = self
+            .layers
+            .iter()
+            .map(|layer| (layer.id(), layer))
+            .collect::<HashMap<_, _>>();
+        let folders = layer_folders
+            .iter()
+            .map(|folder| (folder.id(), folder))
+            .collect::<HashMap<_, _>>();
+
+        let entries = arrange(
+            self.layers
+                .iter()
+                .map(|layer| (layer.index(), layer.id(), layer.folder_id().flatten())),
+            layer_folders
+                .iter()
+                .map(|folder| (folder.index(), folder.id())),
+        );
+
+        entries
+            .into_iter()
+            .map(|entry| match entry {
+                TreeEntry::Layer(id) => LayerNode::Layer(layers[&id]),
+                TreeEntry::Folder(id, folder_layers) => LayerNode::Folder(
+                    folders[&id],
+                    folder_layers.iter().map(|id| layers[id]).collect(),
+                ),
+            })
+            .collect()
+    }
+}
+
 impl LayerGroupBuilder {
     pub fn add_layer(
         &mut self,
@@ -91,6 +128,38 @@ impl LayerGroupBuilder {
         self.layers.iter_mut()
     }
 
+    /// Id of the first layer with this name, adding the layer after every other layer and folder
+    /// if there is none
+    pub fn layer_named(&mut self, name: &str) -> Result<u32, LayerGroupBuilderError> {
+        if let Some(id) = self
+            .layers
+            .iter()
+            .find(|layer| layer.get_name() == Some(name))
+            .and_then(LayerBuilder::get_id)
+        {
+            return Ok(id);
+        }
+
+        let folders = self.layer_folders.iter().flatten();
+        let next_id = self
+            .layers
+            .iter()
+            .filter_map(LayerBuilder::get_id)
+            .chain(folders.clone().filter_map(LayerFolderBuilder::get_id))
+            .max()
+            .map_or(0, |id| id + 1);
+        let next_index = self
+            .layers
+            .iter()
+            .filter_map(LayerBuilder::get_index)
+            .chain(folders.filter_map(LayerFolderBuilder::get_index))
+            .max()
+            .map_or(0, |index| index + 1);
+
+        self.add_layer(next_id, next_index)?.name(name.to_string());
+        Ok(next_id)
+    }
+
     pub fn add_layer_folder(
         &mut self,
         id: u32,
@@ -117,4 +186,257 @@ impl LayerGroupBuilder {
         );
         Ok(layer_folders.iter_mut())
     }
+
+    /// Move a layer or folder to a position among the layers and folders around it
+    ///
+    /// Positions past the end move it to the end.
+    pub fn move_layer(&mut self, id: u32, position: usize) -> Result<(), LayerTreeError> {
+        let mut entries = self.entries();
+
+        if let Some(from) = entries.iter().position(|entry| entry.id() == id) {
+            let entry = entries.remove(from);
+            entries.insert(position.min(entries.len()), entry);
+        } else {
+            let layers = entries
+                .iter_mut()
+                .find_map(|entry| match entry {
+                    TreeEntry::Folder(_, layers) if layers.contains(&id) => Some(layers),
+                    _ => None,
+                })
+                .ok_or(LayerTreeError::Missing(id))?;
+            layers.retain(|layer| *layer != id);
+            layers.insert(position.min(layers.len()), id);
+        }
+
+        self.set_entries(&entries);
+        Ok(())
+    }
+
+    /// Move a layer into a folder at a position among its layers, or after them if there is no
+    /// position
+    pub fn nest_layer(
+        &mut self,
+        id: u32,
+        folder_id: u32,
+        position: Option<usize>,
+    ) -> Result<(), LayerTreeError> {
+        let mut entries = self.entries();
+        if !entries
+            .iter()
+            .any(|entry| matches!(entry, TreeEntry::Folder(id, _) if *id == folder_id))
+        {
+            return Err(LayerTreeError::Missing(folder_id));
+        }
+        Self::detach(&mut entries, id)?;
+
+        for entry in &mut entries {
+            if let TreeEntry::Folder(entry_id, layers) = entry
+                && *entry_id == folder_id
+            {
+                let position = position.unwrap_or(layers.len()).min(layers.len());
+                layers.insert(position, id);
+            }
+        }
+
+        self.set_entries(&entries);
+        Ok(())
+    }
+
+    /// Move a layer out of its folder to just after the folder
+    pub fn un_nest_layer(&mut self, id: u32) -> Result<(), LayerTreeError> {
+        let mut entries = self.entries();
+
+        let folder_position = entries.iter().position(
+            |entry| matches!(entry, TreeEntry::Folder(_, layers) if layers.contains(&id)),
+        );
+        let Some(folder_position) = folder_position else {
+            if entries.contains(&TreeEntry::Layer(id)) {
+                return Ok(());
+            }
+            return Err(LayerTreeError::Missing(id));
+        };
+
+        Self::detach(&mut entries, id)?;
+        entries.insert(folder_position + 1, TreeEntry::Layer(id));
+
+        self.set_entries(&entries);
+        Ok(())
+    }
+
+    /// Rename a layer or folder
+    pub fn rename_layer(&mut self, id: u32, name: &str) -> Result<(), LayerTreeError> {
+        if let Some(layer) = self
+            .layers
+            .iter_mut()
+            .find(|layer| layer.get_id() == Some(id))
+        {
+            layer.name(name.to_string());
+        } else if let Some(folder) = self
+            .layer_folders
+            .iter_mut()
+            .flatten()
+            .find(|folder| folder.get_id() == Some(id))
+        {
+            folder.name(name.to_string());
+        } else {
+            return Err(LayerTreeError::Missing(id));
+        }
+
+        Ok(())
+    }
+
+    /// Remove a layer, or a folder along with its layers, returning the ids of the removed layers
+    ///
+    /// Lines are left as they are, see `TrackBuilder::delete_layer` to move or delete them too.
+    pub fn remove_layer(&mut self, id: u32) -> Result<Vec<u32>, LayerTreeError> {
+        let removed = self.layers_in(id).ok_or(LayerTreeError::Missing(id))?;
+        let mut entries = self.entries();
+        if entries
+            .iter()
+            .all(|entry| entry.layers().iter().all(|layer| removed.contains(layer)))
+        {
+            return Err(LayerTreeError::LastLayer);
+        }
+
+        match entries.iter().position(|entry| entry.id() == id) {
+            Some(position) => {
+                let entry = entries.remove(position);
+                if let TreeEntry::Folder(..) = entry
+                    && let Some(folders) = self.layer_folders.as_mut()
+                {
+                    folders.retain(|folder| folder.get_id() != Some(id));
+                }
+            }
+            None => Self::detach(&mut entries, id)?,
+        }
+        self.layers
+            .retain(|layer| layer.get_id().is_none_or(|id| !removed.contains(&id)));
+
+        self.set_entries(&entries);
+        Ok(removed)
+    }
+
+    /// Remove a folder, keeping its layers where they are
+    pub fn flatten_folder(&mut self, id: u32) -> Result<(), LayerTreeError> {
+        let mut entries = self.entries();
+        let position = entries
+            .iter()
+            .position(|entry| matches!(entry, TreeEntry::Folder(folder_id, _) if *folder_id == id))
+            .ok_or(LayerTreeError::Missing(id))?;
+
+        let layers = entries.remove(position).layers();
+        entries.splice(position..position, layers.into_iter().map(TreeEntry::Layer));
+        if let Some(folders) = self.layer_folders.as_mut() {
+            folders.retain(|folder| folder.get_id() != Some(id));
+        }
+
+        self.set_entries(&entries);
+        Ok(())
+    }
+
+    /// Remove every folder, keeping their layers where they are
+    pub fn flatten_folders(&mut self) -> &mut Self {
+        let entries = self
+            .entries()
+            .iter()
+            .flat_map(TreeEntry::layers)
+            .map(TreeEntry::Layer)
+            .collect::<Vec<_>>();
+        if let Some(folders) = self.layer_folders.as_mut() {
+            folders.clear();
+        }
+
+        self.set_entries(&entries);
+        self
+    }
+
+    pub(in crate::track) fn is_layer(&self, id: u32) -> bool {
+        self.layers.iter().any(|layer| layer.get_id() == Some(id))
+    }
+
+    /// Ids of the layers of a layer or folder
+    pub(in crate::track) fn layers_in(&self, id: u32) -> Option<Vec<u32>> {
+        self.entries().into_iter().find_map(|entry| match entry {
+            TreeEntry::Folder(folder_id, _) if folder_id == id => Some(entry.layers()),
+            TreeEntry::Folder(_, layers) if layers.contains(&id) => Some(vec![id]),
+            TreeEntry::Layer(layer_id) if layer_id == id => Some(vec![id]),
+            _ => None,
+        })
+    }
+
+    // Layers and folders missing their id or index fail to build anyway, so they are left out
+    fn entries(&self) -> Vec<TreeEntry> {
+        arrange(
+            self.layers.iter().filter_map(|layer| {
+                Some((layer.get_index()?, layer.get_id()?, layer.get_folder_id()))
+            }),
+            self.layer_folders
+                .iter()
+                .flatten()
+                .filter_map(|folder| Some((folder.get_index()?, folder.get_id()?))),
+        )
+    }
+
+    /// Take a layer out of the top level or the folder it is in
+    fn detach(entries: &mut Vec<TreeEntry>, id: u32) -> Result<(), LayerTreeError> {
+        for (position, entry) in entries.iter_mut().enumerate() {
+            match entry {
+                TreeEntry::Layer(layer_id) if *layer_id == id => {
+                    entries.remove(position);
+                    return Ok(());
+                }
+                TreeEntry::Folder(folder_id, _) if *folder_id == id => {
+                    return Err(LayerTreeError::NestedFolder(id));
+                }
+                TreeEntry::Folder(_, layers) if layers.contains(&id) => {
+                    layers.retain(|layer| *layer != id);
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        Err(LayerTreeError::Missing(id))
+    }
+
+    /// Number the layers and folders in tree order, and point layers at the folders they are in
+    fn set_entries(&mut self, entries: &[TreeEntry]) {
+        let mut layer_places = HashMap::new();
+        let mut folder_places = HashMap::new();
+        let mut index = 0;
+
+        for entry in entries {
+            match entry {
+                TreeEntry::Layer(id) => {
+                    layer_places.insert(*id, (index, None));
+                    index += 1;
+                }
+                TreeEntry::Folder(id, layers) => {
+                    let size = u32::try_from(layers.len()).unwrap_or(u32::MAX);
+                    folder_places.insert(*id, (index, size));
+                    index += 1;
+                    for layer in layers {
+                        layer_places.insert(*layer, (index, Some(*id)));
+                        index += 1;
+                    }
+                }
+            }
+        }
+
+        let has_folders = self.layer_folders.is_some();
+        for layer in &mut self.layers {
+            if let Some(&(index, folder_id)) = layer.get_id().and_then(|id| layer_places.get(&id)) {
+                layer.index(index);
+                if has_folders {
+                    layer.folder_id(folder_id);
+                }
+            }
+        }
+
+        for folder in self.layer_folders.iter_mut().flatten() {
+            if let Some(&(index, size)) = folder.get_id().and_then(|id| folder_places.get(&id)) {
+                folder.index(index).size(size);
+            }
+        }
+    }
 }
@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::track::groups::layer::{layer_base::Layer, layer_folder::LayerFolder};
+
+/// A layer or a folder along with the layers in it, in the order the editor lists them
+pub enum LayerNode<'a> {
+    Layer(&'a Layer),
+    Folder(&'a LayerFolder, Vec<&'a Layer>),
+}
+
+/// What happens to the lines of a deleted layer
+#[derive(Debug, Clone, Copy)]
+pub enum LayerLines {
+    /// Move the lines onto the layer with this id
+    MoveTo(u32),
+    Delete,
+}
+
+#[derive(Error, Debug)]
+pub enum LayerTreeError {
+    #[error("There is no layer or folder with id {0}")]
+    Missing(u32),
+    #[error("Folder {0} can not be put into another folder")]
+    NestedFolder(u32),
+    #[error("Lines can not be moved onto layer {0}, which is being deleted or is not a layer")]
+    InvalidLineTarget(u32),
+    #[error("A track needs at least one layer")]
+    LastLayer,
+}
+
+/// Entry of the layer tree by id
+#[derive(Debug, Clone, PartialEq)]
+pub(in crate::track) enum TreeEntry {
+    Layer(u32),
+    Folder(u32, Vec<u32>),
+}
+
+impl TreeEntry {
+    pub(in crate::track) fn id(&self) -> u32 {
+        match self {
+            TreeEntry::Layer(id) | TreeEntry::Folder(id, _) => *id,
+        }
+    }
+
+    /// Ids of the layers of this entry
+    pub(in crate::track) fn layers(&self) -> Vec<u32> {
+        match self {
+            TreeEntry::Layer(id) => vec![*id],
+            TreeEntry::Folder(_, layers) => layers.clone(),
+        }
+    }
+}
+
+/// Arrange layers and folders into a tree by their indices and folder ids
+///
+/// Layers are given as their index, id and folder id, and folders as their index and id. Layers
+/// pointing at folders that do not exist are put at the top level.
+pub(in crate::track) fn arrange(
+    layers: impl Iterator<Item = (usize, u32, Option<u32>)>,
+    folders: impl Iterator<Item = (usize, u32)>,
+) -> Vec<TreeEntry> {
+    let mut folders = folders
+        .map(|(index, id)| (id, (index, vec![])))
+        .collect::<HashMap<_, _>>();
+    let mut top = vec![];
+
+    let mut layers = layers.collect::<Vec<_>>();
+    layers.sort_by_key(|(index, ..)| *index);
+    for (index, id, folder_id) in layers {
+        match folder_id.and_then(|folder_id| folders.get_mut(&folder_id)) {
+            Some((_, folder_layers)) => folder_layers.push(id),
+            None => top.push((index, TreeEntry::Layer(id))),
+        }
+    }
+    top.extend(
+        folders
+            .into_iter()
+            .map(|(id, (index, layers))| (index, TreeEntry::Folder(id, layers))),
+    );
+    top.sort_by_key(|(index, entry)| (*index, entry.id()));
+
+    top.into_iter().map(|(_, entry)| entry).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LayerLines, LayerNode, LayerTreeError};
+    use crate::track::{GridVersion, TrackBuilder, Vec2, line::line_view::Line};
+
+    fn track_builder() -> TrackBuilder {
+        let mut track_builder = TrackBuilder::default();
+        track_builder.metadata().grid_version(GridVersion::V6_2);
+        let layer_group = track_builder.layer_group();
+        layer_group
+            .add_layer(0, 0)
+            .unwrap()
+            .name("Base".to_string());
+        layer_group.add_layer_folder(1, 1).unwrap().size(2);
+        layer_group.add_layer(2, 2).unwrap().folder_id(Some(1));
+        layer_group.add_layer(3, 3).unwrap().folder_id(Some(1));
+        layer_group.add_layer(4, 4).unwrap();
+
+        let point = Vec2::new(0.0, 0.0);
+        for (id, layer) in [(1, 2), (2, 3), (3, 4)] {
+            track_builder
+                .line_group()
+                .add_scenery_line(id, (point, point))
+                .layer(layer);
+        }
+        track_builder
+    }
+
+    /// Ids of the tree, with the ids of folders followed by the ids of their layers
+    fn tree_ids(track_builder: &mut TrackBuilder) -> Vec<Vec<u32>> {
+        let track = track_builder.build().unwrap();
+        let layer_group = track.layer_group().as_ref().unwrap();
+        layer_group
+            .tree()
+            .iter()
+            .map(|node| match node {
+                LayerNode::Layer(layer) => vec![layer.id()],
+                LayerNode::Folder(folder, layers) => [folder.id()]
+                    .into_iter()
+                    .chain(layers.iter().map(|layer| layer.id()))
+                    .collect(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn layers_are_rearranged_with_consistent_indices() {
+        let mut track_builder = track_builder();
+        assert_eq!(
+            tree_ids(&mut track_builder),
+            [vec![0], vec![1, 2, 3], vec![4]]
+        );
+
+        let layer_group = track_builder.layer_group();
+        layer_group.move_layer(4, 0).unwrap();
+        layer_group.nest_layer(0, 1, Some(0)).unwrap();
+        layer_group.un_nest_layer(3).unwrap();
+        layer_group.rename_layer(1, "Props").unwrap();
+        assert_eq!(
+            tree_ids(&mut track_builder),
+            [vec![4], vec![1, 0, 2], vec![3]]
+        );
+
+        let deleted = track_builder
+            .delete_layer(2, LayerLines::MoveTo(3))
+            .unwrap();
+        assert!(deleted.is_empty());
+        let track = track_builder.build().unwrap();
+        let layer_group = track.layer_group().as_ref().unwrap();
+        let layers = layer_group
+            .layers()
+            .iter()
+            .map(|layer| (layer.id(), layer.index(), layer.folder_id()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            layers,
+            [
+                (0, 2, Some(Some(1))),
+                (3, 3, Some(None)),
+                (4, 0, Some(None))
+            ]
+        );
+        let folder = &layer_group.layer_folders().as_ref().unwrap()[0];
+        assert_eq!((folder.index(), folder.size()), (1, Some(1)));
+        assert_eq!(folder.name().as_deref(), Some("Props"));
+        let line_layers = track
+            .line_group()
+            .iter()
+            .map(|line| line.layer())
+            .collect::<Vec<_>>();
+        assert_eq!(line_layers, [Some(3), Some(3), Some(4)]);
+
+        track_builder.layer_group().flatten_folders();
+        assert_eq!(tree_ids(&mut track_builder), [vec![4], vec![0], vec![3]]);
+        let deleted = track_builder.delete_layer(3, LayerLines::Delete).unwrap();
+        assert_eq!(deleted, [1, 2]);
+        let track = track_builder.build().unwrap();
+        let ids = track
+            .line_group()
+            .iter()
+            .map(|line| line.id())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, [3]);
+        assert!(
+            track
+                .line_group()
+                .iter()
+                .all(|line: Line| line.layer() == Some(4))
+        );
+    }
+
+    #[test]
+    fn invalid_layer_changes_are_rejected() {
+        let mut track_builder = track_builder();
+        let layer_group = track_builder.layer_group();
+
+        let Err(LayerTreeError::NestedFolder(1)) = layer_group.nest_layer(1, 1, None) else {
+            panic!("Folders should not nest");
+        };
+        let Err(LayerTreeError::Missing(5)) = layer_group.move_layer(5, 0) else {
+            panic!("Missing layers should be reported");
+        };
+        let mut without_layers = TrackBuilder::default();
+        without_layers.metadata().grid_version(GridVersion::V6_2);
+        let Err(LayerTreeError::Missing(0)) = without_layers.delete_layer(0, LayerLines::Delete)
+        else {
+            panic!("Tracks without layers should have nothing to delete");
+        };
+        assert!(without_layers.build().unwrap().layer_group().is_none());
+        let Err(LayerTreeError::InvalidLineTarget(3)) =
+            track_builder.delete_layer(1, LayerLines::MoveTo(3))
+        else {
+            panic!("Lines should not move onto deleted layers");
+        };
+
+        track_builder.delete_layer(1, LayerLines::Delete).unwrap();
+        track_builder
+            .delete_layer(4, LayerLines::MoveTo(0))
+            .unwrap();
+        let Err(LayerTreeError::LastLayer) = track_builder.delete_layer(0, LayerLines::Delete)
+        else {
+            panic!("The last layer should not be deleted");
+        };
+        assert_eq!(tree_ids(&mut track_builder), [vec![0]]);
+    }
+
+    #[test]
+    fn lines_without_a_layer_are_on_the_base_layer() {
+        let mut track_builder = track_builder();
+        let point = Vec2::new(0.0, 0.0);
+        track_builder
+            .line_group()
+            .add_scenery_line(4, (point, point));
+
+        track_builder
+            .delete_layer(0, LayerLines::MoveTo(4))
+            .unwrap();
+        let track = track_builder.build().unwrap();
+        assert_eq!(track.line_group().line(4).unwrap().layer(), Some(4));
+
+        let deleted = track_builder.delete_layer(4, LayerLines::Delete).unwrap();
+        assert_eq!(deleted, [3, 4]);
+    }
+}
@@ -1,4 +1,10 @@
 pub mod acceleration_line;
+pub mod line_adjacency;
+pub mod line_cleanup;
+pub mod line_extensions;
 pub mod line_group;
+pub mod line_sequence;
+pub mod line_view;
+pub(in crate::track) mod point_grid;
 pub mod scenery_line;
 pub mod standard_line;
@@ -13,6 +13,9 @@ pub struct AccelerationLine {
     right_extension: bool,
     #[builder(setter(strip_option), default)]
     multiplier: Option<f64>,
+    /// Id of the layer the line is drawn on
+    #[builder(setter(strip_option), default)]
+    layer: Option<u32>,
 }
 
 impl AccelerationLine {
@@ -32,3 +35,29 @@ impl AccelerationLine {
         self.endpoints.1.y()
     }
 }
+
+impl AccelerationLineBuilder {
+    pub(in crate::track) fn get_id(&self) -> Option<u32> {
+        self.id
+    }
+
+    pub(in crate::track) fn get_endpoints(&self) -> Option<(Vec2, Vec2)> {
+        self.endpoints
+    }
+
+    pub(in crate::track) fn get_flipped(&self) -> Option<bool> {
+        self.flipped
+    }
+
+    pub(in crate::track) fn get_extensions(&self) -> (Option<bool>, Option<bool>) {
+        (self.left_extension, self.right_extension)
+    }
+
+    pub(in crate::track) fn get_multiplier(&self) -> Option<f64> {
+        self.multiplier.flatten()
+    }
+
+    pub(in crate::track) fn get_layer(&self) -> Option<u32> {
+        self.layer.flatten()
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+
+use getset::CloneGetters;
+use thiserror::Error;
+
+use crate::track::{Vec2, line::point_grid::PointGrid};
+
+/// The lines connected to either endpoint of a line
+#[derive(CloneGetters, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[getset(get_clone = "pub")]
+pub struct LineLink {
+    /// Line whose second endpoint joins this line's first endpoint
+    previous: Option<u32>,
+    /// Line whose first endpoint joins this line's second endpoint
+    next: Option<u32>,
+}
+
+impl LineLink {
+    pub fn new(previous: Option<u32>, next: Option<u32>) -> Self {
+        Self { previous, next }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.previous.is_none() && self.next.is_none()
+    }
+}
+
+/// Connectivity between lines, keyed by line id
+///
+/// Linked ids are kept as given, even if they refer to lines that no longer exist, because
+/// flash tracks regularly contain links to deleted lines.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LineAdjacency {
+    links: BTreeMap<u32, LineLink>,
+}
+
+#[derive(Error, Debug)]
+pub enum LineAdjacencyError {
+    #[error("Line adjacency references missing line {0}")]
+    MissingLine(u32),
+}
+
+impl LineAdjacency {
+    /// Retrieve the link of a line, which is empty if the line is not connected
+    pub fn get(&self, id: u32) -> LineLink {
+        self.links.get(&id).copied().unwrap_or_default()
+    }
+
+    /// Set the link of a line, removing it if the link is empty
+    pub fn set(&mut self, id: u32, link: LineLink) -> &mut Self {
+        if link.is_empty() {
+            self.links.remove(&id);
+        } else {
+            self.links.insert(id, link);
+        }
+        self
+    }
+
+    pub fn clear(&mut self) -> &mut Self {
+        self.links.clear();
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.links.is_empty()
+    }
+
+    /// Iterate over connected lines in ascending id order
+    pub fn iter(&self) -> impl Iterator<Item = (u32, LineLink)> + '_ {
+        self.links.iter().map(|(id, link)| (*id, *link))
+    }
+
+    /// Compute adjacency from line geometry, connecting lines whose endpoints are within
+    /// `tolerance` of each other
+    ///
+    /// When several lines snap to the same endpoint, the closest one wins, with ties going to
+    /// the lowest id.
+    pub fn from_geometry(
+        lines: impl IntoIterator<Item = (u32, (Vec2, Vec2))>,
+        tolerance: f64,
+    ) -> Self {
+        let lines: Vec<(u32, (Vec2, Vec2))> = lines.into_iter().collect();
+
+        let mut starts = PointGrid::new(tolerance);
+        let mut ends = PointGrid::new(tolerance);
+        for (id, endpoints) in &lines {
+            starts.insert(endpoints.0, *id);
+            ends.insert(endpoints.1, *id);
+        }
+
+        let closest = |id: u32, point: Vec2, grid: &PointGrid<u32>| {
+            grid.near(point)
+                .filter(|(other_id, _)| **other_id != id)
+                .min_by(|(a_id, a), (b_id, b)| a.total_cmp(b).then(a_id.cmp(b_id)))
+                .map(|(other_id, _)| *other_id)
+        };
+
+        let mut adjacency = Self::default();
+        for (id, endpoints) in &lines {
+            let previous = closest(*id, endpoints.0, &ends);
+            let next = closest(*id, endpoints.1, &starts);
+            adjacency.set(*id, LineLink::new(previous, next));
+        }
+        adjacency
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LineAdjacency, LineLink};
+    use crate::track::Vec2;
+
+    #[test]
+    fn closest_endpoints_are_linked() {
+        let line = |x1, x2| (Vec2::new(x1, 0.0), Vec2::new(x2, 0.0));
+        let lines = [
+            (4, line(10.5, 20.0)),
+            (3, line(10.2, 20.0)),
+            (2, line(10.2, 20.0)),
+            (1, line(0.0, 10.0)),
+            (5, line(30.0, 40.0)),
+        ];
+        let adjacency = LineAdjacency::from_geometry(lines, 1.0);
+
+        assert_eq!(adjacency.get(1), LineLink::new(None, Some(2)));
+        assert_eq!(adjacency.get(4), LineLink::new(Some(1), None));
+        assert_eq!(adjacency.get(5), LineLink::default());
+    }
+
+    #[test]
+    fn zero_tolerance_links_equal_endpoints() {
+        let line = |x1, x2| (Vec2::new(x1, 0.0), Vec2::new(x2, 0.0));
+        let lines = [
+            (1, line(0.0, 10.0)),
+            (2, line(10.0, 20.0)),
+            (3, line(20.5, 30.0)),
+        ];
+        let adjacency = LineAdjacency::from_geometry(lines, 0.0);
+
+        assert_eq!(adjacency.get(1), LineLink::new(None, Some(2)));
+        assert_eq!(adjacency.get(2), LineLink::new(Some(1), None));
+        assert_eq!(adjacency.get(3), LineLink::default());
+    }
+}
@@ -0,0 +1,742 @@
+use std::collections::HashMap;
+
+use crate::track::{LineType, Vec2, line::point_grid::PointGrid};
+
+/// Angle in radians within which lines are checked for lying along the same straight line
+const ANGLE_TOLERANCE: f64 = 1e-3;
+
+/// Options for removing and merging redundant lines
+///
+/// Only scenery lines are changed unless `include_physics` is set.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CleanupOptions {
+    /// Remove lines no longer than `min_length`
+    pub remove_degenerate: bool,
+    pub min_length: f64,
+    /// Remove lines with the same type, properties and endpoints as a line drawn before them
+    pub remove_duplicates: bool,
+    /// Merge lines with the same type and properties that lie along the same straight line and
+    /// touch or overlap
+    pub merge_collinear: bool,
+    /// Simplify chains of connected scenery lines with the Douglas-Peucker algorithm, keeping
+    /// every point of a chain within this distance of the simplified chain
+    pub simplify_tolerance: Option<f64>,
+    /// Also remove and merge standard and acceleration lines, which can change how the track
+    /// rides, but never simplify them
+    pub include_physics: bool,
+    /// Distance within which points count as the same
+    pub tolerance: f64,
+}
+
+impl Default for CleanupOptions {
+    fn default() -> Self {
+        Self {
+            remove_degenerate: true,
+            min_length: 1e-6,
+            remove_duplicates: true,
+            merge_collinear: true,
+            simplify_tolerance: None,
+            include_physics: false,
+            tolerance: 1e-6,
+        }
+    }
+}
+
+/// What cleaning up lines changed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CleanupReport {
+    /// Ids of lines removed for being too short
+    pub degenerate: Vec<u32>,
+    /// Ids of lines removed as duplicates, with the id of the line they duplicated
+    pub duplicates: Vec<(u32, u32)>,
+    /// Ids of lines merged into a collinear line, with the id of that line
+    pub merged: Vec<(u32, u32)>,
+    /// Ids of lines removed by simplification, with the id of the line that now covers them
+    pub simplified: Vec<(u32, u32)>,
+    /// Ids of the remaining lines whose endpoints changed
+    pub reshaped: Vec<u32>,
+}
+
+impl CleanupReport {
+    pub fn is_empty(&self) -> bool {
+        self.removed_count() == 0 && self.reshaped.is_empty()
+    }
+
+    pub fn removed_count(&self) -> usize {
+        self.degenerate.len() + self.duplicates.len() + self.merged.len() + self.simplified.len()
+    }
+
+    /// The remaining line that took the place of a removed line, if any
+    pub fn replacement(&self, id: u32) -> Option<u32> {
+        self.replacements().get(&id).copied()
+    }
+
+    /// The remaining line that took the place of each removed line that has one
+    pub fn replacements(&self) -> HashMap<u32, u32> {
+        let direct = self
+            .duplicates
+            .iter()
+            .chain(&self.merged)
+            .chain(&self.simplified)
+            .copied()
+            .collect::<HashMap<_, _>>();
+
+        // Lines can be replaced by lines that were themselves replaced later
+        direct
+            .keys()
+            .map(|removed| {
+                let mut replacement = direct[removed];
+                for _ in 0..direct.len() {
+                    match direct.get(&replacement) {
+                        Some(next) => replacement = *next,
+                        None => break,
+                    }
+                }
+                (*removed, replacement)
+            })
+            .collect()
+    }
+}
+
+/// The properties of a line that cleaning up looks at
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CleanupLine {
+    pub id: u32,
+    pub line_type: LineType,
+    pub endpoints: (Vec2, Vec2),
+    pub flipped: bool,
+    pub extensions: (bool, bool),
+    pub multiplier: Option<f64>,
+    pub width: Option<f64>,
+    pub layer: Option<u32>,
+}
+
+impl CleanupLine {
+    /// Whether lines share everything but their geometry
+    fn same_kind(&self, other: &CleanupLine) -> bool {
+        self.line_type == other.line_type
+            && self.multiplier == other.multiplier
+            && self.width == other.width
+            && self.layer == other.layer
+    }
+
+    fn point(&self, end: usize) -> Vec2 {
+        if end == 0 {
+            self.endpoints.0
+        } else {
+            self.endpoints.1
+        }
+    }
+}
+
+/// A line left after cleaning up, along with the ids of the lines its endpoints came from
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CleanedLine {
+    pub line: CleanupLine,
+    pub start_id: u32,
+    pub end_id: u32,
+}
+
+/// Clean up lines given in drawing order, returning what is left of each line
+pub(crate) fn clean_up(
+    lines: &[CleanupLine],
+    options: &CleanupOptions,
+) -> (Vec<Option<CleanedLine>>, CleanupReport) {
+    let mut cleanup = Cleanup {
+        slots: lines
+            .iter()
+            .map(|line| {
+                Some(CleanedLine {
+                    line: *line,
+                    start_id: line.id,
+                    end_id: line.id,
+                })
+            })
+            .collect(),
+        options,
+        report: CleanupReport::default(),
+    };
+
+    if options.remove_degenerate {
+        cleanup.remove_degenerate();
+    }
+    if options.remove_duplicates {
+        cleanup.remove_duplicates();
+    }
+    if options.merge_collinear {
+        cleanup.merge_collinear();
+    }
+    if let Some(tolerance) = options.simplify_tolerance {
+        cleanup.simplify(tolerance);
+    }
+
+    let mut report = cleanup.report;
+    for (slot, original) in cleanup.slots.iter().zip(lines) {
+        if let Some(cleaned) = slot
+            && !same_points(cleaned.line.endpoints, original.endpoints)
+        {
+            report.reshaped.push(original.id);
+        }
+    }
+
+    (cleanup.slots, report)
+}
+
+struct Cleanup<'a> {
+    slots: Vec<Option<CleanedLine>>,
+    options: &'a CleanupOptions,
+    report: CleanupReport,
+}
+
+impl Cleanup<'_> {
+    /// Indices of the remaining lines that may be changed
+    fn eligible(&self) -> Vec<usize> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(index, slot)| {
+                let line = slot.as_ref()?.line;
+                (line.line_type == LineType::Scenery || self.options.include_physics)
+                    .then_some(index)
+            })
+            .collect()
+    }
+
+    fn line(&self, index: usize) -> CleanupLine {
+        self.slots[index].as_ref().unwrap().line
+    }
+
+    fn near(&self, a: Vec2, b: Vec2) -> bool {
+        distance(a, b) <= self.options.tolerance
+    }
+
+    fn remove_degenerate(&mut self) {
+        for index in self.eligible() {
+            let line = self.line(index);
+            if distance(line.endpoints.0, line.endpoints.1) <= self.options.min_length {
+                self.report.degenerate.push(line.id);
+                self.slots[index] = None;
+            }
+        }
+    }
+
+    fn remove_duplicates(&mut self) {
+        // Lines drawn backwards and flipped collide the same way, as do scenery lines drawn
+        // either way, so lines are compared with their endpoints in a set order
+        let canonical = |line: &CleanupLine| {
+            let (start, end) = line.endpoints;
+            if (start.x(), start.y()) <= (end.x(), end.y()) {
+                (start, end, line.flipped)
+            } else {
+                (end, start, !line.flipped)
+            }
+        };
+
+        let mut seen = PointGrid::new(self.options.tolerance);
+
+        for index in self.eligible() {
+            let line = self.line(index);
+            let (start, end, flipped) = canonical(&line);
+
+            let original = seen
+                .near(start)
+                .map(|(other_index, _)| *other_index)
+                .filter(|other_index| {
+                    let other = self.line(*other_index);
+                    let (_, other_end, other_flipped) = canonical(&other);
+                    line.same_kind(&other)
+                        && (line.line_type == LineType::Scenery || flipped == other_flipped)
+                        && self.near(end, other_end)
+                })
+                .min();
+
+            match original {
+                Some(other_index) => {
+                    self.report
+                        .duplicates
+                        .push((line.id, self.line(other_index).id));
+                    self.slots[index] = None;
+                }
+                None => seen.insert(start, index),
+            }
+        }
+    }
+
+    fn merge_collinear(&mut self) {
+        // Direction of each line, pointing right or straight down
+        let direction = |line: &CleanupLine| {
+            let (start, end) = line.endpoints;
+            let length = distance(start, end);
+            let (x, y) = (
+                (end.x() - start.x()) / length,
+                (end.y() - start.y()) / length,
+            );
+            if x > 0.0 || (x == 0.0 && y > 0.0) {
+                (x, y, true)
+            } else {
+                (-x, -y, false)
+            }
+        };
+
+        let mut by_angle = self
+            .eligible()
+            .into_iter()
+            .filter(|index| {
+                let (start, end) = self.line(*index).endpoints;
+                distance(start, end) > 0.0
+            })
+            .map(|index| {
+                let (x, y, _) = direction(&self.line(index));
+                (y.atan2(x), index)
+            })
+            .collect::<Vec<_>>();
+        by_angle.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        // Lines at about the same angle, then about the same distance from the origin, are
+        // checked for overlaps along their shared direction
+        for angle_group in split_runs(&by_angle, ANGLE_TOLERANCE) {
+            let (ux, uy, _) = direction(&self.line(angle_group[0].1));
+            let mut by_offset = angle_group
+                .iter()
+                .map(|(_, index)| {
+                    let start = self.line(*index).endpoints.0;
+                    (ux * start.y() - uy * start.x(), *index)
+                })
+                .collect::<Vec<_>>();
+            by_offset.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            for offset_group in split_runs(&by_offset, self.options.tolerance) {
+                let mut kinds: Vec<Vec<usize>> = vec![];
+                for (_, index) in offset_group {
+                    let line = self.line(*index);
+                    let forward = direction(&line).2;
+                    let kind = kinds.iter_mut().find(|kind| {
+                        let other = self.line(kind[0]);
+                        line.same_kind(&other)
+                            && (line.line_type == LineType::Scenery
+                                || (line.flipped == other.flipped
+                                    && forward == direction(&other).2))
+                    });
+                    match kind {
+                        Some(kind) => kind.push(*index),
+                        None => kinds.push(vec![*index]),
+                    }
+                }
+
+                for kind in kinds {
+                    self.merge_along(kind, (ux, uy));
+                }
+            }
+        }
+    }
+
+    /// Merge touching or overlapping lines of one kind that lie along a direction
+    fn merge_along(&mut self, mut indices: Vec<usize>, (ux, uy): (f64, f64)) {
+        let along = |point: Vec2| ux * point.x() + uy * point.y();
+        // Each line as its endpoint furthest back and furthest along the direction
+        let span = |line: &CleanupLine| {
+            let (start, end) = line.endpoints;
+            if along(start) <= along(end) {
+                ((start, 0), (end, 1))
+            } else {
+                ((end, 1), (start, 0))
+            }
+        };
+
+        indices.sort_by(|a, b| {
+            let a = along(span(&self.line(*a)).0.0);
+            let b = along(span(&self.line(*b)).0.0);
+            a.total_cmp(&b)
+        });
+
+        let mut run: Vec<usize> = vec![];
+        let mut back = (Vec2::new(0.0, 0.0), 0, 0);
+        let mut front = (Vec2::new(0.0, 0.0), 0, 0);
+
+        for index in indices {
+            let line = self.line(index);
+            let ((line_back, back_end), (line_front, front_end)) = span(&line);
+
+            let touches = !run.is_empty()
+                && along(line_back) <= along(front.0) + self.options.tolerance
+                && line_distance(line_back, (back.0, front.0)) <= self.options.tolerance
+                && line_distance(line_front, (back.0, front.0)) <= self.options.tolerance;
+
+            if !touches {
+                self.finish_merge(&run, back, front);
+                run.clear();
+                back = (line_back, index, back_end);
+                front = (line_front, index, front_end);
+            } else if along(line_front) > along(front.0) {
+                front = (line_front, index, front_end);
+            }
+            run.push(index);
+        }
+
+        self.finish_merge(&run, back, front);
+    }
+
+    /// Replace a run of merged lines with the line drawn first, stretched over all of them
+    fn finish_merge(
+        &mut self,
+        run: &[usize],
+        back: (Vec2, usize, usize),
+        front: (Vec2, usize, usize),
+    ) {
+        let Some(&survivor) = run.iter().min() else {
+            return;
+        };
+        if run.len() < 2 {
+            return;
+        }
+
+        // Endpoints keep the direction the surviving line was drawn in, which every physics
+        // line in the run shares
+        let survivor_line = self.line(survivor);
+        let ((start, start_index, start_end), (end, end_index, end_end)) =
+            if span_is_forward(&survivor_line, back.0, front.0) {
+                (back, front)
+            } else {
+                (front, back)
+            };
+
+        let start_slot = self.slots[start_index].unwrap();
+        let end_slot = self.slots[end_index].unwrap();
+        let extension = |slot: &CleanedLine, end: usize| {
+            if end == 0 {
+                slot.line.extensions.0
+            } else {
+                slot.line.extensions.1
+            }
+        };
+        let merged = CleanedLine {
+            line: CleanupLine {
+                endpoints: (start, end),
+                extensions: (
+                    extension(&start_slot, start_end),
+                    extension(&end_slot, end_end),
+                ),
+                ..survivor_line
+            },
+            start_id: if start_end == 0 {
+                start_slot.start_id
+            } else {
+                start_slot.end_id
+            },
+            end_id: if end_end == 0 {
+                end_slot.start_id
+            } else {
+                end_slot.end_id
+            },
+        };
+
+        for &index in run {
+            if index != survivor {
+                self.report
+                    .merged
+                    .push((self.line(index).id, survivor_line.id));
+                self.slots[index] = None;
+            }
+        }
+        self.slots[survivor] = Some(merged);
+    }
+
+    fn simplify(&mut self, tolerance: f64) {
+        for (chain, points) in self.scenery_chains() {
+            let kept = douglas_peucker(&points, tolerance);
+
+            for pair in kept.windows(2) {
+                let (from, to) = (pair[0], pair[1]);
+                let survivor = chain[from];
+                let survivor_id = self.line(survivor).id;
+
+                for &removed in &chain[from + 1..to] {
+                    self.report
+                        .simplified
+                        .push((self.line(removed).id, survivor_id));
+                    self.slots[removed] = None;
+                }
+
+                if let Some(slot) = self.slots[survivor].as_mut() {
+                    slot.line.endpoints = (points[from], points[to]);
+                }
+            }
+        }
+    }
+
+    /// Runs of scenery lines of one kind joined end to end, with the points along each run
+    ///
+    /// Runs stop at points where more than two lines meet. Loops end on the point they start at.
+    fn scenery_chains(&self) -> Vec<(Vec<usize>, Vec<Vec2>)> {
+        let lines = self
+            .eligible()
+            .into_iter()
+            .filter(|index| self.line(*index).line_type == LineType::Scenery)
+            .collect::<Vec<_>>();
+
+        let mut ends = PointGrid::new(self.options.tolerance);
+        for &index in &lines {
+            let line = self.line(index);
+            for end in 0..2 {
+                ends.insert(line.point(end), (index, end));
+            }
+        }
+
+        // The end of another line joined to a line end, if the run continues through it
+        let joined = |index: usize, end: usize| -> Option<(usize, usize)> {
+            let line = self.line(index);
+            let point = line.point(end);
+            let meeting = ends
+                .near(point)
+                .map(|(line_end, _)| *line_end)
+                .collect::<Vec<_>>();
+
+            match meeting[..] {
+                [a, b] => {
+                    let other = if a == (index, end) { b } else { a };
+                    (other.0 != index && line.same_kind(&self.line(other.0))).then_some(other)
+                }
+                _ => None,
+            }
+        };
+
+        let mut visited = vec![false; self.slots.len()];
+        let mut chains = vec![];
+
+        for &first in &lines {
+            if visited[first] {
+                continue;
+            }
+
+            // Walk back to the start of the run, or all the way around a loop
+            let (mut index, mut entry) = (first, 0);
+            for _ in 0..lines.len() {
+                match joined(index, entry) {
+                    Some((previous, previous_end)) if previous != first => {
+                        (index, entry) = (previous, 1 - previous_end);
+                    }
+                    _ => break,
+                }
+            }
+
+            let start = index;
+            let line = self.line(start);
+            let mut chain = vec![start];
+            let mut points = vec![line.point(entry), line.point(1 - entry)];
+            let mut exit = 1 - entry;
+            visited[start] = true;
+
+            while let Some((next, next_end)) = joined(index, exit) {
+                if next == start || visited[next] {
+                    break;
+                }
+                visited[next] = true;
+                chain.push(next);
+                points.push(self.line(next).point(1 - next_end));
+                (index, exit) = (next, 1 - next_end);
+            }
+
+            if chain.len() > 1 {
+                chains.push((chain, points));
+            }
+        }
+
+        chains
+    }
+}
+
+/// Whether a line was drawn from `back` toward `front`
+fn span_is_forward(line: &CleanupLine, back: Vec2, front: Vec2) -> bool {
+    let (start, end) = line.endpoints;
+    (end.x() - start.x()) * (front.x() - back.x()) + (end.y() - start.y()) * (front.y() - back.y())
+        >= 0.0
+}
+
+/// Split values sorted by their key into runs where each key is within `gap` of the last
+fn split_runs(sorted: &[(f64, usize)], gap: f64) -> Vec<&[(f64, usize)]> {
+    let mut runs = vec![];
+    let mut start = 0;
+
+    for index in 1..=sorted.len() {
+        if index == sorted.len() || sorted[index].0 - sorted[index - 1].0 > gap {
+            runs.push(&sorted[start..index]);
+            start = index;
+        }
+    }
+
+    runs
+}
+
+/// Indices of the points to keep, always including the first and last
+fn douglas_peucker(points: &[Vec2], tolerance: f64) -> Vec<usize> {
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+    let mut spans = vec![(0, points.len() - 1)];
+
+    while let Some((from, to)) = spans.pop() {
+        let furthest = (from + 1..to)
+            .map(|index| {
+                (
+                    segment_distance(points[index], (points[from], points[to])),
+                    index,
+                )
+            })
+            .max_by(|a, b| a.0.total_cmp(&b.0));
+
+        if let Some((distance, index)) = furthest
+            && distance > tolerance
+        {
+            keep[index] = true;
+            spans.push((from, index));
+            spans.push((index, to));
+        }
+    }
+
+    (0..points.len()).filter(|index| keep[*index]).collect()
+}
+
+fn distance(a: Vec2, b: Vec2) -> f64 {
+    (a.x() - b.x()).hypot(a.y() - b.y())
+}
+
+fn same_points(a: (Vec2, Vec2), b: (Vec2, Vec2)) -> bool {
+    (a.0.x(), a.0.y(), a.1.x(), a.1.y()) == (b.0.x(), b.0.y(), b.1.x(), b.1.y())
+}
+
+/// Distance from a point to the infinite line through two points
+fn line_distance(point: Vec2, (start, end): (Vec2, Vec2)) -> f64 {
+    let length = distance(start, end);
+    if length == 0.0 {
+        return distance(point, start);
+    }
+
+    ((end.x() - start.x()) * (start.y() - point.y())
+        - (start.x() - point.x()) * (end.y() - start.y()))
+    .abs()
+        / length
+}
+
+/// Distance from a point to the segment between two points
+fn segment_distance(point: Vec2, (start, end): (Vec2, Vec2)) -> f64 {
+    let (dx, dy) = (end.x() - start.x(), end.y() - start.y());
+    let length_squared = dx * dx + dy * dy;
+    if length_squared == 0.0 {
+        return distance(point, start);
+    }
+
+    let t = (((point.x() - start.x()) * dx + (point.y() - start.y()) * dy) / length_squared)
+        .clamp(0.0, 1.0);
+    distance(point, Vec2::new(start.x() + t * dx, start.y() + t * dy))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CleanupOptions;
+    use crate::track::{CameraZoomEvent, GridVersion, LineHitTrigger, TrackBuilder, Vec2};
+
+    #[test]
+    fn scenery_is_cleaned_up_and_physics_lines_are_left_alone() {
+        let point = Vec2::new;
+        let mut track_builder = TrackBuilder::default();
+        track_builder.metadata().grid_version(GridVersion::V6_2);
+        let line_group = track_builder.line_group();
+        line_group.add_scenery_line(1, (point(0.0, 0.0), point(10.0, 0.0)));
+        line_group.add_scenery_line(2, (point(10.0, 0.0), point(0.0, 0.0)));
+        line_group.add_scenery_line(3, (point(5.0, 0.0), point(20.0, 0.0)));
+        line_group.add_scenery_line(4, (point(30.0, 30.0), point(30.0, 30.0)));
+        line_group.add_standard_line(5, (point(0.0, 0.0), point(10.0, 0.0)), false, false, false);
+        line_group.add_standard_line(6, (point(0.0, 0.0), point(10.0, 0.0)), false, false, false);
+        line_group.add_scenery_line(7, (point(0.0, 50.0), point(10.0, 51.0)));
+        line_group.add_scenery_line(8, (point(10.0, 51.0), point(20.0, 50.0)));
+        line_group.add_scenery_line(9, (point(30.0, 50.5), point(20.0, 50.0)));
+
+        let options = CleanupOptions {
+            simplify_tolerance: Some(2.0),
+            ..Default::default()
+        };
+        let report = track_builder.clean_up_lines(&options);
+        assert_eq!(report.degenerate, [4]);
+        assert_eq!(report.duplicates, [(2, 1)]);
+        assert_eq!(report.merged, [(3, 1)]);
+        assert_eq!(report.simplified, [(8, 7), (9, 7)]);
+        assert_eq!(report.reshaped, [1, 7]);
+
+        let track = track_builder.build().unwrap();
+        let ids = track
+            .line_group()
+            .iter()
+            .map(|line| line.id())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, [1, 5, 6, 7]);
+        let (start, end) = track.line_group().line(7).unwrap().endpoints();
+        assert_eq!(
+            (start.x(), start.y(), end.x(), end.y()),
+            (0.0, 50.0, 30.0, 50.5)
+        );
+    }
+
+    #[test]
+    fn merged_physics_lines_keep_their_links_and_triggers() {
+        let point = Vec2::new;
+        let mut track_builder = TrackBuilder::default();
+        track_builder.metadata().grid_version(GridVersion::V6_2);
+        let line_group = track_builder.line_group();
+        line_group.add_standard_line(1, (point(0.0, 0.0), point(10.0, 0.0)), false, true, false);
+        line_group.add_standard_line(2, (point(10.0, 0.0), point(20.0, 0.0)), false, false, true);
+        line_group.add_standard_line(
+            3,
+            (point(20.0, 0.0), point(30.0, 10.0)),
+            false,
+            false,
+            false,
+        );
+        line_group.recompute_adjacency(1e-6);
+        track_builder
+            .legacy_camera_zoom_group()
+            .add_trigger()
+            .trigger(LineHitTrigger::new(2, 40))
+            .event(CameraZoomEvent::new(2.0));
+
+        let options = CleanupOptions {
+            include_physics: true,
+            ..Default::default()
+        };
+        let report = track_builder.clean_up_lines(&options);
+        assert_eq!(report.merged, [(2, 1)]);
+
+        let track = track_builder.build().unwrap();
+        let line_group = track.line_group();
+        let merged = line_group.line(1).unwrap();
+        assert_eq!(merged.endpoints().1.x(), 20.0);
+        assert!(merged.left_extension() && merged.right_extension());
+        assert_eq!(line_group.adjacency().get(1).next(), Some(3));
+        assert_eq!(line_group.adjacency().get(3).previous(), Some(1));
+
+        let triggers = track
+            .legacy_camera_zoom_group()
+            .as_ref()
+            .unwrap()
+            .triggers();
+        assert_eq!(triggers[0].trigger().id(), 1);
+    }
+
+    #[test]
+    fn zero_tolerance_only_removes_exact_duplicates() {
+        let point = Vec2::new;
+        let mut track_builder = TrackBuilder::default();
+        track_builder.metadata().grid_version(GridVersion::V6_2);
+        let line_group = track_builder.line_group();
+        line_group.add_scenery_line(1, (point(0.0, 0.0), point(10.0, 0.0)));
+        line_group.add_scenery_line(2, (point(0.0, 0.0), point(10.0, 0.0)));
+        line_group.add_scenery_line(3, (point(0.0, 1e-9), point(10.0, 0.0)));
+
+        let options = CleanupOptions {
+            merge_collinear: false,
+            tolerance: 0.0,
+            ..Default::default()
+        };
+        let report = track_builder.clean_up_lines(&options);
+        assert_eq!(report.duplicates, [(2, 1)]);
+    }
+}
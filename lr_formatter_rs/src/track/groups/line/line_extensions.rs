@@ -0,0 +1,130 @@
+use std::f64::consts::FRAC_PI_2;
+
+use crate::track::{Vec2, line::point_grid::PointGrid};
+
+/// Options for recomputing line extensions from geometry
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExtensionOptions {
+    /// Recompute every physics line, instead of only lines without any extension
+    pub overwrite: bool,
+    /// Maximum distance between two endpoints for them to count as connected
+    pub snap_distance: f64,
+    /// Largest angle in radians that a joint may bend toward the riding side and still be extended
+    pub max_angle: f64,
+}
+
+impl Default for ExtensionOptions {
+    fn default() -> Self {
+        Self {
+            overwrite: false,
+            snap_distance: 1e-6,
+            max_angle: FRAC_PI_2,
+        }
+    }
+}
+
+/// The geometry of a physics line, used to decide which of its endpoints need extending
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ExtensionLine {
+    pub endpoints: (Vec2, Vec2),
+    pub flipped: bool,
+}
+
+/// Compute the (left, right) extensions of each line
+///
+/// Two lines meeting at a joint that bends toward the riding side leave a wedge below the
+/// joint that neither of their collision zones cover, which riders can fall through. Endpoints
+/// at such joints get extended to cover the wedge, like the editors do when snapping lines.
+pub(crate) fn compute_extensions(
+    lines: &[ExtensionLine],
+    options: &ExtensionOptions,
+) -> Vec<(bool, bool)> {
+    // Each endpoint with the index of its line, the far endpoint, and whether it is the end
+    let mut endpoints = PointGrid::new(options.snap_distance);
+    for (index, line) in lines.iter().enumerate() {
+        let (start, end) = line.endpoints;
+        endpoints.insert(start, (index, end, false));
+        endpoints.insert(end, (index, start, true));
+    }
+
+    lines
+        .iter()
+        .enumerate()
+        .map(|(index, line)| {
+            let (start, end) = line.endpoints;
+            let direction = (end.x() - start.x(), end.y() - start.y());
+            let normal = if line.flipped {
+                (-direction.1, direction.0)
+            } else {
+                (direction.1, -direction.0)
+            };
+
+            // Direction leaving each endpoint away from the line
+            let left_outward = (-direction.0, -direction.1);
+            let right_outward = direction;
+
+            let extended = |point: Vec2, outward: (f64, f64)| {
+                endpoints
+                    .near(point)
+                    .any(|(&(other_index, far_point, is_end), _)| {
+                        // Lines snapping at both endpoints only count from their start
+                        if other_index == index
+                            || is_end && distance(far_point, point) <= options.snap_distance
+                        {
+                            return false;
+                        }
+
+                        let towards = (far_point.x() - point.x(), far_point.y() - point.y());
+                        let bend = dot(towards, normal);
+                        let angle =
+                            f64::atan2(cross(outward, towards).abs(), dot(outward, towards));
+                        bend > 0.0 && angle <= options.max_angle
+                    })
+            };
+
+            (extended(start, left_outward), extended(end, right_outward))
+        })
+        .collect()
+}
+
+fn distance(a: Vec2, b: Vec2) -> f64 {
+    (a.x() - b.x()).hypot(a.y() - b.y())
+}
+
+fn dot(a: (f64, f64), b: (f64, f64)) -> f64 {
+    a.0 * b.0 + a.1 * b.1
+}
+
+fn cross(a: (f64, f64), b: (f64, f64)) -> f64 {
+    a.0 * b.1 - a.1 * b.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExtensionLine, ExtensionOptions, compute_extensions};
+    use crate::track::Vec2;
+
+    fn line(x1: f64, y1: f64, x2: f64, y2: f64) -> ExtensionLine {
+        ExtensionLine {
+            endpoints: (Vec2::new(x1, y1), Vec2::new(x2, y2)),
+            flipped: false,
+        }
+    }
+
+    #[test]
+    fn extends_valleys_but_not_crests() {
+        let options = ExtensionOptions::default();
+
+        let valley = [line(0.0, 0.0, 10.0, 0.0), line(10.0, 0.0, 20.0, -5.0)];
+        assert_eq!(
+            compute_extensions(&valley, &options),
+            [(false, true), (true, false)]
+        );
+
+        let crest = [line(0.0, 0.0, 10.0, 0.0), line(10.0, 0.0, 20.0, 5.0)];
+        assert_eq!(
+            compute_extensions(&crest, &options),
+            [(false, false), (false, false)]
+        );
+    }
+}
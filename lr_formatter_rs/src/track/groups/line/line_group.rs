@@ -1,5 +1,5 @@
 use crate::track::{
-    Vec2,
+    LineType, Vec2,
     group_builder::{
         group_builder_base::{GroupBuilder, GroupBuilderBase},
         group_builder_error::{GroupBuilderError, IntoGroupResult},
@@ -9,11 +9,19 @@ use crate::track::{
         acceleration_line::{
             AccelerationLine, AccelerationLineBuilder, AccelerationLineBuilderError,
         },
+        line_adjacency::{LineAdjacency, LineAdjacencyError, LineLink},
+        line_cleanup::{CleanupLine, CleanupOptions, CleanupReport, clean_up},
+        line_extensions::{ExtensionLine, ExtensionOptions, compute_extensions},
+        line_sequence::{LineIdError, LineSequence},
+        line_view::Line,
         scenery_line::{SceneryLine, SceneryLineBuilder, SceneryLineBuilderError},
         standard_line::{StandardLine, StandardLineBuilder, StandardLineBuilderError},
     },
 };
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+
+/// Id of the layer that lines without a layer are drawn on, like the base layer of linerider.com
+const BASE_LAYER: u32 = 0;
 
 define_group_builder!(
   enum LineFeature {
@@ -25,9 +33,48 @@ define_group_builder!(
     standard_lines: Vec<StandardLine>, Vec<StandardLineBuilder>, StandardLineBuilderError,
     acceleration_lines: Vec<AccelerationLine>, Vec<AccelerationLineBuilder>, AccelerationLineBuilderError,
     scenery_lines: Vec<SceneryLine>, Vec<SceneryLineBuilder>, SceneryLineBuilderError,
+    adjacency: LineAdjacency, LineAdjacency, LineAdjacencyError,
+  }
+
+  private {
+    line_order: Vec<(LineType, usize)>,
+    line_positions: HashMap<u32, (LineType, usize)>,
+  }
+
+  private_builder {
+    line_order: LineSequence,
   }
 );
 
+impl LineGroup {
+    /// Iterate over the lines of every type, in the order they were added
+    pub fn iter(&self) -> impl Iterator<Item = Line<'_>> {
+        self.line_order
+            .iter()
+            .map(|(line_type, index)| self.line_at(*line_type, *index))
+    }
+
+    /// Find the line with an id, of any type
+    pub fn line(&self, id: u32) -> Option<Line<'_>> {
+        self.line_positions
+            .get(&id)
+            .map(|(line_type, index)| self.line_at(*line_type, *index))
+    }
+
+    fn line_at(&self, line_type: LineType, index: usize) -> Line<'_> {
+        match line_type {
+            LineType::Standard => Line::from(&self.standard_lines[index]),
+            LineType::Acceleration => Line::from(&self.acceleration_lines[index]),
+            LineType::Scenery => Line::from(&self.scenery_lines[index]),
+        }
+    }
+
+    /// Number of lines of every type
+    pub fn line_count(&self) -> usize {
+        self.standard_lines.len() + self.acceleration_lines.len() + self.scenery_lines.len()
+    }
+}
+
 impl GroupBuilder for LineGroupBuilder {
     fn build_group(&mut self) -> Result<Self::Output, GroupBuilderError<Self::SubError>> {
         let mut standard_lines: Vec<StandardLine> = vec![];
@@ -55,11 +102,44 @@ impl GroupBuilder for LineGroupBuilder {
             scenery_lines.push(scenery_line);
         }
 
+        let mut line_positions = HashMap::new();
+        let all_ids = standard_lines
+            .iter()
+            .enumerate()
+            .map(|(index, line)| (line.id(), (LineType::Standard, index)))
+            .chain(
+                acceleration_lines
+                    .iter()
+                    .enumerate()
+                    .map(|(index, line)| (line.id(), (LineType::Acceleration, index))),
+            )
+            .chain(
+                scenery_lines
+                    .iter()
+                    .enumerate()
+                    .map(|(index, line)| (line.id(), (LineType::Scenery, index))),
+            );
+
+        for (id, position) in all_ids {
+            if line_positions.insert(id, position).is_some() {
+                return Err(GroupBuilderError::DuplicateId(id));
+            }
+        }
+
+        for (id, _) in self.adjacency.iter() {
+            if !line_positions.contains_key(&id) {
+                return Err(LineAdjacencyError::MissingLine(id)).map_group_err();
+            }
+        }
+
         Ok(LineGroup {
             features: self.features.clone(),
             standard_lines,
             acceleration_lines,
             scenery_lines,
+            adjacency: self.adjacency.clone(),
+            line_order: self.line_order.order().to_vec(),
+            line_positions,
         })
     }
 }
@@ -73,6 +153,8 @@ impl LineGroupBuilder {
         left_extension: bool,
         right_extension: bool,
     ) -> &mut StandardLineBuilder {
+        self.line_order
+            .push(LineType::Standard, self.standard_lines.len(), id);
         self.standard_lines.push(
             StandardLineBuilder::default()
                 .id(id)
@@ -86,7 +168,20 @@ impl LineGroupBuilder {
         self.standard_lines.last_mut().unwrap()
     }
 
+    /// Add a standard line with the next free id
+    pub fn add_standard_line_with_next_id(
+        &mut self,
+        endpoints: (Vec2, Vec2),
+        flipped: bool,
+        left_extension: bool,
+        right_extension: bool,
+    ) -> Result<&mut StandardLineBuilder, LineIdError> {
+        let id = self.next_line_id()?;
+        Ok(self.add_standard_line(id, endpoints, flipped, left_extension, right_extension))
+    }
+
     pub fn get_standard_lines(&mut self) -> impl Iterator<Item = &mut StandardLineBuilder> {
+        self.line_order.invalidate_ids();
         self.standard_lines.iter_mut()
     }
 
@@ -98,6 +193,8 @@ impl LineGroupBuilder {
         left_extension: bool,
         right_extension: bool,
     ) -> &mut AccelerationLineBuilder {
+        self.line_order
+            .push(LineType::Acceleration, self.acceleration_lines.len(), id);
         self.acceleration_lines.push(
             AccelerationLineBuilder::default()
                 .id(id)
@@ -111,7 +208,20 @@ impl LineGroupBuilder {
         self.acceleration_lines.last_mut().unwrap()
     }
 
+    /// Add an acceleration line with the next free id
+    pub fn add_acceleration_line_with_next_id(
+        &mut self,
+        endpoints: (Vec2, Vec2),
+        flipped: bool,
+        left_extension: bool,
+        right_extension: bool,
+    ) -> Result<&mut AccelerationLineBuilder, LineIdError> {
+        let id = self.next_line_id()?;
+        Ok(self.add_acceleration_line(id, endpoints, flipped, left_extension, right_extension))
+    }
+
     pub fn get_acceleration_lines(&mut self) -> impl Iterator<Item = &mut AccelerationLineBuilder> {
+        self.line_order.invalidate_ids();
         self.acceleration_lines.iter_mut()
     }
 
@@ -120,6 +230,8 @@ impl LineGroupBuilder {
         id: u32,
         endpoints: (Vec2, Vec2),
     ) -> &mut SceneryLineBuilder {
+        self.line_order
+            .push(LineType::Scenery, self.scenery_lines.len(), id);
         self.scenery_lines.push(
             SceneryLineBuilder::default()
                 .id(id)
@@ -130,7 +242,444 @@ impl LineGroupBuilder {
         self.scenery_lines.last_mut().unwrap()
     }
 
+    /// Add a scenery line with the next free id
+    pub fn add_scenery_line_with_next_id(
+        &mut self,
+        endpoints: (Vec2, Vec2),
+    ) -> Result<&mut SceneryLineBuilder, LineIdError> {
+        let id = self.next_line_id()?;
+        Ok(self.add_scenery_line(id, endpoints))
+    }
+
     pub fn get_scenery_lines(&mut self) -> impl Iterator<Item = &mut SceneryLineBuilder> {
+        self.line_order.invalidate_ids();
         self.scenery_lines.iter_mut()
     }
+
+    /// The id after the highest line id so far, starting from 1
+    ///
+    /// Fails once a line uses the highest possible id, even if lower ids are free.
+    pub fn next_line_id(&mut self) -> Result<u32, LineIdError> {
+        let (standard_lines, acceleration_lines, scenery_lines) = (
+            &self.standard_lines,
+            &self.acceleration_lines,
+            &self.scenery_lines,
+        );
+
+        self.line_order.next_id(|| {
+            standard_lines
+                .iter()
+                .map(|line| line.get_id())
+                .chain(acceleration_lines.iter().map(|line| line.get_id()))
+                .chain(scenery_lines.iter().map(|line| line.get_id()))
+                .flatten()
+                .max()
+        })
+    }
+
+    /// Move lines onto layers, given the layer id of each line id
+    pub fn set_line_layers(&mut self, layers: &HashMap<u32, u32>) -> &mut Self {
+        for line in &mut self.standard_lines {
+            if let Some(layer) = line.get_id().and_then(|id| layers.get(&id)) {
+                line.layer(*layer);
+            }
+        }
+
+        for line in &mut self.acceleration_lines {
+            if let Some(layer) = line.get_id().and_then(|id| layers.get(&id)) {
+                line.layer(*layer);
+            }
+        }
+
+        for line in &mut self.scenery_lines {
+            if let Some(layer) = line.get_id().and_then(|id| layers.get(&id)) {
+                line.layer(*layer);
+            }
+        }
+
+        self
+    }
+
+    /// Move the lines on any of these layers onto another layer, where lines without a layer are
+    /// on the base layer
+    pub fn move_lines_to_layer(&mut self, layers: &HashSet<u32>, layer: u32) -> &mut Self {
+        let on_layers =
+            |line_layer: Option<u32>| layers.contains(&line_layer.unwrap_or(BASE_LAYER));
+
+        for line in &mut self.standard_lines {
+            if on_layers(line.get_layer()) {
+                line.layer(layer);
+            }
+        }
+
+        for line in &mut self.acceleration_lines {
+            if on_layers(line.get_layer()) {
+                line.layer(layer);
+            }
+        }
+
+        for line in &mut self.scenery_lines {
+            if on_layers(line.get_layer()) {
+                line.layer(layer);
+            }
+        }
+
+        self
+    }
+
+    /// Remove the lines on any of these layers, where lines without a layer are on the base
+    /// layer, returning the ids of the removed lines
+    pub fn remove_lines_on_layers(&mut self, layers: &HashSet<u32>) -> Vec<u32> {
+        let on_layers = |layer: Option<u32>| layers.contains(&layer.unwrap_or(BASE_LAYER));
+        let order = self.line_order.order().to_vec();
+        let (standard_lines, acceleration_lines, scenery_lines) = (
+            std::mem::take(&mut self.standard_lines),
+            std::mem::take(&mut self.acceleration_lines),
+            std::mem::take(&mut self.scenery_lines),
+        );
+        self.line_order = LineSequence::default();
+        let mut removed = vec![];
+
+        for (line_type, index) in order {
+            match line_type {
+                LineType::Standard => {
+                    let line = &standard_lines[index];
+                    let id = line.get_id().unwrap_or_default();
+                    if on_layers(line.get_layer()) {
+                        removed.push(id);
+                        continue;
+                    }
+                    self.line_order
+                        .push(line_type, self.standard_lines.len(), id);
+                    self.standard_lines.push(line.clone());
+                }
+                LineType::Acceleration => {
+                    let line = &acceleration_lines[index];
+                    let id = line.get_id().unwrap_or_default();
+                    if on_layers(line.get_layer()) {
+                        removed.push(id);
+                        continue;
+                    }
+                    self.line_order
+                        .push(line_type, self.acceleration_lines.len(), id);
+                    self.acceleration_lines.push(line.clone());
+                }
+                LineType::Scenery => {
+                    let line = &scenery_lines[index];
+                    let id = line.get_id().unwrap_or_default();
+                    if on_layers(line.get_layer()) {
+                        removed.push(id);
+                        continue;
+                    }
+                    self.line_order
+                        .push(line_type, self.scenery_lines.len(), id);
+                    self.scenery_lines.push(line.clone());
+                }
+            }
+        }
+
+        if !removed.is_empty() {
+            let removed = removed.iter().copied().collect::<HashSet<_>>();
+            let kept = |id: Option<u32>| id.filter(|id| !removed.contains(id));
+            let links = self.adjacency.iter().collect::<Vec<_>>();
+            self.adjacency.clear();
+            for (id, link) in links {
+                if !removed.contains(&id) {
+                    self.adjacency
+                        .set(id, LineLink::new(kept(link.previous()), kept(link.next())));
+                }
+            }
+        }
+
+        removed
+    }
+
+    /// Remove and merge redundant lines, keeping the order of the lines that are left
+    ///
+    /// Links to removed physics lines are moved onto the lines that replaced them.
+    pub fn clean_up(&mut self, options: &CleanupOptions) -> CleanupReport {
+        let order = self.line_order.order().to_vec();
+        // Lines missing required properties fail to build anyway, so they are left alone
+        let (positions, lines): (Vec<usize>, Vec<CleanupLine>) = order
+            .iter()
+            .enumerate()
+            .filter_map(|(position, (line_type, index))| {
+                let line = match line_type {
+                    LineType::Standard => {
+                        let line = &self.standard_lines[*index];
+                        let (left, right) = line.get_extensions();
+                        CleanupLine {
+                            id: line.get_id()?,
+                            line_type: *line_type,
+                            endpoints: line.get_endpoints()?,
+                            flipped: line.get_flipped()?,
+                            extensions: (left?, right?),
+                            multiplier: None,
+                            width: None,
+                            layer: line.get_layer(),
+                        }
+                    }
+                    LineType::Acceleration => {
+                        let line = &self.acceleration_lines[*index];
+                        let (left, right) = line.get_extensions();
+                        CleanupLine {
+                            id: line.get_id()?,
+                            line_type: *line_type,
+                            endpoints: line.get_endpoints()?,
+                            flipped: line.get_flipped()?,
+                            extensions: (left?, right?),
+                            multiplier: line.get_multiplier(),
+                            width: None,
+                            layer: line.get_layer(),
+                        }
+                    }
+                    LineType::Scenery => {
+                        let line = &self.scenery_lines[*index];
+                        CleanupLine {
+                            id: line.get_id()?,
+                            line_type: *line_type,
+                            endpoints: line.get_endpoints()?,
+                            flipped: false,
+                            extensions: (false, false),
+                            multiplier: None,
+                            width: line.get_width(),
+                            layer: line.get_layer(),
+                        }
+                    }
+                };
+                Some((position, line))
+            })
+            .unzip();
+
+        let (cleaned, report) = clean_up(&lines, options);
+        if report.is_empty() {
+            return report;
+        }
+
+        let mut results = vec![None; order.len()];
+        let mut removed = HashSet::new();
+        for ((position, line), cleaned) in positions.into_iter().zip(&lines).zip(&cleaned) {
+            if cleaned.is_none() {
+                removed.insert(line.id);
+            }
+            results[position] = Some(*cleaned);
+        }
+
+        let (standard_lines, acceleration_lines, scenery_lines) = (
+            std::mem::take(&mut self.standard_lines),
+            std::mem::take(&mut self.acceleration_lines),
+            std::mem::take(&mut self.scenery_lines),
+        );
+        self.line_order = LineSequence::default();
+
+        for ((line_type, index), result) in order.into_iter().zip(results) {
+            // Lines left alone have no result, and removed lines have an empty one
+            let cleaned = match result {
+                Some(None) => continue,
+                Some(Some(cleaned)) => Some(cleaned),
+                None => None,
+            };
+
+            match line_type {
+                LineType::Standard => {
+                    let mut line = standard_lines[index].clone();
+                    if let Some(cleaned) = cleaned {
+                        line.endpoints(cleaned.line.endpoints)
+                            .left_extension(cleaned.line.extensions.0)
+                            .right_extension(cleaned.line.extensions.1);
+                    }
+                    let id = line.get_id().unwrap_or_default();
+                    self.line_order
+                        .push(line_type, self.standard_lines.len(), id);
+                    self.standard_lines.push(line);
+                }
+                LineType::Acceleration => {
+                    let mut line = acceleration_lines[index].clone();
+                    if let Some(cleaned) = cleaned {
+                        line.endpoints(cleaned.line.endpoints)
+                            .left_extension(cleaned.line.extensions.0)
+                            .right_extension(cleaned.line.extensions.1);
+                    }
+                    let id = line.get_id().unwrap_or_default();
+                    self.line_order
+                        .push(line_type, self.acceleration_lines.len(), id);
+                    self.acceleration_lines.push(line);
+                }
+                LineType::Scenery => {
+                    let mut line = scenery_lines[index].clone();
+                    if let Some(cleaned) = cleaned {
+                        line.endpoints(cleaned.line.endpoints);
+                    }
+                    let id = line.get_id().unwrap_or_default();
+                    self.line_order
+                        .push(line_type, self.scenery_lines.len(), id);
+                    self.scenery_lines.push(line);
+                }
+            }
+        }
+
+        // Merged lines take the links of the lines their endpoints came from
+        let mut links = self.adjacency.iter().collect::<HashMap<_, _>>();
+        for (line, cleaned) in lines.iter().zip(&cleaned) {
+            if let Some(cleaned) = cleaned
+                && (cleaned.start_id, cleaned.end_id) != (line.id, line.id)
+            {
+                let previous = links
+                    .get(&cleaned.start_id)
+                    .and_then(|link| link.previous());
+                let next = links.get(&cleaned.end_id).and_then(|link| link.next());
+                links.insert(line.id, LineLink::new(previous, next));
+            }
+        }
+
+        let replacements = report.replacements();
+        let remap = |link_id: Option<u32>, id: u32| {
+            let link_id = link_id?;
+            let link_id = if removed.contains(&link_id) {
+                *replacements.get(&link_id)?
+            } else {
+                link_id
+            };
+            (link_id != id).then_some(link_id)
+        };
+
+        self.adjacency.clear();
+        for (id, link) in links {
+            if !removed.contains(&id) {
+                self.adjacency.set(
+                    id,
+                    LineLink::new(remap(link.previous(), id), remap(link.next(), id)),
+                );
+            }
+        }
+
+        report
+    }
+
+    pub fn adjacency(&mut self) -> &mut LineAdjacency {
+        &mut self.adjacency
+    }
+
+    /// Replace the adjacency of physics lines with connections computed from their endpoints
+    pub fn recompute_adjacency(&mut self, tolerance: f64) -> &mut LineAdjacency {
+        let physics_lines = self
+            .standard_lines
+            .iter()
+            .map(|line| (line.get_id(), line.get_endpoints()))
+            .chain(
+                self.acceleration_lines
+                    .iter()
+                    .map(|line| (line.get_id(), line.get_endpoints())),
+            )
+            .filter_map(|(id, endpoints)| Some((id?, endpoints?)));
+
+        self.adjacency = LineAdjacency::from_geometry(physics_lines, tolerance);
+        &mut self.adjacency
+    }
+
+    /// Recompute the extensions of physics lines from their connected endpoints
+    pub fn compute_extensions(&mut self, options: ExtensionOptions) -> &mut Self {
+        let mut lines = vec![];
+        let mut targets = vec![];
+
+        for (index, line) in self.standard_lines.iter().enumerate() {
+            if let Some(endpoints) = line.get_endpoints() {
+                let flipped = line.get_flipped().unwrap_or(false);
+                lines.push(ExtensionLine { endpoints, flipped });
+                targets.push((LineType::Standard, index, line.get_extensions()));
+            }
+        }
+
+        for (index, line) in self.acceleration_lines.iter().enumerate() {
+            if let Some(endpoints) = line.get_endpoints() {
+                let flipped = line.get_flipped().unwrap_or(false);
+                lines.push(ExtensionLine { endpoints, flipped });
+                targets.push((LineType::Acceleration, index, line.get_extensions()));
+            }
+        }
+
+        let extensions = compute_extensions(&lines, &options);
+
+        for ((line_type, index, current), (left, right)) in targets.into_iter().zip(extensions) {
+            let missing = !matches!(current, (Some(true), _) | (_, Some(true)));
+            if !options.overwrite && !missing {
+                continue;
+            }
+
+            match line_type {
+                LineType::Standard => {
+                    self.standard_lines[index]
+                        .left_extension(left)
+                        .right_extension(right);
+                }
+                LineType::Acceleration => {
+                    self.acceleration_lines[index]
+                        .left_extension(left)
+                        .right_extension(right);
+                }
+                LineType::Scenery => {}
+            }
+        }
+
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LineGroupBuilder;
+    use crate::track::{
+        GroupBuilderError, LineType, Vec2, group_builder::group_builder_base::GroupBuilder,
+        line::line_sequence::LineIdError,
+    };
+
+    #[test]
+    fn lines_without_ids_get_the_next_free_id() {
+        let endpoints = (Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0));
+        let mut line_group = LineGroupBuilder::default();
+        assert_eq!(line_group.next_line_id().unwrap(), 1);
+
+        line_group.add_standard_line(5, endpoints, false, false, false);
+        line_group.add_scenery_line_with_next_id(endpoints).unwrap();
+        assert_eq!(line_group.next_line_id().unwrap(), 7);
+
+        for line in line_group.get_scenery_lines() {
+            line.id(10);
+        }
+        line_group
+            .add_acceleration_line_with_next_id(endpoints, false, false, false)
+            .unwrap();
+
+        let lines = line_group.build_group().unwrap();
+        let ids = lines.iter().map(|line| line.id()).collect::<Vec<_>>();
+        assert_eq!(ids, [5, 10, 11]);
+        assert_eq!(lines.line(10).unwrap().line_type(), LineType::Scenery);
+        assert!(lines.line(6).is_none());
+    }
+
+    #[test]
+    fn duplicate_line_ids_fail_to_build() {
+        let endpoints = (Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0));
+        let mut line_group = LineGroupBuilder::default();
+        line_group.add_standard_line(3, endpoints, false, false, false);
+        line_group.add_scenery_line(3, endpoints);
+
+        let Err(GroupBuilderError::DuplicateId(3)) = line_group.build_group() else {
+            panic!("Expected line id 3 to be reported as a duplicate");
+        };
+    }
+
+    #[test]
+    fn next_line_id_fails_after_the_last_id() {
+        let endpoints = (Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0));
+        let mut line_group = LineGroupBuilder::default();
+        line_group.add_scenery_line(u32::MAX - 1, endpoints);
+        line_group.add_scenery_line_with_next_id(endpoints).unwrap();
+
+        let Err(LineIdError::Exhausted) = line_group.add_scenery_line_with_next_id(endpoints)
+        else {
+            panic!("Expected no free id after line id {}", u32::MAX);
+        };
+        assert_eq!(line_group.build_group().unwrap().line_count(), 2);
+    }
 }
@@ -0,0 +1,46 @@
+use thiserror::Error;
+
+use crate::track::LineType;
+
+/// The order lines were added to a line group builder in, along with the next free line id
+#[derive(Debug, Clone, Default)]
+pub struct LineSequence {
+    order: Vec<(LineType, usize)>,
+    // Cleared whenever line ids may have changed, and recomputed when next needed. Wider than
+    // line ids so that running out of ids can be told apart from using the last one.
+    next_id: Option<u64>,
+}
+
+#[derive(Error, Debug)]
+pub enum LineIdError {
+    #[error("There is no free line id after line id {}", u32::MAX)]
+    Exhausted,
+}
+
+impl LineSequence {
+    pub(in crate::track) fn push(&mut self, line_type: LineType, index: usize, id: u32) {
+        self.order.push((line_type, index));
+        if let Some(next_id) = self.next_id {
+            self.next_id = Some(next_id.max(u64::from(id) + 1));
+        }
+    }
+
+    pub(in crate::track) fn order(&self) -> &[(LineType, usize)] {
+        &self.order
+    }
+
+    /// The next free id, computed from the current ids if they may have changed
+    pub(in crate::track) fn next_id(
+        &mut self,
+        max_id: impl FnOnce() -> Option<u32>,
+    ) -> Result<u32, LineIdError> {
+        let next_id = *self
+            .next_id
+            .get_or_insert_with(|| max_id().map_or(1, |max_id| u64::from(max_id) + 1));
+        u32::try_from(next_id).map_err(|_| LineIdError::Exhausted)
+    }
+
+    pub(in crate::track) fn invalidate_ids(&mut self) {
+        self.next_id = None;
+    }
+}
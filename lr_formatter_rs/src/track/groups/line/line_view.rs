@@ -0,0 +1,189 @@
+use crate::track::{
+    LineType, Vec2,
+    groups::line::{
+        acceleration_line::AccelerationLine, scenery_line::SceneryLine, standard_line::StandardLine,
+    },
+};
+
+/// A line of any type, for reading the properties that all line types share
+#[derive(Clone, Copy)]
+pub enum Line<'a> {
+    Standard(&'a StandardLine),
+    Acceleration(&'a AccelerationLine),
+    Scenery(&'a SceneryLine),
+}
+
+impl Line<'_> {
+    pub fn id(&self) -> u32 {
+        match self {
+            Line::Standard(line) => line.id(),
+            Line::Acceleration(line) => line.id(),
+            Line::Scenery(line) => line.id(),
+        }
+    }
+
+    pub fn line_type(&self) -> LineType {
+        match self {
+            Line::Standard(_) => LineType::Standard,
+            Line::Acceleration(_) => LineType::Acceleration,
+            Line::Scenery(_) => LineType::Scenery,
+        }
+    }
+
+    pub fn endpoints(&self) -> (Vec2, Vec2) {
+        let (x1, y1, x2, y2) = match self {
+            Line::Standard(line) => (line.x1(), line.y1(), line.x2(), line.y2()),
+            Line::Acceleration(line) => (line.x1(), line.y1(), line.x2(), line.y2()),
+            Line::Scenery(line) => (line.x1(), line.y1(), line.x2(), line.y2()),
+        };
+
+        (Vec2::new(x1, y1), Vec2::new(x2, y2))
+    }
+
+    /// Whether the line collides from its other side, always false for scenery lines
+    pub fn flipped(&self) -> bool {
+        match self {
+            Line::Standard(line) => line.flipped(),
+            Line::Acceleration(line) => line.flipped(),
+            Line::Scenery(_) => false,
+        }
+    }
+
+    pub fn left_extension(&self) -> bool {
+        match self {
+            Line::Standard(line) => line.left_extension(),
+            Line::Acceleration(line) => line.left_extension(),
+            Line::Scenery(_) => false,
+        }
+    }
+
+    pub fn right_extension(&self) -> bool {
+        match self {
+            Line::Standard(line) => line.right_extension(),
+            Line::Acceleration(line) => line.right_extension(),
+            Line::Scenery(_) => false,
+        }
+    }
+
+    /// Multiplier of an acceleration line
+    pub fn multiplier(&self) -> Option<f64> {
+        match self {
+            Line::Acceleration(line) => line.multiplier(),
+            _ => None,
+        }
+    }
+
+    /// Width of a scenery line
+    pub fn width(&self) -> Option<f64> {
+        match self {
+            Line::Scenery(line) => line.width(),
+            _ => None,
+        }
+    }
+
+    /// Id of the layer the line is drawn on, where lines without one are on the layer with id 0
+    pub fn layer(&self) -> Option<u32> {
+        match self {
+            Line::Standard(line) => line.layer(),
+            Line::Acceleration(line) => line.layer(),
+            Line::Scenery(line) => line.layer(),
+        }
+    }
+
+    pub fn length(&self) -> f64 {
+        let (dx, dy) = self.direction();
+        dx.hypot(dy)
+    }
+
+    /// Angle of the line from its first to its second point, in radians
+    pub fn angle(&self) -> f64 {
+        let (dx, dy) = self.direction();
+        dy.atan2(dx)
+    }
+
+    /// Unit vector pointing out of the side riders collide with, which `flipped` swaps
+    ///
+    /// Zero length lines have a zero normal.
+    pub fn normal(&self) -> Vec2 {
+        let (dx, dy) = self.direction();
+        let length = dx.hypot(dy);
+        if length == 0.0 {
+            return Vec2::new(0.0, 0.0);
+        }
+
+        if self.flipped() {
+            Vec2::new(-dy / length, dx / length)
+        } else {
+            Vec2::new(dy / length, -dx / length)
+        }
+    }
+
+    fn direction(&self) -> (f64, f64) {
+        let (start, end) = self.endpoints();
+        (end.x() - start.x(), end.y() - start.y())
+    }
+}
+
+impl<'a> From<&'a StandardLine> for Line<'a> {
+    fn from(line: &'a StandardLine) -> Self {
+        Line::Standard(line)
+    }
+}
+
+impl<'a> From<&'a AccelerationLine> for Line<'a> {
+    fn from(line: &'a AccelerationLine) -> Self {
+        Line::Acceleration(line)
+    }
+}
+
+impl<'a> From<&'a SceneryLine> for Line<'a> {
+    fn from(line: &'a SceneryLine) -> Self {
+        Line::Scenery(line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::track::{GridVersion, LineType, TrackBuilder, Vec2};
+
+    #[test]
+    fn lines_of_every_type_share_one_view() {
+        let mut track_builder = TrackBuilder::default();
+        track_builder.metadata().grid_version(GridVersion::V6_2);
+        let line_group = track_builder.line_group();
+        line_group.add_standard_line(
+            1,
+            (Vec2::new(0.0, 0.0), Vec2::new(3.0, 4.0)),
+            false,
+            true,
+            false,
+        );
+        line_group.add_acceleration_line(
+            2,
+            (Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0)),
+            true,
+            false,
+            false,
+        );
+        line_group.add_scenery_line(3, (Vec2::new(0.0, 0.0), Vec2::new(0.0, 2.0)));
+        let track = track_builder.build().unwrap();
+        let lines = track.line_group();
+
+        assert_eq!(
+            lines.iter().map(|line| line.id()).collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+        assert_eq!(lines.line(3).unwrap().line_type(), LineType::Scenery);
+        assert!(lines.line(4).is_none());
+
+        let standard = lines.line(1).unwrap();
+        assert_eq!(standard.length(), 5.0);
+        assert!(standard.left_extension());
+
+        let acceleration = lines.line(2).unwrap();
+        assert_eq!(acceleration.angle(), 0.0);
+        // With y pointing down, unflipped lines are ridden from above
+        assert_eq!(standard.normal().y(), -0.6);
+        assert_eq!(acceleration.normal().y(), 1.0);
+    }
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use crate::track::Vec2;
+
+// Smallest cell width, since dividing by a tiny tolerance would push every point into the same
+// saturated cell
+const MIN_CELL_SIZE: f64 = 1e-6;
+
+/// Points bucketed into square cells at least as wide as a tolerance, so the points within the
+/// tolerance of another point can be found without comparing every pair
+pub(in crate::track) struct PointGrid<T> {
+    tolerance: f64,
+    cells: HashMap<(i64, i64), Vec<(Vec2, T)>>,
+}
+
+impl<T> PointGrid<T> {
+    pub(in crate::track) fn new(tolerance: f64) -> Self {
+        Self {
+            tolerance,
+            cells: HashMap::new(),
+        }
+    }
+
+    pub(in crate::track) fn insert(&mut self, point: Vec2, value: T) {
+        self.cells
+            .entry(self.cell(point))
+            .or_default()
+            .push((point, value));
+    }
+
+    /// Values whose points lie within the tolerance of a point, along with their distance to it
+    pub(in crate::track) fn near(&self, point: Vec2) -> impl Iterator<Item = (&T, f64)> {
+        self.nearby_cells(point)
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .filter_map(move |(other, value)| {
+                let distance = (other.x() - point.x()).hypot(other.y() - point.y());
+                (distance <= self.tolerance).then_some((value, distance))
+            })
+    }
+
+    fn cell(&self, point: Vec2) -> (i64, i64) {
+        let size = self.tolerance.max(MIN_CELL_SIZE);
+        (
+            (point.x() / size).floor() as i64,
+            (point.y() / size).floor() as i64,
+        )
+    }
+
+    /// Cells within the tolerance of a point
+    fn nearby_cells(&self, point: Vec2) -> impl Iterator<Item = (i64, i64)> {
+        let (x, y) = self.cell(point);
+        (-1..=1).flat_map(move |dx| {
+            (-1..=1).map(move |dy| (x.saturating_add(dx), y.saturating_add(dy)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PointGrid;
+    use crate::track::Vec2;
+
+    #[test]
+    fn zero_tolerance_only_finds_equal_points() {
+        let mut grid = PointGrid::new(0.0);
+        for index in 0..1000 {
+            grid.insert(Vec2::new(f64::from(index), 0.0), index);
+        }
+        grid.insert(Vec2::new(5.0, 1e-9), 1000);
+
+        let near: Vec<_> = grid.near(Vec2::new(5.0, 0.0)).collect();
+        assert_eq!(near, vec![(&5, 0.0)]);
+        // Equal points end up in one cell without sharing it with all others
+        assert_eq!(grid.cells.len(), 1000);
+    }
+}
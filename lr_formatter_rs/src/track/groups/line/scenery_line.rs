@@ -10,6 +10,9 @@ pub struct SceneryLine {
     endpoints: (Vec2, Vec2),
     #[builder(setter(strip_option), default)]
     width: Option<f64>,
+    /// Id of the layer the line is drawn on
+    #[builder(setter(strip_option), default)]
+    layer: Option<u32>,
 }
 
 impl SceneryLine {
@@ -29,3 +32,21 @@ impl SceneryLine {
         self.endpoints.1.y()
     }
 }
+
+impl SceneryLineBuilder {
+    pub(in crate::track) fn get_id(&self) -> Option<u32> {
+        self.id
+    }
+
+    pub(in crate::track) fn get_endpoints(&self) -> Option<(Vec2, Vec2)> {
+        self.endpoints
+    }
+
+    pub(in crate::track) fn get_width(&self) -> Option<f64> {
+        self.width.flatten()
+    }
+
+    pub(in crate::track) fn get_layer(&self) -> Option<u32> {
+        self.layer.flatten()
+    }
+}
@@ -11,6 +11,9 @@ pub struct StandardLine {
     flipped: bool,
     left_extension: bool,
     right_extension: bool,
+    /// Id of the layer the line is drawn on
+    #[builder(setter(strip_option), default)]
+    layer: Option<u32>,
 }
 
 impl StandardLine {
@@ -30,3 +33,25 @@ impl StandardLine {
         self.endpoints.1.y()
     }
 }
+
+impl StandardLineBuilder {
+    pub(in crate::track) fn get_id(&self) -> Option<u32> {
+        self.id
+    }
+
+    pub(in crate::track) fn get_endpoints(&self) -> Option<(Vec2, Vec2)> {
+        self.endpoints
+    }
+
+    pub(in crate::track) fn get_flipped(&self) -> Option<bool> {
+        self.flipped
+    }
+
+    pub(in crate::track) fn get_extensions(&self) -> (Option<bool>, Option<bool>) {
+        (self.left_extension, self.right_extension)
+    }
+
+    pub(in crate::track) fn get_layer(&self) -> Option<u32> {
+        self.layer.flatten()
+    }
+}
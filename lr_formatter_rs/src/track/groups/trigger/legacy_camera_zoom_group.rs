@@ -1,5 +1,5 @@
 use crate::track::{
-    CameraZoomEvent, GroupBuilderBase, LineHitTrigger,
+    CameraZoomEvent, FrameBoundsTrigger, GroupBuilderBase, LineHitTrigger,
     group_builder::{
         group_builder_base::GroupBuilder,
         group_builder_error::{GroupBuilderError, IntoGroupResult},
@@ -9,7 +9,7 @@ use crate::track::{
         TriggeredEvent, TriggeredEventBuilder, TriggeredEventBuilderError,
     },
 };
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 pub type LegacyCameraZoomTrigger = TriggeredEvent<CameraZoomEvent, LineHitTrigger>;
 pub type LegacyCameraZoomTriggerBuilder = TriggeredEventBuilder<CameraZoomEvent, LineHitTrigger>;
@@ -49,4 +49,136 @@ impl LegacyCameraZoomGroupBuilder {
     pub fn get_triggers(&mut self) -> impl Iterator<Item = &mut LegacyCameraZoomTriggerBuilder> {
         self.triggers.iter_mut()
     }
+
+    /// Remove the triggers the rider sets off, returning the zooms they cause as frame based
+    /// triggers
+    ///
+    /// `frames` holds the ids of the lines the rider touches on each frame, starting from frame 0,
+    /// and may be computed while it is iterated. A trigger goes off on each frame its line starts
+    /// being touched and zooms over its frame length, until the next trigger goes off. When
+    /// triggers go off on the same frame, the last of them wins. Triggers that never go off are
+    /// kept.
+    pub fn remove_fired_triggers<F>(
+        &mut self,
+        frames: impl IntoIterator<Item = F>,
+    ) -> Vec<(CameraZoomEvent, FrameBoundsTrigger)>
+    where
+        F: IntoIterator<Item = u32>,
+    {
+        let mut line_triggers = HashMap::<u32, Vec<usize>>::new();
+        for (index, trigger) in self.triggers.iter().enumerate() {
+            if let (Some(line_hit), Some(_)) = (trigger.get_trigger(), trigger.get_event()) {
+                line_triggers.entry(line_hit.id()).or_default().push(index);
+            }
+        }
+
+        let mut fired = HashSet::new();
+        // The trigger that wins on each frame a trigger goes off
+        let mut firings = vec![];
+        let mut touched = HashSet::new();
+        for (frame, line_ids) in frames.into_iter().enumerate() {
+            let Ok(frame) = u32::try_from(frame) else {
+                break;
+            };
+            let previously_touched = std::mem::take(&mut touched);
+            touched.extend(line_ids);
+
+            let winner = touched
+                .iter()
+                .filter(|id| !previously_touched.contains(*id))
+                .filter_map(|id| line_triggers.get(id))
+                .flatten()
+                .inspect(|index| {
+                    fired.insert(**index);
+                })
+                .max()
+                .copied();
+            if let Some(index) = winner {
+                firings.push((frame, index));
+            }
+        }
+
+        let next_starts = firings
+            .iter()
+            .skip(1)
+            .map(|(frame, _)| Some(*frame))
+            .chain([None]);
+        let zooms = firings
+            .iter()
+            .zip(next_starts)
+            .filter_map(|((start, index), next_start)| {
+                let trigger = &self.triggers[*index];
+                let frame_length = trigger.get_trigger()?.frame_length();
+                let end = start.saturating_add(frame_length);
+                let end = next_start.map_or(end, |next_start| end.min(next_start));
+                Some((*trigger.get_event()?, FrameBoundsTrigger::new(*start, end)))
+            })
+            .collect();
+
+        let mut index = 0;
+        self.triggers.retain(|_| {
+            index += 1;
+            !fired.contains(&(index - 1))
+        });
+
+        zooms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::track::{CameraZoomEvent, GridVersion, LineHitTrigger, TrackBuilder};
+
+    #[test]
+    fn legacy_triggers_become_frame_based_zooms() {
+        let mut track_builder = TrackBuilder::default();
+        track_builder.metadata().grid_version(GridVersion::V6_2);
+        let legacy_camera_zoom_group = track_builder.legacy_camera_zoom_group();
+        for (id, zoom, frame_length) in [(1, 2.0, 40), (2, 0.5, 10), (3, 3.0, 5)] {
+            legacy_camera_zoom_group
+                .add_trigger()
+                .trigger(LineHitTrigger::new(id, frame_length))
+                .event(CameraZoomEvent::new(zoom));
+        }
+
+        let mut frames = vec![vec![]; 50];
+        frames[1] = vec![1];
+        // Staying on a line does not set its trigger off again
+        frames[2] = vec![1];
+        frames[20] = vec![2, 4];
+        frames[40] = vec![1];
+        assert_eq!(track_builder.convert_legacy_zoom_triggers(frames), [3]);
+
+        let track = track_builder.build().unwrap();
+        let zooms = track
+            .camera_zoom_group()
+            .as_ref()
+            .unwrap()
+            .triggers()
+            .iter()
+            .map(|trigger| {
+                let frame_bounds = trigger.trigger();
+                (
+                    trigger.event().zoom(),
+                    frame_bounds.start(),
+                    frame_bounds.end(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(zooms, [(2.0, 1, 20), (0.5, 20, 30), (2.0, 40, 80)]);
+        let legacy_triggers = track
+            .legacy_camera_zoom_group()
+            .as_ref()
+            .unwrap()
+            .triggers();
+        assert_eq!(legacy_triggers.len(), 1);
+
+        assert!(track_builder.convert_legacy_zoom_triggers([[3]]).is_empty());
+        let track = track_builder.build().unwrap();
+        assert!(track.legacy_camera_zoom_group().is_none());
+        assert_eq!(
+            track.camera_zoom_group().as_ref().unwrap().triggers().len(),
+            4
+        );
+    }
 }
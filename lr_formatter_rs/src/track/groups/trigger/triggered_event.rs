@@ -9,3 +9,13 @@ pub struct TriggeredEvent<E: Event, T: Trigger> {
     event: E,
     trigger: T,
 }
+
+impl<E: Event, T: Trigger> TriggeredEventBuilder<E, T> {
+    pub(in crate::track) fn get_event(&self) -> Option<&E> {
+        self.event.as_ref()
+    }
+
+    pub(in crate::track) fn get_trigger(&self) -> Option<&T> {
+        self.trigger.as_ref()
+    }
+}
@@ -1,6 +1,6 @@
 use getset::CloneGetters;
 
-#[derive(Debug, Clone, Copy, CloneGetters)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, CloneGetters)]
 #[getset(get_clone = "pub")]
 pub struct RGBColor {
     red: u8,
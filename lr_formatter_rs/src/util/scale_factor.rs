@@ -17,3 +17,11 @@ pub fn to_lrweb_gravity(gravity: Vec2) -> Vec2 {
 pub fn from_lra_scenery_width(width: u8) -> f64 {
     f64::from(width) / 10.0
 }
+
+pub fn to_lra_zoom(zoom: f64) -> f32 {
+    f64::powf(2.0, zoom) as f32
+}
+
+pub fn to_lra_scenery_width(width: f64) -> u8 {
+    (width * 10.0).round().clamp(0.0, f64::from(u8::MAX)) as u8
+}
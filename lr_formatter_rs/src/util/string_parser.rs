@@ -3,6 +3,8 @@ use std::io::{self, Read};
 use std::string::FromUtf8Error;
 use thiserror::Error;
 
+use crate::formats::{Budget, LimitExceeded};
+
 #[derive(Error, Debug)]
 pub enum ParseLengthPrefixedStringError {
     #[error("IO error while reading string: {0}")]
@@ -14,6 +16,9 @@ pub enum ParseLengthPrefixedStringError {
         #[source]
         source: FromUtf8Error,
     },
+
+    #[error("{0}")]
+    LimitExceeded(#[from] LimitExceeded),
 }
 
 pub enum StringLength {
@@ -25,12 +30,21 @@ pub enum StringLength {
 pub(crate) fn parse_string<B: ByteOrder>(
     cursor: &mut io::Cursor<Vec<u8>>,
     length_type: StringLength,
+    budget: &mut Budget,
 ) -> Result<String, ParseLengthPrefixedStringError> {
     let length = match length_type {
         StringLength::U16 => usize::from(cursor.read_u16::<B>()?),
         StringLength::Fixed(size) => size,
     };
 
+    // Do not allocate for a string longer than the data that is left
+    let remaining = (cursor.get_ref().len() as u64).saturating_sub(cursor.position());
+    if length as u64 > remaining {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+
+    budget.string(length)?;
+
     let mut buffer = vec![0; length];
     cursor.read_exact(&mut buffer)?;
     let string = String::from_utf8(buffer)